@@ -0,0 +1,96 @@
+//! age-encrypted secret key files.
+//!
+//! `sign --secret-file` reads a bare hex secret key, as written by `generate-keys --secret-out`,
+//! and decrypts it first when it is an age file (binary or ASCII-armored). Decryption uses the
+//! identity file named by `ageIdentity` in the config file, falling back to `~/.ssh/id_ed25519`.
+//! The identity may be an age identity file or an unencrypted OpenSSH private key.
+//!
+//! `key export --age-recipient` writes a key pair file's secret the same way, armored and
+//! encrypted to `age1…` or `ssh-…` recipients.
+
+use std::fs;
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use age::armor::{ArmoredReader, ArmoredWriter, Format};
+use age::{Decryptor, Encryptor, Identity, IdentityFile, Recipient};
+
+use crate::config::Config;
+
+const BINARY_HEADER: &[u8] = b"age-encryption.org/v1\n";
+const ARMOR_HEADER: &[u8] = b"-----BEGIN AGE ENCRYPTED FILE-----";
+
+/// Whether `contents` is an age file.
+pub fn is_age(contents: &[u8]) -> bool {
+    contents.starts_with(BINARY_HEADER) || contents.starts_with(ARMOR_HEADER)
+}
+
+fn identity_path(config: &Config) -> PathBuf {
+    if let Some(path) = &config.age_identity {
+        return path.clone();
+    }
+    std::env::var_os("HOME")
+        .map(|home| Path::new(&home).join(".ssh/id_ed25519"))
+        .filter(|path| path.exists())
+        .expect("Set ageIdentity in the config file to decrypt age files")
+}
+
+fn identities(path: &Path) -> Vec<Box<dyn Identity>> {
+    let contents = fs::read(path).expect("Failed to read age identity file");
+    if contents.starts_with(b"-----BEGIN") {
+        let identity = age::ssh::Identity::from_buffer(
+            contents.as_slice(),
+            Some(path.display().to_string()),
+        )
+        .expect("Age identity file is not a supported SSH private key");
+        return vec![Box::new(identity)];
+    }
+    IdentityFile::from_buffer(contents.as_slice())
+        .and_then(|file| file.into_identities().map_err(io::Error::other))
+        .expect("Age identity file is not valid")
+}
+
+/// Decrypts the age file `contents` with the configured identity.
+pub fn decrypt(contents: &[u8], config: &Config) -> Vec<u8> {
+    let identities = identities(&identity_path(config));
+    let decryptor = Decryptor::new_buffered(ArmoredReader::new(BufReader::new(contents)))
+        .expect("Age file is not valid");
+    let mut reader = decryptor
+        .decrypt(identities.iter().map(|identity| identity.as_ref()))
+        .expect("Age file is not encrypted to the configured identity");
+    let mut plaintext = Vec::new();
+    reader
+        .read_to_end(&mut plaintext)
+        .expect("Age file is corrupted");
+    plaintext
+}
+
+fn recipient(recipient: &str) -> Box<dyn Recipient> {
+    if recipient.starts_with("ssh-") {
+        let parsed: age::ssh::Recipient = recipient
+            .parse()
+            .unwrap_or_else(|_| panic!("Unsupported SSH recipient {}", recipient));
+        return Box::new(parsed);
+    }
+    let parsed: age::x25519::Recipient = recipient
+        .parse()
+        .unwrap_or_else(|_| panic!("Invalid age recipient {}", recipient));
+    Box::new(parsed)
+}
+
+/// Encrypts `plaintext` to every one of `recipients`, as an ASCII-armored age file.
+pub fn encrypt(plaintext: &[u8], recipients: &[String]) -> String {
+    let recipients: Vec<Box<dyn Recipient>> = recipients.iter().map(|r| recipient(r)).collect();
+    let encryptor = Encryptor::with_recipients(recipients.iter().map(|r| r.as_ref()))
+        .expect("Recipients cannot be mixed in one age file");
+    let mut armored = Vec::new();
+    let mut writer = ArmoredWriter::wrap_output(&mut armored, Format::AsciiArmor)
+        .and_then(|output| encryptor.wrap_output(output))
+        .expect("Failed to start age encryption");
+    writer
+        .write_all(plaintext)
+        .and_then(|_| writer.finish())
+        .and_then(|output| output.finish())
+        .expect("Age encryption failed");
+    String::from_utf8(armored).expect("Armored age files are ASCII")
+}
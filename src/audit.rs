@@ -0,0 +1,171 @@
+//! Hash-chained audit log of signing operations.
+//!
+//! The log is a JSON-lines file. Every entry records which key signed what (as a digest, so
+//! the log never holds message contents), who asked for it and when, together with the hash of
+//! the previous entry. Each entry's own hash covers all of its other fields, so editing,
+//! reordering or deleting an entry breaks the chain from that point on.
+//!
+//! Dropping entries from the end leaves a shorter chain that is still valid, so the log alone
+//! cannot show it was truncated. `audit verify` reports the hash of the last entry (`head`);
+//! kept somewhere the log's writer cannot change, it is passed back with `--anchor` and the log
+//! must then still contain that entry, at the position it was reported with.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+
+/// `prevHash` of the first entry in a log.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Bytes read at a time from the end of the log when looking for its last entry.
+const TAIL_CHUNK: u64 = 4096;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AuditRecord {
+    seq: u64,
+    timestamp: u64,
+    public_key: String,
+    message_digest: String,
+    requester: String,
+    prev_hash: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AuditEntry {
+    #[serde(flatten)]
+    record: AuditRecord,
+    hash: String,
+}
+
+impl AuditRecord {
+    fn hash(&self) -> String {
+        let canonical = serde_json::to_vec(self).expect("Failed to serialize audit record");
+        hex::encode(Keccak256::digest(canonical))
+    }
+}
+
+fn read_entries(path: &Path) -> Vec<AuditEntry> {
+    if !path.exists() {
+        return Vec::new();
+    }
+    fs::read_to_string(path)
+        .expect("Failed to read audit log")
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).expect("Malformed audit log entry"))
+        .collect()
+}
+
+/// The last entry of the log open in `file`, read backwards from its end so that appending costs
+/// the same however long the log has grown.
+fn last_entry(file: &mut File) -> Option<AuditEntry> {
+    let mut end = file.seek(SeekFrom::End(0)).expect("Failed to read audit log");
+    let mut tail = Vec::new();
+    while end > 0 {
+        let start = end.saturating_sub(TAIL_CHUNK);
+        let mut chunk = vec![0u8; (end - start) as usize];
+        file.seek(SeekFrom::Start(start))
+            .and_then(|_| file.read_exact(&mut chunk))
+            .expect("Failed to read audit log");
+        chunk.extend_from_slice(&tail);
+        tail = chunk;
+        end = start;
+        // the last line is whole once a newline precedes it
+        if tail.trim_ascii_end().contains(&b'\n') {
+            break;
+        }
+    }
+    let tail = tail.trim_ascii_end();
+    let line = match tail.iter().rposition(|&byte| byte == b'\n') {
+        Some(newline) => &tail[newline + 1..],
+        None => tail,
+    };
+    let line = line.trim_ascii();
+    (!line.is_empty())
+        .then(|| serde_json::from_slice(line).expect("Malformed audit log entry"))
+}
+
+fn requester() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Appends an entry for a signature made by `public_key` over `message`, holding a lock on the
+/// log from reading its last entry to writing the new one so concurrent signers stay chained.
+pub fn record(path: &Path, public_key: &[u8], message: &[u8]) {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)
+        .expect("Failed to open audit log");
+    file.lock().expect("Failed to lock audit log");
+    let (seq, prev_hash) = match last_entry(&mut file) {
+        Some(last) => (last.record.seq + 1, last.hash.clone()),
+        None => (0, GENESIS_HASH.to_string()),
+    };
+    let record = AuditRecord {
+        seq,
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("System clock is before the Unix epoch")
+            .as_secs(),
+        public_key: hex::encode(public_key),
+        message_digest: hex::encode(Keccak256::digest(message)),
+        requester: requester(),
+        prev_hash,
+    };
+    let entry = AuditEntry {
+        hash: record.hash(),
+        record,
+    };
+    let line = serde_json::to_string(&entry).expect("Failed to serialize audit entry");
+    writeln!(file, "{}", line).expect("Failed to write audit log");
+}
+
+/// Walks the chain and reports the first entry whose link or hash does not check out, or, with
+/// an `anchor`, whether the entry with that hash is missing from it.
+pub fn verify(path: &Path, anchor: Option<&str>) -> Value {
+    let entries = read_entries(path);
+    let mut prev_hash = GENESIS_HASH.to_string();
+    for (index, entry) in entries.iter().enumerate() {
+        let reason = if entry.record.seq != index as u64 {
+            Some("sequence number out of order")
+        } else if entry.record.prev_hash != prev_hash {
+            Some("previous hash does not match")
+        } else if entry.record.hash() != entry.hash {
+            Some("entry hash does not match its contents")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return json!({
+                "valid": false,
+                "entries": entries.len(),
+                "firstInvalid": index,
+                "reason": reason,
+            });
+        }
+        prev_hash = entry.hash.clone();
+    }
+    let head = entries.last().map(|entry| entry.hash.clone());
+    if let Some(anchor) = anchor {
+        let anchor = anchor.trim_start_matches("0x").to_lowercase();
+        if !entries.iter().any(|entry| entry.hash == anchor) {
+            return json!({
+                "valid": false,
+                "entries": entries.len(),
+                "head": head,
+                "reason": "anchored entry is missing; the log was truncated or rewritten",
+            });
+        }
+    }
+    json!({ "valid": true, "entries": entries.len(), "head": head })
+}
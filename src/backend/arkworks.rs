@@ -0,0 +1,164 @@
+//! arkworks backend, generic over any pairing-friendly curve whose 𝔾₁ supports the
+//! Wahby–Boneh hash-to-curve map. Intended for experiments on curves other than the production
+//! ones; the layout is min-sig like the other backends (signatures in 𝔾₁, public keys in 𝔾₂).
+//!
+//! Messages are hashed with `BLS_SIG_<CURVE>G1_XMD:SHA-256_SSWU_RO_NUL_`, and points are
+//! encoded with the curve's canonical compressed serialization (for BLS12-381 this is the same
+//! zcash format blst uses, so both backends agree byte-for-byte).
+
+use std::marker::PhantomData;
+
+use ark_ec::hashing::curve_maps::wb::{WBConfig, WBMap};
+use ark_ec::hashing::map_to_curve_hasher::MapToCurveBasedHasher;
+use ark_ec::hashing::HashToCurve;
+use ark_ec::pairing::Pairing;
+use ark_ec::short_weierstrass::{Affine, Projective};
+use ark_ec::{AffineRepr, CurveGroup, PrimeGroup};
+use ark_ff::field_hashers::DefaultFieldHasher;
+use ark_ff::{BigInteger, PrimeField, UniformRand, Zero};
+use ark_serialize::CanonicalSerialize;
+use rand_core::OsRng;
+use sha2::Sha256;
+
+use super::Backend;
+use crate::point::Group;
+
+pub const BLS12_381_DST: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_";
+pub const BLS12_377_DST: &[u8] = b"BLS_SIG_BLS12377G1_XMD:SHA-256_SSWU_RO_NUL_";
+
+pub struct ArkworksBackend<E, C> {
+    dst: &'static [u8],
+    curve: PhantomData<(E, C)>,
+}
+
+impl<E, C> ArkworksBackend<E, C> {
+    pub fn new(dst: &'static [u8]) -> Self {
+        ArkworksBackend {
+            dst,
+            curve: PhantomData,
+        }
+    }
+}
+
+fn serialize<T: CanonicalSerialize>(value: &T) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    value
+        .serialize_compressed(&mut bytes)
+        .expect("Serialization failed");
+    bytes
+}
+
+fn deserialize<T: AffineRepr>(bytes: &[u8], what: &str) -> T {
+    if bytes.len() == T::generator().compressed_size() {
+        T::deserialize_compressed(bytes)
+    } else {
+        T::deserialize_uncompressed(bytes)
+    }
+    .unwrap_or_else(|_| panic!("Invalid {}", what))
+}
+
+impl<E, C> ArkworksBackend<E, C>
+where
+    E: Pairing<G1 = Projective<C>, G1Affine = Affine<C>>,
+    C: WBConfig<ScalarField = E::ScalarField>,
+{
+    fn decode_secret_key(secret: &[u8]) -> E::ScalarField {
+        let scalar_size = E::ScalarField::default().compressed_size();
+        assert_eq!(
+            secret.len(),
+            scalar_size,
+            "Secret key must be {} bytes",
+            scalar_size
+        );
+        let scalar = E::ScalarField::from_be_bytes_mod_order(secret);
+        assert_eq!(
+            scalar.into_bigint().to_bytes_be(),
+            secret,
+            "Failed to deserialize secret key"
+        );
+        scalar
+    }
+
+    fn encode_secret_key(scalar: &E::ScalarField) -> Vec<u8> {
+        scalar.into_bigint().to_bytes_be()
+    }
+
+    fn hash_to_g1(&self, message: &[u8]) -> E::G1Affine {
+        MapToCurveBasedHasher::<E::G1, DefaultFieldHasher<Sha256, 128>, WBMap<C>>::new(self.dst)
+            .and_then(|hasher| hasher.hash(message))
+            .expect("Hashing failed")
+    }
+
+    fn public_key(scalar: &E::ScalarField) -> Vec<u8> {
+        serialize(&(E::G2::generator() * scalar).into_affine())
+    }
+}
+
+impl<E, C> Backend for ArkworksBackend<E, C>
+where
+    E: Pairing<G1 = Projective<C>, G1Affine = Affine<C>>,
+    C: WBConfig<ScalarField = E::ScalarField>,
+{
+    fn generate_keys(&self) -> (Vec<u8>, Vec<u8>) {
+        let secret_key = E::ScalarField::rand(&mut OsRng);
+        (
+            Self::encode_secret_key(&secret_key),
+            Self::public_key(&secret_key),
+        )
+    }
+
+    fn public_key_from_secret(&self, secret: &[u8]) -> Vec<u8> {
+        Self::public_key(&Self::decode_secret_key(secret))
+    }
+
+    fn public_key_in_group(&self, secret: &[u8], group: Group) -> Vec<u8> {
+        let scalar = Self::decode_secret_key(secret);
+        match group {
+            Group::G1 => serialize(&(E::G1::generator() * scalar).into_affine()),
+            Group::G2 => Self::public_key(&scalar),
+        }
+    }
+
+    fn reduce_secret(&self, secret: &[u8]) -> Vec<u8> {
+        let scalar_size = E::ScalarField::default().compressed_size();
+        assert_eq!(
+            secret.len(),
+            scalar_size,
+            "Secret key must be {} bytes",
+            scalar_size
+        );
+        Self::encode_secret_key(&E::ScalarField::from_be_bytes_mod_order(secret))
+    }
+
+    fn sign(&self, secret: &[u8], message: &[u8]) -> Vec<u8> {
+        let hashed_message: E::G1 = self.hash_to_g1(message).into();
+        let signature = hashed_message * Self::decode_secret_key(secret);
+        serialize(&signature.into_affine())
+    }
+
+    fn aggregate_public_keys(&self, public_keys: &[Vec<u8>]) -> Vec<u8> {
+        let agg_key: E::G2 = public_keys
+            .iter()
+            .map(|key| deserialize::<E::G2Affine>(key, "public key"))
+            .sum();
+        serialize(&agg_key.into_affine())
+    }
+
+    fn aggregate_signatures(&self, signatures: &[Vec<u8>]) -> Vec<u8> {
+        let agg_sig: E::G1 = signatures
+            .iter()
+            .map(|sig| deserialize::<E::G1Affine>(sig, "signature"))
+            .sum();
+        serialize(&agg_sig.into_affine())
+    }
+
+    fn verify(&self, signature: &[u8], public_key: &[u8], message: &[u8]) -> bool {
+        let signature = deserialize::<E::G1Affine>(signature, "signature");
+        let public_key = deserialize::<E::G2Affine>(public_key, "public key");
+        let miller_loop = E::multi_miller_loop(
+            [signature, self.hash_to_g1(message)],
+            [(-E::G2::generator()).into_affine(), public_key],
+        );
+        E::final_exponentiation(miller_loop).is_some_and(|result| result.is_zero())
+    }
+}
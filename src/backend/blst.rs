@@ -0,0 +1,260 @@
+//! BLS12-381 backend built on blst, in the min-sig layout so it mirrors the default backend:
+//! signatures in 𝔾₁ (48 bytes compressed), public keys in 𝔾₂ (96 bytes compressed).
+//!
+//! Messages are hashed with the standard basic-scheme ciphersuite
+//! `BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_`. Points are accepted compressed or
+//! uncompressed and always emitted compressed.
+//!
+//! [`BlstMinPkBackend`] is the mirror image (public keys in 𝔾₁, signatures in 𝔾₂) used by the
+//! ciphersuites that other ecosystems standardised on. Both take their DST and scheme as
+//! parameters so `--suite` can configure them; `--backend blst` is the min-sig basic scheme.
+
+use ::blst::min_pk;
+use ::blst::min_sig::{AggregatePublicKey, AggregateSignature, PublicKey, SecretKey, Signature};
+use ::blst::BLST_ERROR;
+use rand_core::{OsRng, RngCore};
+
+use super::Backend;
+use crate::point::Group;
+
+pub const DST: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// The BLS12-381 group order r, big-endian.
+const ORDER: [u8; 32] = [
+    0x73, 0xed, 0xa7, 0x53, 0x29, 0x9d, 0x7d, 0x48, 0x33, 0x39, 0xd8, 0x08, 0x09, 0xa1, 0xd8, 0x05,
+    0x53, 0xbd, 0xa4, 0x02, 0xff, 0xfe, 0x5b, 0xfe, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01,
+];
+
+/// Reduces a big-endian integer modulo a big-endian `order` of the same length by repeated
+/// subtraction, which takes at most two rounds for the BLS12-381 order.
+fn reduce_be(value: &[u8], order: &[u8]) -> Vec<u8> {
+    let mut value = value.to_vec();
+    while value.as_slice() >= order {
+        let mut borrow = 0i16;
+        for (byte, &o) in value.iter_mut().zip(order).rev() {
+            let difference = *byte as i16 - o as i16 - borrow;
+            borrow = (difference < 0) as i16;
+            *byte = difference.rem_euclid(256) as u8;
+        }
+    }
+    value
+}
+
+fn reduce_secret(secret: &[u8]) -> Vec<u8> {
+    assert_eq!(secret.len(), 32, "Secret key must be 32 bytes");
+    reduce_be(secret, &ORDER)
+}
+
+/// Min-sig BLS12-381 with a configurable DST, optionally in the message-augmentation scheme
+/// where every signature covers the signer's compressed public key followed by the message.
+pub struct BlstBackend {
+    dst: Vec<u8>,
+    augment: bool,
+}
+
+impl BlstBackend {
+    pub fn new(dst: Vec<u8>, augment: bool) -> Self {
+        BlstBackend { dst, augment }
+    }
+
+    /// The bytes prepended to the message when hashing to the curve.
+    fn augmentation(&self, public_key: &PublicKey) -> Vec<u8> {
+        if self.augment {
+            public_key.compress().to_vec()
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+fn decode_secret_key(secret: &[u8]) -> SecretKey {
+    assert_eq!(secret.len(), 32, "Secret key must be 32 bytes");
+    SecretKey::from_bytes(secret).expect("Failed to deserialize secret key")
+}
+
+fn decode_public_key(public_key: &[u8]) -> PublicKey {
+    PublicKey::key_validate(public_key).expect("Invalid public key")
+}
+
+fn decode_signature(signature: &[u8]) -> Signature {
+    Signature::sig_validate(signature, false).expect("Invalid signature")
+}
+
+impl Backend for BlstBackend {
+    fn generate_keys(&self) -> (Vec<u8>, Vec<u8>) {
+        let mut ikm = [0u8; 32];
+        OsRng.fill_bytes(&mut ikm);
+        let secret_key = SecretKey::key_gen(&ikm, &[]).expect("Key generation failed");
+        (
+            secret_key.to_bytes().to_vec(),
+            secret_key.sk_to_pk().to_bytes().to_vec(),
+        )
+    }
+
+    fn public_key_from_secret(&self, secret: &[u8]) -> Vec<u8> {
+        decode_secret_key(secret).sk_to_pk().to_bytes().to_vec()
+    }
+
+    fn public_key_in_group(&self, secret: &[u8], group: Group) -> Vec<u8> {
+        match group {
+            Group::G1 => decode_min_pk_secret_key(secret)
+                .sk_to_pk()
+                .to_bytes()
+                .to_vec(),
+            Group::G2 => self.public_key_from_secret(secret),
+        }
+    }
+
+    fn reduce_secret(&self, secret: &[u8]) -> Vec<u8> {
+        reduce_secret(secret)
+    }
+
+    fn sign(&self, secret: &[u8], message: &[u8]) -> Vec<u8> {
+        let secret_key = decode_secret_key(secret);
+        let augmentation = self.augmentation(&secret_key.sk_to_pk());
+        secret_key
+            .sign(message, &self.dst, &augmentation)
+            .to_bytes()
+            .to_vec()
+    }
+
+    fn aggregate_public_keys(&self, public_keys: &[Vec<u8>]) -> Vec<u8> {
+        let keys: Vec<PublicKey> = public_keys.iter().map(|k| decode_public_key(k)).collect();
+        let refs: Vec<&PublicKey> = keys.iter().collect();
+        AggregatePublicKey::aggregate(&refs, false)
+            .expect("Aggregation failed")
+            .to_public_key()
+            .to_bytes()
+            .to_vec()
+    }
+
+    fn aggregate_signatures(&self, signatures: &[Vec<u8>]) -> Vec<u8> {
+        let sigs: Vec<Signature> = signatures.iter().map(|s| decode_signature(s)).collect();
+        let refs: Vec<&Signature> = sigs.iter().collect();
+        AggregateSignature::aggregate(&refs, false)
+            .expect("Aggregation failed")
+            .to_signature()
+            .to_bytes()
+            .to_vec()
+    }
+
+    fn verify(&self, signature: &[u8], public_key: &[u8], message: &[u8]) -> bool {
+        let signature = decode_signature(signature);
+        let public_key = decode_public_key(public_key);
+        let augmentation = self.augmentation(&public_key);
+        signature.verify(false, message, &self.dst, &augmentation, &public_key, false)
+            == BLST_ERROR::BLST_SUCCESS
+    }
+}
+
+/// Min-pk counterpart of [`BlstBackend`], with the key generation procedure left to the suite.
+pub struct BlstMinPkBackend {
+    dst: Vec<u8>,
+    augment: bool,
+    key_gen: fn(&[u8]) -> min_pk::SecretKey,
+}
+
+impl BlstMinPkBackend {
+    pub fn new(dst: Vec<u8>, augment: bool, key_gen: fn(&[u8]) -> min_pk::SecretKey) -> Self {
+        BlstMinPkBackend {
+            dst,
+            augment,
+            key_gen,
+        }
+    }
+
+    /// The bytes prepended to the message when hashing to the curve.
+    fn augmentation(&self, public_key: &min_pk::PublicKey) -> Vec<u8> {
+        if self.augment {
+            public_key.compress().to_vec()
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+fn decode_min_pk_secret_key(secret: &[u8]) -> min_pk::SecretKey {
+    assert_eq!(secret.len(), 32, "Secret key must be 32 bytes");
+    min_pk::SecretKey::from_bytes(secret).expect("Failed to deserialize secret key")
+}
+
+fn decode_min_pk_public_key(public_key: &[u8]) -> min_pk::PublicKey {
+    min_pk::PublicKey::key_validate(public_key).expect("Invalid public key")
+}
+
+fn decode_min_pk_signature(signature: &[u8]) -> min_pk::Signature {
+    min_pk::Signature::sig_validate(signature, false).expect("Invalid signature")
+}
+
+impl Backend for BlstMinPkBackend {
+    fn generate_keys(&self) -> (Vec<u8>, Vec<u8>) {
+        let mut ikm = [0u8; 32];
+        OsRng.fill_bytes(&mut ikm);
+        let secret_key = (self.key_gen)(&ikm);
+        (
+            secret_key.to_bytes().to_vec(),
+            secret_key.sk_to_pk().to_bytes().to_vec(),
+        )
+    }
+
+    fn public_key_from_secret(&self, secret: &[u8]) -> Vec<u8> {
+        decode_min_pk_secret_key(secret)
+            .sk_to_pk()
+            .to_bytes()
+            .to_vec()
+    }
+
+    fn public_key_in_group(&self, secret: &[u8], group: Group) -> Vec<u8> {
+        match group {
+            Group::G1 => self.public_key_from_secret(secret),
+            Group::G2 => decode_secret_key(secret).sk_to_pk().to_bytes().to_vec(),
+        }
+    }
+
+    fn reduce_secret(&self, secret: &[u8]) -> Vec<u8> {
+        reduce_secret(secret)
+    }
+
+    fn sign(&self, secret: &[u8], message: &[u8]) -> Vec<u8> {
+        let secret_key = decode_min_pk_secret_key(secret);
+        let augmentation = self.augmentation(&secret_key.sk_to_pk());
+        secret_key
+            .sign(message, &self.dst, &augmentation)
+            .to_bytes()
+            .to_vec()
+    }
+
+    fn aggregate_public_keys(&self, public_keys: &[Vec<u8>]) -> Vec<u8> {
+        let keys: Vec<min_pk::PublicKey> = public_keys
+            .iter()
+            .map(|k| decode_min_pk_public_key(k))
+            .collect();
+        let refs: Vec<&min_pk::PublicKey> = keys.iter().collect();
+        min_pk::AggregatePublicKey::aggregate(&refs, false)
+            .expect("Aggregation failed")
+            .to_public_key()
+            .to_bytes()
+            .to_vec()
+    }
+
+    fn aggregate_signatures(&self, signatures: &[Vec<u8>]) -> Vec<u8> {
+        let sigs: Vec<min_pk::Signature> = signatures
+            .iter()
+            .map(|s| decode_min_pk_signature(s))
+            .collect();
+        let refs: Vec<&min_pk::Signature> = sigs.iter().collect();
+        min_pk::AggregateSignature::aggregate(&refs, false)
+            .expect("Aggregation failed")
+            .to_signature()
+            .to_bytes()
+            .to_vec()
+    }
+
+    fn verify(&self, signature: &[u8], public_key: &[u8], message: &[u8]) -> bool {
+        let signature = decode_min_pk_signature(signature);
+        let public_key = decode_min_pk_public_key(public_key);
+        let augmentation = self.augmentation(&public_key);
+        signature.verify(false, message, &self.dst, &augmentation, &public_key, false)
+            == BLST_ERROR::BLST_SUCCESS
+    }
+}
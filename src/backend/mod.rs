@@ -0,0 +1,118 @@
+//! Curve backends behind the BLS commands.
+//!
+//! The CLI only ever deals in byte encodings of secret keys, public keys and signatures, so a
+//! backend is the piece that owns the field, group and pairing arithmetic for one curve and
+//! exposes the BLS operations over those encodings. [`sylow`] (BN254) is the default; other
+//! backends are compiled in through cargo features and picked with `--backend`.
+
+#[cfg(feature = "arkworks")]
+pub mod arkworks;
+#[cfg(feature = "blst")]
+pub mod blst;
+pub mod sylow;
+
+use clap::ValueEnum;
+
+use crate::point::Group;
+
+/// The BLS operations every backend provides.
+///
+/// Malformed inputs are treated the same way throughout the tool: the backend panics with a
+/// message naming the offending value.
+pub trait Backend: Send + Sync {
+    /// Generates a fresh key pair, returning `(secret_key, public_key)`.
+    fn generate_keys(&self) -> (Vec<u8>, Vec<u8>);
+
+    /// Derives the public key belonging to a secret key.
+    fn public_key_from_secret(&self, secret: &[u8]) -> Vec<u8>;
+
+    /// Derives the key belonging to a secret key in either group, for deployments that expect
+    /// it in the other group than the one this backend uses.
+    fn public_key_in_group(&self, secret: &[u8], group: Group) -> Vec<u8>;
+
+    /// Reduces a secret key encoding of the right length modulo the group order, returning the
+    /// encoding of the key that is actually used when signing with it.
+    fn reduce_secret(&self, secret: &[u8]) -> Vec<u8>;
+
+    /// Signs a message with a secret key.
+    fn sign(&self, secret: &[u8], message: &[u8]) -> Vec<u8>;
+
+    /// Adds up public keys into a single aggregate key.
+    fn aggregate_public_keys(&self, public_keys: &[Vec<u8>]) -> Vec<u8>;
+
+    /// Adds up signatures into a single aggregate signature.
+    fn aggregate_signatures(&self, signatures: &[Vec<u8>]) -> Vec<u8>;
+
+    /// Checks a (possibly aggregate) signature on a message against a (possibly aggregate) key.
+    fn verify(&self, signature: &[u8], public_key: &[u8], message: &[u8]) -> bool;
+}
+
+/// Backends selectable with `--backend`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum BackendKind {
+    /// BN254 through the sylow crate
+    Sylow,
+    /// BLS12-381 (min-sig) through the blst crate
+    #[cfg(feature = "blst")]
+    Blst,
+    /// BLS12-381 (min-sig) through arkworks
+    #[cfg(feature = "arkworks")]
+    ArkworksBls12_381,
+    /// BLS12-377 (min-sig) through arkworks
+    #[cfg(feature = "arkworks")]
+    ArkworksBls12_377,
+}
+
+impl BackendKind {
+    /// The name the backend goes by on the command line and in files.
+    pub fn name(self) -> String {
+        self.to_possible_value()
+            .expect("Backend has a name")
+            .get_name()
+            .to_string()
+    }
+
+    /// The curve the backend works on.
+    pub fn curve(self) -> &'static str {
+        match self {
+            BackendKind::Sylow => "bn254",
+            #[cfg(feature = "blst")]
+            BackendKind::Blst => "bls12-381",
+            #[cfg(feature = "arkworks")]
+            BackendKind::ArkworksBls12_381 => "bls12-381",
+            #[cfg(feature = "arkworks")]
+            BackendKind::ArkworksBls12_377 => "bls12-377",
+        }
+    }
+
+    /// The domain separation tag messages are hashed under.
+    pub fn dst(self) -> &'static [u8] {
+        match self {
+            BackendKind::Sylow => sylow::DST,
+            #[cfg(feature = "blst")]
+            BackendKind::Blst => blst::DST,
+            #[cfg(feature = "arkworks")]
+            BackendKind::ArkworksBls12_381 => arkworks::BLS12_381_DST,
+            #[cfg(feature = "arkworks")]
+            BackendKind::ArkworksBls12_377 => arkworks::BLS12_377_DST,
+        }
+    }
+
+    pub fn instantiate(self) -> Box<dyn Backend> {
+        match self {
+            BackendKind::Sylow => Box::new(sylow::SylowBackend::default()),
+            #[cfg(feature = "blst")]
+            BackendKind::Blst => Box::new(blst::BlstBackend::new(blst::DST.to_vec(), false)),
+            #[cfg(feature = "arkworks")]
+            BackendKind::ArkworksBls12_381 => Box::new(arkworks::ArkworksBackend::<
+                ark_bls12_381::Bls12_381,
+                ark_bls12_381::g1::Config,
+            >::new(arkworks::BLS12_381_DST)),
+            #[cfg(feature = "arkworks")]
+            BackendKind::ArkworksBls12_377 => Box::new(arkworks::ArkworksBackend::<
+                ark_bls12_377::Bls12_377,
+                ark_bls12_377::g1::Config,
+            >::new(arkworks::BLS12_377_DST)),
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! BN254 backend built on sylow: signatures in 𝔾₁ (64 bytes), public keys in 𝔾₂ (128 bytes).
+
+use ::sylow::{
+    glued_miller_loop, FieldExtensionTrait, Fp, Fr, G1Affine, G1Projective, G2Affine,
+    G2PreComputed, G2Projective, GroupTrait, Gt, XMDExpander,
+};
+use rand_core::OsRng;
+use sha3::Keccak256;
+
+use super::Backend;
+use crate::fixed_base::g2_generator_mul;
+use crate::hash_to_curve::{HashParams, HashToCurve};
+use crate::point::Group;
+
+pub(crate) const DST: &[u8; 30] = b"WARLOCK-CHAOS-V01-CS01-SHA-256";
+pub(crate) const SECURITY_BITS: u64 = 128;
+
+#[derive(Default)]
+pub struct SylowBackend {
+    hash_params: HashParams,
+}
+
+impl SylowBackend {
+    pub fn new(hash_params: HashParams) -> Self {
+        SylowBackend { hash_params }
+    }
+
+    fn hash_to_g1(&self, message: &[u8]) -> G1Projective {
+        if self.hash_params == HashParams::default() {
+            // sylow's own pipeline, which the default configuration has always used
+            return hash_to_g1(message);
+        }
+        HashToCurve {
+            params: self.hash_params,
+            dst: DST,
+        }
+        .hash(message)
+    }
+}
+
+fn decode_secret_key(secret: &[u8]) -> Fp {
+    let secret_key_array: [u8; 32] = secret
+        .try_into()
+        .expect("Secret key must be 32 bytes");
+    Fp::from_be_bytes(&secret_key_array)
+        .into_option()
+        .expect("Failed to deserialize secret key")
+}
+
+pub(crate) fn decode_public_key(public_key: &[u8]) -> G2Projective {
+    let key_array: [u8; 128] = public_key
+        .try_into()
+        .expect("Public key must be 128 bytes");
+    G2Affine::from_be_bytes(&key_array)
+        .into_option()
+        .expect("Invalid public key")
+}
+
+pub(crate) fn decode_signature(signature: &[u8]) -> G1Projective {
+    let sig_array: [u8; 64] = signature
+        .try_into()
+        .expect("Signature must be 64 bytes");
+    G1Affine::from_be_bytes(&sig_array)
+        .into_option()
+        .expect("Invalid signature")
+}
+
+pub(crate) fn hash_to_g1(message: &[u8]) -> G1Projective {
+    let expander = XMDExpander::<Keccak256>::new(DST, SECURITY_BITS);
+    G1Projective::hash_to_curve(&expander, message).expect("Hashing failed")
+}
+
+/// Checks that the product of pairings e(P₁, Q₁)·…·e(Pₙ, Qₙ) is the identity in 𝔾ₜ.
+///
+/// All Miller loops are glued into one accumulator so only a single final exponentiation is
+/// performed. Pairs containing the point at infinity contribute the identity and are skipped,
+/// which is how `sylow::pairing` treats them as well.
+pub(crate) fn pairing_check(pairs: &[(G1Projective, G2Projective)]) -> bool {
+    let (g1s, g2s): (Vec<G1Affine>, Vec<G2PreComputed>) = pairs
+        .iter()
+        .filter(|(p, q)| !p.is_zero() && !q.is_zero())
+        .map(|(p, q)| (G1Affine::from(p), q.precompute()))
+        .unzip();
+    glued_miller_loop(&g2s, &g1s).final_exponentiation() == Gt::identity()
+}
+
+impl Backend for SylowBackend {
+    fn generate_keys(&self) -> (Vec<u8>, Vec<u8>) {
+        let secret_key = Fp::new(Fr::rand(&mut OsRng).value());
+        let public_key = g2_generator_mul(&secret_key);
+        (
+            secret_key.to_be_bytes().to_vec(),
+            G2Affine::from(public_key).to_be_bytes().to_vec(),
+        )
+    }
+
+    fn public_key_from_secret(&self, secret: &[u8]) -> Vec<u8> {
+        let public_key = g2_generator_mul(&decode_secret_key(secret));
+        G2Affine::from(public_key).to_be_bytes().to_vec()
+    }
+
+    fn public_key_in_group(&self, secret: &[u8], group: Group) -> Vec<u8> {
+        match group {
+            Group::G1 => {
+                let public_key = G1Projective::generator() * decode_secret_key(secret);
+                G1Affine::from(public_key).to_be_bytes().to_vec()
+            }
+            Group::G2 => self.public_key_from_secret(secret),
+        }
+    }
+
+    fn reduce_secret(&self, secret: &[u8]) -> Vec<u8> {
+        assert_eq!(secret.len(), 32, "Secret key must be 32 bytes");
+        let radix = Fr::from(256);
+        let scalar = secret
+            .iter()
+            .fold(Fr::ZERO, |acc, &byte| acc * radix + Fr::from(byte as u64));
+        Fp::from(scalar).to_be_bytes().to_vec()
+    }
+
+    fn sign(&self, secret: &[u8], message: &[u8]) -> Vec<u8> {
+        let signature = self.hash_to_g1(message) * decode_secret_key(secret);
+        G1Affine::from(signature).to_be_bytes().to_vec()
+    }
+
+    fn aggregate_public_keys(&self, public_keys: &[Vec<u8>]) -> Vec<u8> {
+        let agg_key = public_keys
+            .iter()
+            .fold(G2Projective::zero(), |acc, key| acc + decode_public_key(key));
+        G2Affine::from(agg_key).to_be_bytes().to_vec()
+    }
+
+    fn aggregate_signatures(&self, signatures: &[Vec<u8>]) -> Vec<u8> {
+        let agg_sig = signatures
+            .iter()
+            .fold(G1Projective::zero(), |acc, sig| acc + decode_signature(sig));
+        G1Affine::from(agg_sig).to_be_bytes().to_vec()
+    }
+
+    fn verify(&self, signature: &[u8], public_key: &[u8], message: &[u8]) -> bool {
+        pairing_check(&[
+            (decode_signature(signature), -G2Projective::generator()),
+            (self.hash_to_g1(message), decode_public_key(public_key)),
+        ])
+    }
+}
@@ -0,0 +1,157 @@
+//! Resumable verification of large files of signatures.
+//!
+//! `batch-verify` reads a JSON-lines file with one `{"signature", "publicKey", "message"}`
+//! record per line (hex, hex and text, as `verify` takes them) and reports how many verify and
+//! which lines do not. Runs over millions of records take hours, so with `--checkpoint` it
+//! periodically writes where it got to — the byte offset after the last checked line, the line
+//! number and the results so far — and `--resume` picks a run up from such a file instead of
+//! starting over. A checkpoint is only accepted for the input and backend it was written for.
+//!
+//! Checkpoints are written to a temporary file and renamed over the old one, so a crash while
+//! writing leaves the previous checkpoint intact.
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend::Backend;
+use crate::threads;
+
+/// Lines read ahead and verified in parallel at a time.
+const BLOCK_LEN: usize = 4096;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Record {
+    signature: String,
+    public_key: String,
+    message: String,
+}
+
+/// Progress of a run: enough to resume it and to report on it.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+    input: PathBuf,
+    backend: String,
+    offset: u64,
+    lines: u64,
+    valid: u64,
+    invalid_lines: Vec<u64>,
+    complete: bool,
+}
+
+impl Checkpoint {
+    fn new(input: &Path, backend: &str) -> Self {
+        Checkpoint {
+            input: input.to_path_buf(),
+            backend: backend.to_string(),
+            offset: 0,
+            lines: 0,
+            valid: 0,
+            invalid_lines: Vec::new(),
+            complete: false,
+        }
+    }
+
+    pub fn read(path: &Path) -> Self {
+        let contents = fs::read_to_string(path).expect("Failed to read checkpoint");
+        serde_json::from_str(&contents).expect("Malformed checkpoint")
+    }
+
+    fn write(&self, path: &Path) {
+        let contents = serde_json::to_string_pretty(self).expect("Failed to serialize checkpoint");
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, contents + "\n").expect("Failed to write checkpoint");
+        fs::rename(&temporary, path).expect("Failed to write checkpoint");
+    }
+}
+
+/// Whether the record on one line verifies; records with undecodable hex do not.
+fn check(backend: &dyn Backend, line: &str, number: u64) -> bool {
+    let record: Record = serde_json::from_str(line)
+        .unwrap_or_else(|error| panic!("Line {} is not a verification record: {}", number, error));
+    match (hex::decode(&record.signature), hex::decode(&record.public_key)) {
+        (Ok(signature), Ok(public_key)) => {
+            backend.verify(&signature, &public_key, record.message.as_bytes())
+        }
+        _ => false,
+    }
+}
+
+/// Verifies every record of `input` with `backend`, named `backend_name` in checkpoints, on
+/// `threads` threads, continuing from `resume` if given and writing a checkpoint to
+/// `checkpoint` every `every` records and at the end.
+pub fn verify(
+    backend: &dyn Backend,
+    backend_name: &str,
+    input: &Path,
+    resume: Option<Checkpoint>,
+    checkpoint: Option<&Path>,
+    every: u64,
+    threads: usize,
+) -> Checkpoint {
+    assert!(every > 0, "Checkpoints must be at least one record apart");
+    let input = fs::canonicalize(input).expect("Failed to open input");
+    let mut progress = match resume {
+        Some(progress) => {
+            assert!(
+                progress.input == input,
+                "Checkpoint is for {}, not {}",
+                progress.input.display(),
+                input.display()
+            );
+            assert!(
+                progress.backend == backend_name,
+                "Checkpoint is for the {} backend, not {}",
+                progress.backend,
+                backend_name
+            );
+            progress
+        }
+        None => Checkpoint::new(&input, backend_name),
+    };
+    let mut file = File::open(&input).expect("Failed to open input");
+    file.seek(SeekFrom::Start(progress.offset))
+        .expect("Failed to seek to the checkpoint");
+    let mut reader = BufReader::new(file);
+    loop {
+        // each line with its number and length, so progress advances one line at a time
+        let mut block = Vec::with_capacity(BLOCK_LEN);
+        while block.len() < BLOCK_LEN {
+            let mut line = String::new();
+            let read = reader.read_line(&mut line).expect("Failed to read input");
+            if read == 0 {
+                break;
+            }
+            block.push((progress.lines + block.len() as u64 + 1, read as u64, line));
+        }
+        if block.is_empty() {
+            break;
+        }
+        let results = threads::map(threads, &block, |(number, _, line)| {
+            (!line.trim().is_empty()).then(|| check(backend, line, *number))
+        });
+        for ((number, read, _), result) in block.iter().zip(results) {
+            progress.offset += read;
+            progress.lines = *number;
+            match result {
+                None => continue,
+                Some(true) => progress.valid += 1,
+                Some(false) => progress.invalid_lines.push(*number),
+            }
+            if let Some(path) = checkpoint {
+                if (progress.valid + progress.invalid_lines.len() as u64).is_multiple_of(every) {
+                    progress.write(path);
+                }
+            }
+        }
+    }
+    progress.complete = true;
+    if let Some(path) = checkpoint {
+        progress.write(path);
+    }
+    progress
+}
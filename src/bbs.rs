@@ -0,0 +1,403 @@
+//! BBS signatures over BN254 for selective disclosure.
+//!
+//! This follows the structure of the IRTF CFRG BBS draft (KeyGen, Sign, Verify, ProofGen,
+//! ProofVerify), instantiated on BN254 with the same Keccak-256 XMD expander and SvdW map as
+//! the main signature scheme. Because the draft only defines BLS12-381 ciphersuites, outputs
+//! are not interoperable with other BBS implementations, but every value is derived exactly
+//! as the draft describes so the construction can be audited against it.
+//!
+//! Encodings: secret keys are 32-byte scalars, public keys 128-byte 𝔾₂ points, signatures
+//! `A ‖ e` (96 bytes) and proofs `Ā ‖ B̄ ‖ D ‖ ê ‖ r̂₁ ‖ r̂₃ ‖ m̂ⱼ… ‖ c`. None of the points
+//! may be the identity.
+
+use rand_core::OsRng;
+use sha3::Keccak256;
+use sylow::{
+    Expander, FieldExtensionTrait, Fp, Fr, G1Affine, G1Projective, G2Affine, G2Projective,
+    GroupTrait, XMDExpander,
+};
+
+use crate::backend::sylow::{decode_public_key, pairing_check};
+use crate::fixed_base::g2_generator_mul;
+
+const API_ID: &[u8] = b"BBS_BN254G1_XMD:KECCAK-256_SVDW_RO_H2G_HM2S_";
+const SECURITY_BITS: u64 = 128;
+/// Bytes of expanded output reduced into one scalar (⌈(⌈log₂ r⌉ + k) / 8⌉).
+const EXPAND_LEN: usize = 48;
+
+const POINT_LEN: usize = 64;
+const SCALAR_LEN: usize = 32;
+const SIGNATURE_LEN: usize = POINT_LEN + SCALAR_LEN;
+const PROOF_FIXED_LEN: usize = 3 * POINT_LEN + 4 * SCALAR_LEN;
+
+fn dst(suffix: &[u8]) -> Vec<u8> {
+    [API_ID, suffix].concat()
+}
+
+fn i2osp(value: usize) -> [u8; 8] {
+    (value as u64).to_be_bytes()
+}
+
+fn scalar_to_bytes(scalar: &Fr) -> [u8; SCALAR_LEN] {
+    Fp::from(scalar).to_be_bytes()
+}
+
+fn scalar_from_bytes(bytes: &[u8], what: &str) -> Fr {
+    let array: [u8; SCALAR_LEN] = bytes
+        .try_into()
+        .unwrap_or_else(|_| panic!("{} must be {} bytes", what, SCALAR_LEN));
+    Fr::from_be_bytes(&array)
+        .into_option()
+        .unwrap_or_else(|| panic!("{} is not below the group order", what))
+}
+
+fn point_to_bytes(point: &G1Projective) -> [u8; POINT_LEN] {
+    G1Affine::from(point).to_be_bytes()
+}
+
+fn point_from_bytes(bytes: &[u8], what: &str) -> G1Projective {
+    let array: [u8; POINT_LEN] = bytes
+        .try_into()
+        .unwrap_or_else(|_| panic!("{} must be {} bytes", what, POINT_LEN));
+    let point = G1Affine::from_be_bytes(&array)
+        .into_option()
+        .unwrap_or_else(|| panic!("Invalid {}", what));
+    // the pairing check skips identity points, so Ā = B̄ = identity would pass any key; the
+    // draft's octets_to_signature and octets_to_proof reject them
+    assert!(!point.is_zero(), "The {} must not be the identity", what);
+    point
+}
+
+fn mul(point: &G1Projective, scalar: &Fr) -> G1Projective {
+    *point * Fp::from(scalar)
+}
+
+/// Reduces a big-endian byte string of up to 48 bytes modulo the group order.
+fn reduce(bytes: &[u8]) -> Fr {
+    let two_128 = {
+        let mut array = [0u8; SCALAR_LEN];
+        array[15] = 1;
+        Fr::from_be_bytes(&array).into_option().expect("2^128 < r")
+    };
+    let mut padded = [0u8; EXPAND_LEN];
+    padded[EXPAND_LEN - bytes.len()..].copy_from_slice(bytes);
+    padded.chunks(16).fold(Fr::ZERO, |acc, chunk| {
+        let mut array = [0u8; SCALAR_LEN];
+        array[16..].copy_from_slice(chunk);
+        let limb = Fr::from_be_bytes(&array).into_option().expect("2^128 < r");
+        acc * two_128 + limb
+    })
+}
+
+fn hash_to_scalar(message: &[u8], dst: &[u8]) -> Fr {
+    let expander = XMDExpander::<Keccak256>::new(dst, SECURITY_BITS);
+    let expanded = expander
+        .expand_message(message, EXPAND_LEN)
+        .expect("Message expansion failed");
+    reduce(&expanded)
+}
+
+fn hash_to_g1(message: &[u8], dst: &[u8]) -> G1Projective {
+    let expander = XMDExpander::<Keccak256>::new(dst, SECURITY_BITS);
+    G1Projective::hash_to_curve(&expander, message).expect("Hashing failed")
+}
+
+/// Maps each message string to a scalar.
+pub fn messages_to_scalars(messages: &[String]) -> Vec<Fr> {
+    let map_dst = dst(b"MAP_MSG_TO_SCALAR_AS_HASH_");
+    messages
+        .iter()
+        .map(|m| hash_to_scalar(m.as_bytes(), &map_dst))
+        .collect()
+}
+
+/// The base point P₁ and the generators Q₁, H₁…H_L for `count` messages.
+struct Generators {
+    p1: G1Projective,
+    q1: G1Projective,
+    h: Vec<G1Projective>,
+}
+
+impl Generators {
+    fn new(count: usize) -> Self {
+        let generator_dst = dst(b"H2G_HM2S_");
+        let point =
+            |i: usize| hash_to_g1(&[API_ID, b"GENERATOR", &i2osp(i)].concat(), &generator_dst);
+        Generators {
+            p1: hash_to_g1(&[API_ID, b"BP_MESSAGE"].concat(), &dst(b"H2G_HM2S_BP_")),
+            q1: point(0),
+            h: (1..=count).map(point).collect(),
+        }
+    }
+
+    fn domain(&self, public_key: &[u8], header: &[u8]) -> Fr {
+        let mut input = public_key.to_vec();
+        input.extend(i2osp(self.h.len()));
+        input.extend(point_to_bytes(&self.q1));
+        for h in &self.h {
+            input.extend(point_to_bytes(h));
+        }
+        input.extend(API_ID);
+        input.extend(i2osp(header.len()));
+        input.extend(header);
+        hash_to_scalar(&input, &dst(b"H2S_"))
+    }
+
+    /// B = P₁ + Q₁·domain + Σ Hᵢ·mᵢ over the given `(index, scalar)` pairs.
+    fn commitment<'a>(
+        &self,
+        domain: &Fr,
+        messages: impl Iterator<Item = (usize, &'a Fr)>,
+    ) -> G1Projective {
+        messages.fold(self.p1 + mul(&self.q1, domain), |acc, (i, m)| {
+            acc + mul(&self.h[i], m)
+        })
+    }
+}
+
+/// Generates a BBS key pair, returning `(secret_key, public_key)`.
+pub fn keygen() -> (Vec<u8>, Vec<u8>) {
+    let secret_key = Fr::rand(&mut OsRng);
+    let public_key = g2_generator_mul(&Fp::from(secret_key));
+    (
+        scalar_to_bytes(&secret_key).to_vec(),
+        G2Affine::from(public_key).to_be_bytes().to_vec(),
+    )
+}
+
+/// Derives the public key belonging to a BBS secret key.
+pub fn public_key_from_secret(secret: &[u8]) -> Vec<u8> {
+    let secret_key = scalar_from_bytes(secret, "Secret key");
+    G2Affine::from(g2_generator_mul(&Fp::from(secret_key)))
+        .to_be_bytes()
+        .to_vec()
+}
+
+/// Signs all `messages` at once.
+pub fn sign(secret: &[u8], header: &[u8], messages: &[Fr]) -> Vec<u8> {
+    let secret_key = scalar_from_bytes(secret, "Secret key");
+    let public_key = public_key_from_secret(secret);
+    let generators = Generators::new(messages.len());
+    let domain = generators.domain(&public_key, header);
+
+    let mut e_input = scalar_to_bytes(&secret_key).to_vec();
+    for m in messages {
+        e_input.extend(scalar_to_bytes(m));
+    }
+    e_input.extend(scalar_to_bytes(&domain));
+    let e = hash_to_scalar(&e_input, &dst(b"H2S_"));
+
+    let b = generators.commitment(&domain, messages.iter().enumerate());
+    let a = mul(&b, &(Fr::ONE / (secret_key + e)));
+    [point_to_bytes(&a).as_slice(), &scalar_to_bytes(&e)].concat()
+}
+
+fn decode_signature(signature: &[u8]) -> (G1Projective, Fr) {
+    assert_eq!(
+        signature.len(),
+        SIGNATURE_LEN,
+        "Signature must be {} bytes",
+        SIGNATURE_LEN
+    );
+    (
+        point_from_bytes(&signature[..POINT_LEN], "signature point"),
+        scalar_from_bytes(&signature[POINT_LEN..], "Signature scalar"),
+    )
+}
+
+/// Checks a signature over all `messages`.
+pub fn verify(public_key: &[u8], signature: &[u8], header: &[u8], messages: &[Fr]) -> bool {
+    let (a, e) = decode_signature(signature);
+    let w = decode_public_key(public_key);
+    let generators = Generators::new(messages.len());
+    let domain = generators.domain(public_key, header);
+    let b = generators.commitment(&domain, messages.iter().enumerate());
+    pairing_check(&[
+        (a, w + g2_generator_mul(&Fp::from(e))),
+        (b, -G2Projective::generator()),
+    ])
+}
+
+#[allow(clippy::too_many_arguments)]
+fn challenge(
+    abar: &G1Projective,
+    bbar: &G1Projective,
+    d: &G1Projective,
+    t1: &G1Projective,
+    t2: &G1Projective,
+    disclosed: &[(usize, Fr)],
+    domain: &Fr,
+    presentation_header: &[u8],
+) -> Fr {
+    let mut input = Vec::new();
+    for point in [abar, bbar, d, t1, t2] {
+        input.extend(point_to_bytes(point));
+    }
+    input.extend(i2osp(disclosed.len()));
+    for (i, m) in disclosed {
+        input.extend(i2osp(*i));
+        input.extend(scalar_to_bytes(m));
+    }
+    input.extend(scalar_to_bytes(domain));
+    input.extend(i2osp(presentation_header.len()));
+    input.extend(presentation_header);
+    hash_to_scalar(&input, &dst(b"H2S_"))
+}
+
+/// Derives a zero-knowledge proof of a signature that reveals only the messages at the
+/// (zero-based, ascending) `disclosed` indexes.
+pub fn derive_proof(
+    public_key: &[u8],
+    signature: &[u8],
+    header: &[u8],
+    presentation_header: &[u8],
+    messages: &[Fr],
+    disclosed: &[usize],
+) -> Vec<u8> {
+    assert!(
+        verify(public_key, signature, header, messages),
+        "Signature does not verify for these messages"
+    );
+    let (a, e) = decode_signature(signature);
+    let generators = Generators::new(messages.len());
+    let domain = generators.domain(public_key, header);
+    let undisclosed: Vec<usize> = (0..messages.len())
+        .filter(|i| !disclosed.contains(i))
+        .collect();
+
+    let random = || Fr::rand(&mut OsRng);
+    let (r1, r2, e_tilde, r1_tilde, r3_tilde) = (random(), random(), random(), random(), random());
+    let m_tilde: Vec<Fr> = undisclosed.iter().map(|_| random()).collect();
+
+    let b = generators.commitment(&domain, messages.iter().enumerate());
+    let d = mul(&b, &r2);
+    let abar = mul(&a, &(r1 * r2));
+    let bbar = mul(&d, &r1) - mul(&abar, &e);
+    let t1 = mul(&abar, &e_tilde) + mul(&d, &r1_tilde);
+    let t2 = undisclosed
+        .iter()
+        .zip(&m_tilde)
+        .fold(mul(&d, &r3_tilde), |acc, (&j, m)| {
+            acc + mul(&generators.h[j], m)
+        });
+
+    let disclosed_messages: Vec<(usize, Fr)> =
+        disclosed.iter().map(|&i| (i, messages[i])).collect();
+    let c = challenge(
+        &abar,
+        &bbar,
+        &d,
+        &t1,
+        &t2,
+        &disclosed_messages,
+        &domain,
+        presentation_header,
+    );
+
+    let r3 = Fr::ONE / r2;
+    let mut proof = Vec::new();
+    for point in [&abar, &bbar, &d] {
+        proof.extend(point_to_bytes(point));
+    }
+    for scalar in [e_tilde + e * c, r1_tilde - r1 * c, r3_tilde - r3 * c] {
+        proof.extend(scalar_to_bytes(&scalar));
+    }
+    for (&j, m) in undisclosed.iter().zip(&m_tilde) {
+        proof.extend(scalar_to_bytes(&(*m + messages[j] * c)));
+    }
+    proof.extend(scalar_to_bytes(&c));
+    proof
+}
+
+/// Checks a proof against the disclosed `(zero-based index, message)` pairs.
+pub fn verify_proof(
+    public_key: &[u8],
+    proof: &[u8],
+    header: &[u8],
+    presentation_header: &[u8],
+    disclosed: &[(usize, Fr)],
+) -> bool {
+    assert!(
+        proof.len() >= PROOF_FIXED_LEN
+            && (proof.len() - PROOF_FIXED_LEN).is_multiple_of(SCALAR_LEN),
+        "Proof has an invalid length"
+    );
+    let point =
+        |i: usize| point_from_bytes(&proof[i * POINT_LEN..(i + 1) * POINT_LEN], "proof point");
+    let scalars: Vec<Fr> = proof[3 * POINT_LEN..]
+        .chunks(SCALAR_LEN)
+        .map(|chunk| scalar_from_bytes(chunk, "Proof scalar"))
+        .collect();
+    let (abar, bbar, d) = (point(0), point(1), point(2));
+    let (e_hat, r1_hat, r3_hat) = (scalars[0], scalars[1], scalars[2]);
+    let m_hat = &scalars[3..scalars.len() - 1];
+    let c = scalars[scalars.len() - 1];
+
+    let count = disclosed.len() + m_hat.len();
+    if disclosed.iter().any(|(i, _)| *i >= count) {
+        return false;
+    }
+    let undisclosed: Vec<usize> = (0..count)
+        .filter(|i| !disclosed.iter().any(|(j, _)| j == i))
+        .collect();
+    let generators = Generators::new(count);
+    let domain = generators.domain(public_key, header);
+
+    let t1 = mul(&bbar, &c) + mul(&abar, &e_hat) + mul(&d, &r1_hat);
+    let bv = generators.commitment(&domain, disclosed.iter().map(|(i, m)| (*i, m)));
+    let t2 = undisclosed
+        .iter()
+        .zip(m_hat)
+        .fold(mul(&bv, &c) + mul(&d, &r3_hat), |acc, (&j, m)| {
+            acc + mul(&generators.h[j], m)
+        });
+
+    challenge(
+        &abar,
+        &bbar,
+        &d,
+        &t1,
+        &t2,
+        disclosed,
+        &domain,
+        presentation_header,
+    ) == c
+        && pairing_check(&[
+            (abar, decode_public_key(public_key)),
+            (bbar, -G2Projective::generator()),
+        ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// With Ā = B̄ = identity the pairing check is vacuous and the responses can be simulated,
+    /// which would make a proof for any key and any disclosed messages.
+    #[test]
+    #[should_panic(expected = "must not be the identity")]
+    fn rejects_identity_proof_points() {
+        let (_, public_key) = keygen();
+        let messages = messages_to_scalars(&["disclosed".to_string(), "hidden".to_string()]);
+        let disclosed = [(0, messages[0])];
+        let generators = Generators::new(2);
+        let domain = generators.domain(&public_key, b"");
+        let bv = generators.commitment(&domain, disclosed.iter().map(|(i, m)| (*i, m)));
+        let identity = G1Projective::zero();
+        let d = bv;
+        let random = || Fr::rand(&mut OsRng);
+        let (r1_tilde, a, m_tilde) = (random(), random(), random());
+        let t1 = mul(&d, &r1_tilde);
+        let t2 = mul(&bv, &a) + mul(&generators.h[1], &m_tilde);
+        let c = challenge(&identity, &identity, &d, &t1, &t2, &disclosed, &domain, b"");
+
+        let mut proof = Vec::new();
+        for point in [&identity, &identity, &d] {
+            proof.extend(point_to_bytes(point));
+        }
+        for scalar in [Fr::ONE, r1_tilde, a - c, m_tilde, c] {
+            proof.extend(scalar_to_bytes(&scalar));
+        }
+        verify_proof(&public_key, &proof, b"", b"", &disclosed);
+    }
+}
@@ -0,0 +1,107 @@
+//! A chained threshold randomness beacon over BN254.
+//!
+//! A dealer splits a group secret into `n` Shamir shares so that any `t` of them can sign.
+//! Round `r` signs `keccak256(previous_signature ‖ r)`, with `r` as an 8-byte big-endian
+//! integer and an empty previous signature for the first round, so each round commits to the
+//! whole history before it. Each member contributes a signature with its share; any `t`
+//! contributions are combined by Lagrange interpolation at zero into the group signature,
+//! which is an ordinary BLS signature under the group key. The round's randomness is
+//! `keccak256(signature)`.
+//!
+//! Signatures and keys use the same encodings as the default `sylow` backend.
+
+use rand_core::OsRng;
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+use sylow::{FieldExtensionTrait, Fp, Fr, G1Affine, G1Projective, G2Affine};
+
+use crate::backend::sylow::{decode_signature, SylowBackend};
+use crate::backend::Backend;
+use crate::fixed_base::g2_generator_mul;
+
+fn public_key(secret: &Fr) -> String {
+    hex::encode(G2Affine::from(g2_generator_mul(&Fp::from(secret))).to_be_bytes())
+}
+
+/// The message signed in `round`.
+fn round_message(round: u64, previous_signature: &[u8]) -> [u8; 32] {
+    Keccak256::new()
+        .chain_update(previous_signature)
+        .chain_update(round.to_be_bytes())
+        .finalize()
+        .into()
+}
+
+/// Splits a fresh group secret into `shares` shares, any `threshold` of which can sign.
+pub fn deal(threshold: usize, shares: usize) -> Value {
+    assert!(threshold >= 1, "Threshold must be at least 1");
+    assert!(
+        threshold <= shares,
+        "Threshold cannot exceed the number of shares"
+    );
+    let coefficients: Vec<Fr> = (0..threshold).map(|_| Fr::rand(&mut OsRng)).collect();
+    let members: Vec<Value> = (1..=shares as u64)
+        .map(|index| {
+            let x = Fr::from(index);
+            // Horner's rule, highest coefficient first
+            let share = coefficients
+                .iter()
+                .rev()
+                .fold(Fr::ZERO, |acc, coefficient| acc * x + *coefficient);
+            json!({
+                "index": index,
+                "secretShare": hex::encode(Fp::from(share).to_be_bytes()),
+                "publicShare": public_key(&share),
+            })
+        })
+        .collect();
+    json!({
+        "threshold": threshold,
+        "groupKey": public_key(&coefficients[0]),
+        "shares": members,
+    })
+}
+
+/// Signs `round` with one member's secret share.
+pub fn contribute(secret_share: &[u8], round: u64, previous_signature: &[u8]) -> Vec<u8> {
+    SylowBackend::default().sign(secret_share, &round_message(round, previous_signature))
+}
+
+/// Interpolates member contributions, given as `(index, signature)`, into the group signature.
+pub fn combine(contributions: &[(u64, Vec<u8>)]) -> Vec<u8> {
+    let indexes: Vec<u64> = contributions.iter().map(|(index, _)| *index).collect();
+    for (position, index) in indexes.iter().enumerate() {
+        assert!(*index != 0, "Share indexes start at 1");
+        assert!(
+            !indexes[..position].contains(index),
+            "Share {} contributed twice",
+            index
+        );
+    }
+    let signature = contributions
+        .iter()
+        .fold(G1Projective::zero(), |acc, (i, signature)| {
+            // λᵢ = Π_{j≠i} j / (j − i)
+            let xi = Fr::from(*i);
+            let lambda = indexes.iter().filter(|j| *j != i).fold(Fr::ONE, |acc, j| {
+                let xj = Fr::from(*j);
+                acc * (xj / (xj - xi))
+            });
+            acc + decode_signature(signature) * Fp::from(lambda)
+        });
+    G1Affine::from(signature).to_be_bytes().to_vec()
+}
+
+/// Checks a round's group signature, reporting the randomness it yields.
+pub fn verify(group_key: &[u8], round: u64, previous_signature: &[u8], signature: &[u8]) -> Value {
+    let valid = SylowBackend::default().verify(
+        signature,
+        group_key,
+        &round_message(round, previous_signature),
+    );
+    json!({
+        "valid": valid,
+        "round": round,
+        "randomness": hex::encode(Keccak256::digest(signature)),
+    })
+}
@@ -0,0 +1,83 @@
+//! Proof bundles for knowledge-asset publishing.
+//!
+//! A bundle collects what the publishing flow attaches to a knowledge asset: the committee
+//! signatures over an assertion, folded into one aggregate, the bitfield naming who signed, and
+//! the calldata handing both to the verifier contract. The signed message is the raw bytes of
+//! the assertion id.
+//!
+//! The committee file is a JSON array of hex public keys; a member's position in it is their
+//! bit in the bitfield. Each signature is matched to its signer by verifying it, so they can be
+//! given in any order, and one that no member made is rejected rather than bundled.
+
+use std::fs;
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::backend::sylow::SylowBackend;
+use crate::backend::Backend;
+use crate::calldata_profile::CalldataProfile;
+use crate::evm;
+
+/// Reads a committee file.
+pub fn read_committee(path: &Path) -> Vec<Vec<u8>> {
+    let contents = fs::read_to_string(path).expect("Failed to read committee file");
+    let keys: Vec<String> =
+        serde_json::from_str(&contents).expect("Committee file is not a JSON array of keys");
+    keys.iter()
+        .map(|key| hex::decode(key).expect("Invalid hex in committee public key"))
+        .collect()
+}
+
+/// Bundles `signatures` by `committee` members over `assertion_id` for the contract function
+/// `method`, with the aggregate laid out by `profile`.
+pub fn bundle(
+    profile: &CalldataProfile,
+    assertion_id: &[u8],
+    signatures: &[Vec<u8>],
+    committee: &[Vec<u8>],
+    method: &str,
+) -> Value {
+    assert!(!signatures.is_empty(), "A bundle needs at least one signature");
+    let backend = SylowBackend::default();
+    let mut signers: Vec<usize> = signatures
+        .iter()
+        .map(|signature| {
+            committee
+                .iter()
+                .position(|key| backend.verify(signature, key, assertion_id))
+                .unwrap_or_else(|| {
+                    panic!(
+                        "Signature {} is not by any committee member",
+                        hex::encode(signature)
+                    )
+                })
+        })
+        .collect();
+    signers.sort_unstable();
+    assert!(
+        signers.windows(2).all(|pair| pair[0] != pair[1]),
+        "A committee member signed more than once"
+    );
+
+    let aggregate_signature = backend.aggregate_signatures(signatures);
+    let signer_keys: Vec<Vec<u8>> = signers.iter().map(|&i| committee[i].clone()).collect();
+    let aggregate_public_key = backend.aggregate_public_keys(&signer_keys);
+    let bitfield = evm::bitfield(&signers, committee.len());
+    let calldata =
+        evm::submit_calldata(profile, method, &aggregate_signature, &bitfield, assertion_id);
+
+    json!({
+        "assertionId": hex::encode(assertion_id),
+        "committeeSize": committee.len(),
+        "signers": signers,
+        "bitfield": hex::encode(&bitfield),
+        "aggregateSignature": hex::encode(&aggregate_signature),
+        "aggregatePublicKey": hex::encode(&aggregate_public_key),
+        "valid": backend.verify(&aggregate_signature, &aggregate_public_key, assertion_id),
+        "calldata": {
+            "function": evm::submit_signature(profile, method),
+            "data": format!("0x{}", hex::encode(calldata)),
+        },
+    })
+}
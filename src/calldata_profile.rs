@@ -0,0 +1,193 @@
+//! Calldata profiles: how points are laid out in EVM words.
+//!
+//! Chains and verifier contracts disagree on how a point is written into calldata, above all
+//! which 𝔾₂ coefficient comes first, and a point in the wrong layout is not rejected but simply
+//! fails to verify on-chain. `--calldata-profile` picks the layout used by `convert --to evm`
+//! and `--from evm` and by every command that writes or reads calldata:
+//!
+//! - `bn254-precompile`: EIP-196/197, 32-byte big-endian elements, 𝔾₂ `c1` first (the default
+//!   on BN254);
+//! - `bls12381-eip2537`: EIP-2537, big-endian elements zero-padded to 64 bytes, 𝔾₂ `c0` first
+//!   (the default on BLS12-381);
+//! - `gnosis`: the EIP-2537 precompiles as deployed on Gnosis Chain, so the same layout;
+//! - a path to a JSON file describing a custom layout:
+//!
+//! ```json
+//! { "curve": "bn254", "g2Order": "c0-first", "endianness": "big", "elementLen": 32 }
+//! ```
+//!
+//! Every base field element is written on `elementLen` bytes, a multiple of 32: big-endian
+//! elements are padded with zeros in front, little-endian ones behind. The point at infinity is
+//! all zeros in every profile. `curve` is optional; when given, using the profile for points of
+//! another curve is an error rather than a silently wrong encoding.
+
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::convert;
+use crate::point::Group;
+
+const WORD: usize = 32;
+
+#[derive(Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum CoefficientOrder {
+    C1First,
+    C0First,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Endianness {
+    Big,
+    Little,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct CalldataProfile {
+    #[serde(skip)]
+    name: String,
+    curve: Option<String>,
+    g2_order: CoefficientOrder,
+    endianness: Endianness,
+    element_len: usize,
+}
+
+impl CalldataProfile {
+    fn builtin(name: &str) -> Option<Self> {
+        let (curve, g2_order, element_len) = match name {
+            "bn254-precompile" => ("bn254", CoefficientOrder::C1First, 32),
+            "bls12381-eip2537" | "gnosis" => ("bls12-381", CoefficientOrder::C0First, 64),
+            _ => return None,
+        };
+        Some(CalldataProfile {
+            name: name.to_string(),
+            curve: Some(curve.to_string()),
+            g2_order,
+            endianness: Endianness::Big,
+            element_len,
+        })
+    }
+
+    /// The profile called `name`, or the custom one in the JSON file at that path.
+    pub fn load(name: &str) -> Self {
+        if let Some(profile) = Self::builtin(name) {
+            return profile;
+        }
+        let path = Path::new(name);
+        assert!(
+            path.exists(),
+            "Unknown calldata profile {}: not bn254-precompile, bls12381-eip2537, gnosis or a file",
+            name
+        );
+        let contents = fs::read_to_string(path).expect("Failed to read calldata profile");
+        let mut profile: CalldataProfile =
+            serde_json::from_str(&contents).expect("Malformed calldata profile");
+        assert!(
+            profile.element_len > 0 && profile.element_len.is_multiple_of(WORD),
+            "Calldata profile elements must be a positive multiple of 32 bytes"
+        );
+        profile.name = name.to_string();
+        profile
+    }
+
+    /// The precompile layout of `curve`.
+    pub fn default_for(curve: &str) -> Self {
+        match curve {
+            "bn254" => Self::builtin("bn254-precompile"),
+            "bls12-381" => Self::builtin("bls12381-eip2537"),
+            _ => None,
+        }
+        .unwrap_or_else(|| panic!("{} points have no EVM encoding", curve))
+    }
+
+    fn check(&self, curve: &str) {
+        if let Some(profile_curve) = &self.curve {
+            assert!(
+                profile_curve == curve,
+                "Calldata profile {} is for {} points, not {}",
+                self.name,
+                profile_curve,
+                curve
+            );
+        }
+        assert!(
+            self.element_len >= convert::field_len(curve),
+            "Calldata profile {} elements are too short for {} points",
+            self.name,
+            curve
+        );
+    }
+
+    /// Words taken by a point of `group`.
+    pub fn words(&self, group: Group) -> usize {
+        let elements = if group == Group::G1 { 2 } else { 4 };
+        elements * self.element_len / WORD
+    }
+
+    /// The calldata layout of an uncompressed point of `curve`.
+    pub fn encode(&self, curve: &str, uncompressed: &[u8]) -> Vec<u8> {
+        self.check(curve);
+        let len = convert::field_len(curve);
+        if convert::is_infinity(curve, uncompressed) {
+            return vec![0u8; uncompressed.len() / len * self.element_len];
+        }
+        let ordered = match self.g2_order {
+            CoefficientOrder::C1First => uncompressed.to_vec(),
+            CoefficientOrder::C0First => convert::swap_coefficients(curve, uncompressed),
+        };
+        let padding = vec![0u8; self.element_len - len];
+        ordered
+            .chunks(len)
+            .flat_map(|element| match self.endianness {
+                Endianness::Big => [&padding[..], element].concat(),
+                Endianness::Little => {
+                    let reversed: Vec<u8> = element.iter().rev().copied().collect();
+                    [reversed, padding.clone()].concat()
+                }
+            })
+            .collect()
+    }
+
+    /// The uncompressed point of `curve` laid out as `bytes`.
+    pub fn decode(&self, curve: &str, bytes: &[u8]) -> Vec<u8> {
+        self.check(curve);
+        let len = convert::field_len(curve);
+        assert!(
+            bytes.len().is_multiple_of(self.element_len),
+            "Calldata profile {} values are made of {}-byte elements",
+            self.name,
+            self.element_len
+        );
+        let pad = self.element_len - len;
+        let ordered: Vec<u8> = bytes
+            .chunks(self.element_len)
+            .flat_map(|element| {
+                let (padding, value) = match self.endianness {
+                    Endianness::Big => (&element[..pad], element[pad..].to_vec()),
+                    Endianness::Little => (
+                        &element[len..],
+                        element[..len].iter().rev().copied().collect(),
+                    ),
+                };
+                assert!(
+                    padding.iter().all(|&byte| byte == 0),
+                    "Calldata profile {} elements must be zero-padded",
+                    self.name
+                );
+                value
+            })
+            .collect();
+        let group = convert::group(curve, &ordered);
+        if ordered.iter().all(|&byte| byte == 0) {
+            return convert::infinity(curve, group);
+        }
+        match self.g2_order {
+            CoefficientOrder::C1First => ordered,
+            CoefficientOrder::C0First => convert::swap_coefficients(curve, &ordered),
+        }
+    }
+}
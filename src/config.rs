@@ -0,0 +1,63 @@
+//! User configuration.
+//!
+//! Settings live in a JSON file named by `--config`, falling back to
+//! `~/.config/bls-tools/config.json` when that exists. Every section is optional, so a missing
+//! file and an empty object behave the same.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+#[cfg(feature = "blst")]
+use crate::suite::SuiteDefinition;
+use crate::template::Template;
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    /// Extra ciphersuites for `--suite`, by name
+    #[cfg(feature = "blst")]
+    #[serde(default)]
+    pub suites: BTreeMap<String, SuiteDefinition>,
+    /// Identity file decrypting age-encrypted secret key files
+    #[cfg(feature = "age")]
+    #[serde(default)]
+    pub age_identity: Option<PathBuf>,
+    /// Structured message layouts for `--template`, by name
+    #[serde(default)]
+    pub templates: BTreeMap<String, Template>,
+}
+
+fn default_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(Path::new(&home).join(".config/bls-tools/config.json"))
+}
+
+/// The file settings are read from: `path` if given, else the default location.
+pub fn path(path: Option<&Path>) -> Option<PathBuf> {
+    path.map(Path::to_path_buf).or_else(default_path)
+}
+
+impl Config {
+    /// Reads the configuration from `path`, or from the default location if none is given.
+    pub fn load(path: Option<&Path>) -> Self {
+        Self::try_load(path).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like [`Config::load`], but returns what went wrong instead of panicking.
+    pub fn try_load(path: Option<&Path>) -> Result<Self, String> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_path().filter(|path| path.exists()) {
+                Some(path) => path,
+                None => return Ok(Config::default()),
+            },
+        };
+        let contents = fs::read_to_string(&path)
+            .map_err(|error| format!("Failed to read config file: {}", error))?;
+        serde_json::from_str(&contents)
+            .map_err(|error| format!("Config file is not valid: {}", error))
+    }
+}
@@ -0,0 +1,215 @@
+//! Conformance runs against the RFC 9380 hash-to-curve test vectors.
+//!
+//! `conformance hash-to-curve --vectors <file>` takes one of the JSON files published with the
+//! RFC (in the format of the `poc/vectors` directory of the draft's repository) and runs every
+//! vector through each implementation in this build that claims the file's suite:
+//!
+//! - `expand_message_xof` files with SHAKE128 or SHAKE256 go through the expander `--expander
+//!   xof` uses for BN254;
+//! - `BLS12381G1_XMD:SHA-256_SSWU_RO_` goes through blst and arkworks, and
+//!   `BLS12381G2_XMD:SHA-256_SSWU_RO_` through blst (`blst` and `arkworks` features);
+//! - `BLS12377G1_XMD:SHA-256_SSWU_RO_` goes through arkworks.
+//!
+//! Points are hashed as a signature under the secret key 1, which is exactly the hash of the
+//! message, so the check covers the same code path signing uses. Files for any other suite or
+//! expander are refused rather than reported as passing.
+
+use std::fs;
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::hash_to_curve::{ExpanderKind, HashParams, HashToCurve};
+
+/// Hashes a message under a DST, returning the point's coordinates big-endian, in the order
+/// the vectors list them (`x`, then `y`; `c0` before `c1`).
+type Hasher = fn(&[u8], &[u8]) -> Vec<Vec<u8>>;
+
+/// The secret key 1, big-endian.
+#[cfg(feature = "blst")]
+fn one() -> [u8; 32] {
+    let mut one = [0u8; 32];
+    one[31] = 1;
+    one
+}
+
+#[cfg(feature = "blst")]
+fn blst_g1(dst: &[u8], msg: &[u8]) -> Vec<Vec<u8>> {
+    let secret = ::blst::min_sig::SecretKey::from_bytes(&one()).unwrap();
+    let point = secret.sign(msg, dst, &[]).serialize();
+    point.chunks(48).map(<[u8]>::to_vec).collect()
+}
+
+/// blst serializes 𝔾₂ points `x.c1 ‖ x.c0 ‖ y.c1 ‖ y.c0`.
+#[cfg(feature = "blst")]
+fn blst_g2(dst: &[u8], msg: &[u8]) -> Vec<Vec<u8>> {
+    let secret = ::blst::min_pk::SecretKey::from_bytes(&one()).unwrap();
+    let point = secret.sign(msg, dst, &[]).serialize();
+    let chunks: Vec<&[u8]> = point.chunks(48).collect();
+    [chunks[1], chunks[0], chunks[3], chunks[2]]
+        .iter()
+        .map(|chunk| chunk.to_vec())
+        .collect()
+}
+
+#[cfg(feature = "arkworks")]
+fn arkworks_g1<C>(dst: &[u8], msg: &[u8]) -> Vec<Vec<u8>>
+where
+    C: ark_ec::hashing::curve_maps::wb::WBConfig,
+    C::BaseField: ark_ff::PrimeField,
+{
+    use ark_ec::hashing::curve_maps::wb::WBMap;
+    use ark_ec::hashing::map_to_curve_hasher::MapToCurveBasedHasher;
+    use ark_ec::hashing::HashToCurve;
+    use ark_ec::short_weierstrass::Projective;
+    use ark_ff::field_hashers::DefaultFieldHasher;
+    use ark_ff::{BigInteger, PrimeField};
+
+    type Hasher<C> =
+        MapToCurveBasedHasher<Projective<C>, DefaultFieldHasher<sha2::Sha256, 128>, WBMap<C>>;
+    let point = Hasher::<C>::new(dst)
+        .and_then(|hasher| hasher.hash(msg))
+        .expect("Hashing failed");
+    vec![
+        point.x.into_bigint().to_bytes_be(),
+        point.y.into_bigint().to_bytes_be(),
+    ]
+}
+
+/// The implementations in this build that claim `suite`.
+#[cfg_attr(not(any(feature = "blst", feature = "arkworks")), allow(unused_mut))]
+fn implementations(suite: &str) -> Vec<(&'static str, Hasher)> {
+    let mut found: Vec<(&'static str, Hasher)> = Vec::new();
+    match suite {
+        "BLS12381G1_XMD:SHA-256_SSWU_RO_" => {
+            #[cfg(feature = "blst")]
+            found.push(("blst", blst_g1));
+            #[cfg(feature = "arkworks")]
+            found.push(("arkworks", arkworks_g1::<ark_bls12_381::g1::Config>));
+        }
+        "BLS12381G2_XMD:SHA-256_SSWU_RO_" => {
+            #[cfg(feature = "blst")]
+            found.push(("blst", blst_g2));
+        }
+        "BLS12377G1_XMD:SHA-256_SSWU_RO_" => {
+            #[cfg(feature = "arkworks")]
+            found.push(("arkworks", arkworks_g1::<ark_bls12_377::g1::Config>));
+        }
+        _ => {}
+    }
+    found
+}
+
+fn string<'a>(value: &'a Value, field: &str) -> &'a str {
+    value[field]
+        .as_str()
+        .unwrap_or_else(|| panic!("Vectors file is missing {}", field))
+}
+
+fn hex_field(value: &Value, field: &str) -> Vec<u8> {
+    let text = string(value, field);
+    hex::decode(text.trim_start_matches("0x"))
+        .unwrap_or_else(|_| panic!("Invalid hex in {}", field))
+}
+
+/// A point's coordinates as the vectors write them, `x` and `y` each split at commas into
+/// their `c0, c1, …` components.
+fn coordinates(point: &Value) -> Vec<Vec<u8>> {
+    ["x", "y"]
+        .iter()
+        .flat_map(|axis| string(point, axis).split(','))
+        .map(|part| {
+            hex::decode(part.trim().trim_start_matches("0x"))
+                .expect("Invalid hex in point coordinate")
+        })
+        .collect()
+}
+
+fn report(name: &str, results: Vec<Value>) -> Value {
+    let passed = results.iter().filter(|result| result["pass"] == true).count();
+    json!({
+        "suite": name,
+        "valid": passed == results.len(),
+        "passed": passed,
+        "failed": results.len() - passed,
+        "results": results,
+    })
+}
+
+fn check_suite(vectors: &Value) -> Value {
+    let suite = string(vectors, "ciphersuite");
+    let implementations = implementations(suite);
+    assert!(
+        !implementations.is_empty(),
+        "No implementation of {} in this build",
+        suite
+    );
+    let dst = string(vectors, "dst").as_bytes();
+    let cases = vectors["vectors"]
+        .as_array()
+        .expect("Vectors file is missing vectors");
+    let mut results = Vec::new();
+    for (index, case) in cases.iter().enumerate() {
+        let msg = string(case, "msg").as_bytes();
+        let expected = coordinates(&case["P"]);
+        for (implementation, hash) in &implementations {
+            results.push(json!({
+                "vector": index,
+                "implementation": implementation,
+                "pass": hash(dst, msg) == expected,
+            }));
+        }
+    }
+    report(suite, results)
+}
+
+fn check_expander(vectors: &Value) -> Value {
+    let name = string(vectors, "name");
+    let hash = string(vectors, "hash");
+    let security_bits = vectors["k"].as_u64().expect("Vectors file is missing k");
+    let expected_hash = if security_bits <= 128 {
+        "SHAKE128"
+    } else {
+        "SHAKE256"
+    };
+    assert!(
+        name == "expand_message_xof" && hash == expected_hash,
+        "No implementation of {} with {} at k = {} in this build",
+        name,
+        hash,
+        security_bits
+    );
+    let hasher = HashToCurve {
+        params: HashParams::new(ExpanderKind::Xof, security_bits, None),
+        dst: string(vectors, "DST").as_bytes(),
+    };
+    let cases = vectors["tests"]
+        .as_array()
+        .expect("Vectors file is missing tests");
+    let results = cases
+        .iter()
+        .enumerate()
+        .map(|(index, case)| {
+            let len = string(case, "len_in_bytes").trim_start_matches("0x");
+            let len = usize::from_str_radix(len, 16).expect("Invalid len_in_bytes");
+            let output = hasher.expand_message(string(case, "msg").as_bytes(), len);
+            json!({
+                "vector": index,
+                "implementation": "bls-tools",
+                "pass": output == hex_field(case, "uniform_bytes"),
+            })
+        })
+        .collect();
+    report(&format!("{}_{}", name, hash), results)
+}
+
+/// Runs the vectors in `path`, a hash-to-curve suite or message expander file.
+pub fn hash_to_curve(path: &Path) -> Value {
+    let contents = fs::read_to_string(path).expect("Failed to read vectors file");
+    let vectors: Value = serde_json::from_str(&contents).expect("Vectors file is not valid JSON");
+    if vectors.get("ciphersuite").is_some() {
+        check_suite(&vectors)
+    } else {
+        check_expander(&vectors)
+    }
+}
@@ -0,0 +1,234 @@
+//! Conversion of keys and signatures between the encodings other tooling expects.
+//!
+//! Every point goes through its uncompressed encoding, with 𝔾₂ coordinates `c1 ‖ c0` as sylow,
+//! blst and the zcash format all write them, and is checked to be on the curve on the way:
+//!
+//! - `compressed`: the zcash format with flag bits, 48 or 96 bytes (BLS12-381 only);
+//! - `uncompressed`: `x ‖ y`, 𝔾₂ coordinates `c1 ‖ c0`;
+//! - `uncompressed-c0-first`: the same with `c0 ‖ c1`, as gnark and most circuit tooling want;
+//! - `ssz`: the `Bytes48`/`Bytes96` of consensus-layer JSON, `0x` and the compressed point;
+//! - `evm`: precompile input, `0x`-prefixed, laid out by `--calldata-profile`: by default
+//!   EIP-197 for BN254 (the uncompressed encoding, with infinity as zeros) and EIP-2537 for
+//!   BLS12-381 (coordinates padded to 64 bytes, 𝔾₂ `c0` first).
+//!
+//! Hex and base64 are offered for the compressed and uncompressed encodings. The curve is the
+//! one of `--backend`; which group a value is in follows from its length.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use clap::ValueEnum;
+
+use crate::calldata_profile::CalldataProfile;
+use crate::point::{self, Group};
+
+/// Formats selectable with `convert --from` and `--to`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Compressed point as hex (BLS12-381)
+    HexCompressed,
+    /// Uncompressed point as hex, 𝔾₂ coordinates c1 first
+    HexUncompressed,
+    /// Uncompressed point as hex, 𝔾₂ coordinates c0 first
+    HexUncompressedC0First,
+    /// Compressed point as base64 (BLS12-381)
+    Base64Compressed,
+    /// Uncompressed point as base64, 𝔾₂ coordinates c1 first
+    Base64Uncompressed,
+    /// 0x-prefixed compressed point, as in consensus-layer JSON (BLS12-381)
+    Ssz,
+    /// 0x-prefixed precompile input (EIP-197 or EIP-2537)
+    Evm,
+}
+
+enum Layout {
+    Compressed,
+    Uncompressed,
+    C0First,
+    Evm,
+}
+
+impl Format {
+    fn layout(self) -> Layout {
+        match self {
+            Format::HexCompressed | Format::Base64Compressed | Format::Ssz => Layout::Compressed,
+            Format::HexUncompressed | Format::Base64Uncompressed => Layout::Uncompressed,
+            Format::HexUncompressedC0First => Layout::C0First,
+            Format::Evm => Layout::Evm,
+        }
+    }
+
+    fn decode_text(self, value: &str) -> Vec<u8> {
+        match self {
+            Format::Base64Compressed | Format::Base64Uncompressed => {
+                STANDARD.decode(value).expect("Invalid base64 in value")
+            }
+            _ => hex::decode(value.trim_start_matches("0x")).expect("Invalid hex in value"),
+        }
+    }
+
+    fn encode_text(self, bytes: &[u8]) -> String {
+        match self {
+            Format::Base64Compressed | Format::Base64Uncompressed => STANDARD.encode(bytes),
+            Format::Ssz | Format::Evm => format!("0x{}", hex::encode(bytes)),
+            _ => hex::encode(bytes),
+        }
+    }
+}
+
+/// Bytes of a base field element.
+pub fn field_len(curve: &str) -> usize {
+    match curve {
+        "bn254" => 32,
+        "bls12-381" => 48,
+        _ => panic!("Converting {} points is not supported", curve),
+    }
+}
+
+pub fn group(curve: &str, uncompressed: &[u8]) -> Group {
+    let len = field_len(curve);
+    match uncompressed.len() {
+        n if n == 2 * len => Group::G1,
+        n if n == 4 * len => Group::G2,
+        n => panic!("{}-byte values are not {} points", n, curve),
+    }
+}
+
+/// Swaps `c0` and `c1` of both 𝔾₂ coordinates; 𝔾₁ points pass unchanged.
+pub fn swap_coefficients(curve: &str, bytes: &[u8]) -> Vec<u8> {
+    let len = field_len(curve);
+    if bytes.len() != 4 * len {
+        return bytes.to_vec();
+    }
+    [
+        &bytes[len..2 * len],
+        &bytes[..len],
+        &bytes[3 * len..],
+        &bytes[2 * len..3 * len],
+    ]
+    .concat()
+}
+
+/// The encoding of the point at infinity in `group`.
+pub fn infinity(curve: &str, group: Group) -> Vec<u8> {
+    match curve {
+        "bn254" => point::add(group, &[]),
+        _ => {
+            let mut bytes = vec![0u8; if group == Group::G1 { 96 } else { 192 }];
+            bytes[0] = 0x40;
+            bytes
+        }
+    }
+}
+
+pub fn is_infinity(curve: &str, uncompressed: &[u8]) -> bool {
+    let flag = if curve == "bn254" { 0x80 } else { 0x40 };
+    uncompressed[0] & flag != 0
+}
+
+#[cfg(feature = "blst")]
+mod bls12_381 {
+    use ::blst::min_pk::{PublicKey, Signature};
+
+    /// Checks an uncompressed point, 𝔾₁ as min-pk keys and 𝔾₂ as min-pk signatures.
+    pub fn validate(bytes: &[u8]) -> bool {
+        match bytes.len() {
+            96 => PublicKey::deserialize(bytes).is_ok(),
+            _ => Signature::deserialize(bytes).is_ok(),
+        }
+    }
+
+    pub fn compress(bytes: &[u8]) -> Vec<u8> {
+        match bytes.len() {
+            96 => PublicKey::deserialize(bytes).map(|p| p.compress().to_vec()),
+            _ => Signature::deserialize(bytes).map(|p| p.compress().to_vec()),
+        }
+        .expect("Not a valid point")
+    }
+
+    /// The uncompressed encoding of a compressed point, or `None` if it is not one.
+    pub fn decompress(bytes: &[u8]) -> Option<Vec<u8>> {
+        match bytes.len() {
+            48 => PublicKey::uncompress(bytes).map(|p| p.serialize().to_vec()).ok(),
+            96 => Signature::uncompress(bytes).map(|p| p.serialize().to_vec()).ok(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(not(feature = "blst"))]
+mod bls12_381 {
+    pub fn validate(_: &[u8]) -> bool {
+        panic!("Converting bls12-381 points needs the blst feature")
+    }
+
+    pub fn compress(_: &[u8]) -> Vec<u8> {
+        panic!("Converting bls12-381 points needs the blst feature")
+    }
+
+    pub fn decompress(_: &[u8]) -> Option<Vec<u8>> {
+        panic!("Converting bls12-381 points needs the blst feature")
+    }
+}
+
+fn on_curve(curve: &str, uncompressed: &[u8]) -> bool {
+    match curve {
+        "bn254" => point::is_on_curve(group(curve, uncompressed), uncompressed),
+        _ => bls12_381::validate(uncompressed),
+    }
+}
+
+fn validate(curve: &str, uncompressed: &[u8]) {
+    group(curve, uncompressed);
+    assert!(on_curve(curve, uncompressed), "Not a valid {} point", curve);
+}
+
+fn assert_compressible(curve: &str) {
+    assert!(curve == "bls12-381", "{} points have no compressed encoding", curve);
+}
+
+/// Converts `value` from one format to another for points on `curve`, laying out `evm` values
+/// by `profile`.
+pub fn convert(
+    curve: &str,
+    from: Format,
+    to: Format,
+    value: &str,
+    profile: &CalldataProfile,
+) -> String {
+    let bytes = from.decode_text(value.trim());
+    let uncompressed = match from.layout() {
+        Layout::Compressed => {
+            assert_compressible(curve);
+            bls12_381::decompress(&bytes).unwrap_or_else(|| panic!("Not a valid {} point", curve))
+        }
+        Layout::Uncompressed => bytes,
+        Layout::C0First => swap_coefficients(curve, &bytes),
+        Layout::Evm => profile.decode(curve, &bytes),
+    };
+    validate(curve, &uncompressed);
+    let output = match to.layout() {
+        Layout::Compressed => {
+            assert_compressible(curve);
+            bls12_381::compress(&uncompressed)
+        }
+        Layout::Uncompressed => uncompressed,
+        Layout::C0First => swap_coefficients(curve, &uncompressed),
+        Layout::Evm => profile.encode(curve, &uncompressed),
+    };
+    to.encode_text(&output)
+}
+
+/// Whether `bytes` are a point of `curve` in the compressed, uncompressed or `c0`-first
+/// layout of `format`.
+pub fn is_valid(curve: &str, format: Format, bytes: &[u8]) -> bool {
+    let uncompressed = match format.layout() {
+        Layout::Compressed if curve == "bls12-381" => bls12_381::decompress(bytes),
+        Layout::Uncompressed => Some(bytes.to_vec()),
+        Layout::C0First => Some(swap_coefficients(curve, bytes)),
+        _ => None,
+    };
+    let len = field_len(curve);
+    uncompressed.is_some_and(|uncompressed| {
+        [2 * len, 4 * len].contains(&uncompressed.len()) && on_curve(curve, &uncompressed)
+    })
+}
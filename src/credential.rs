@@ -0,0 +1,145 @@
+//! Data Integrity proofs for W3C Verifiable Credentials.
+//!
+//! `vc sign` attaches a `DataIntegrityProof` block to a credential. The signed bytes are
+//! `keccak256(proof options) ‖ keccak256(credential)`, where both halves are canonicalised
+//! JCS-style (object keys sorted, no insignificant whitespace) and the options are the proof
+//! block without its `proofValue`. The cryptosuite name records the backend, so `vc verify`
+//! needs nothing but the credential itself. Since it records nothing else, `--suite` and
+//! non-default hash-to-curve parameters are rejected rather than silently dropped.
+//!
+//! The verification method is the signer's hex public key. Verifying a proof only shows that
+//! the credential was signed by that key; checking that the key belongs to the claimed issuer
+//! is left to the caller.
+
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+
+use crate::backend::BackendKind;
+
+const CRYPTOSUITE_PREFIX: &str = "bls-tools-";
+const CRYPTOSUITE_SUFFIX: &str = "-jcs-2026";
+/// Multibase prefix for lowercase base16.
+const MULTIBASE_BASE16: char = 'f';
+
+fn cryptosuite(backend: BackendKind) -> String {
+    format!("{}{}{}", CRYPTOSUITE_PREFIX, backend.name(), CRYPTOSUITE_SUFFIX)
+}
+
+fn backend_from_cryptosuite(cryptosuite: &str) -> Option<BackendKind> {
+    let name = cryptosuite
+        .strip_prefix(CRYPTOSUITE_PREFIX)?
+        .strip_suffix(CRYPTOSUITE_SUFFIX)?;
+    BackendKind::from_str(name, false).ok()
+}
+
+/// Formats a Unix timestamp as an XML Schema `dateTime` in UTC, e.g. `2024-05-01T12:00:00Z`.
+pub fn format_datetime(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    // civil-from-days, after Howard Hinnant's algorithm
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
+
+fn canonical_digest(value: &Value) -> [u8; 32] {
+    // serde_json keeps object keys sorted, so compact output is already canonical
+    let canonical = serde_json::to_vec(value).expect("Failed to serialize JSON");
+    Keccak256::digest(canonical).into()
+}
+
+fn signing_input(options: &Value, credential: &Value) -> Vec<u8> {
+    [canonical_digest(options), canonical_digest(credential)].concat()
+}
+
+pub fn read(path: &Path) -> Value {
+    let contents = fs::read_to_string(path).expect("Failed to read credential file");
+    serde_json::from_str(&contents).expect("Credential file is not valid JSON")
+}
+
+/// Returns a copy of `credential` carrying a proof by `secret`.
+pub fn sign(backend: BackendKind, secret: &[u8], credential: &Value) -> Value {
+    let Value::Object(mut document) = credential.clone() else {
+        panic!("Credential must be a JSON object");
+    };
+    document.remove("proof");
+    let document = Value::Object(document);
+
+    let instance = backend.instantiate();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System clock is before the Unix epoch")
+        .as_secs();
+    let mut proof = json!({
+        "type": "DataIntegrityProof",
+        "cryptosuite": cryptosuite(backend),
+        "created": format_datetime(now),
+        "verificationMethod": hex::encode(instance.public_key_from_secret(secret)),
+        "proofPurpose": "assertionMethod",
+    });
+    let signature = instance.sign(secret, &signing_input(&proof, &document));
+    proof["proofValue"] = json!(format!("{}{}", MULTIBASE_BASE16, hex::encode(signature)));
+
+    let mut signed = document;
+    signed["proof"] = proof;
+    signed
+}
+
+/// Checks the proof attached to a credential, reporting the key it was made with.
+pub fn verify(credential: &Value) -> Value {
+    let Value::Object(mut document) = credential.clone() else {
+        panic!("Credential must be a JSON object");
+    };
+    let Some(Value::Object(mut options)) = document.remove("proof") else {
+        panic!("Credential has no proof");
+    };
+    let document = Value::Object(document);
+
+    let field = |name: &str| -> String {
+        options
+            .get(name)
+            .and_then(Value::as_str)
+            .unwrap_or_else(|| panic!("Proof has no {}", name))
+            .to_string()
+    };
+    let suite = field("cryptosuite");
+    let verification_method = field("verificationMethod");
+    let proof_value = field("proofValue");
+    let backend = backend_from_cryptosuite(&suite)
+        .unwrap_or_else(|| panic!("Unsupported cryptosuite {}", suite))
+        .instantiate();
+
+    let signature = proof_value
+        .strip_prefix(MULTIBASE_BASE16)
+        .and_then(|hex_value| hex::decode(hex_value).ok())
+        .expect("proofValue must be base16 multibase");
+    let public_key = hex::decode(&verification_method).expect("Invalid hex in verificationMethod");
+    options.remove("proofValue");
+    let valid = backend.verify(
+        &signature,
+        &public_key,
+        &signing_input(&Value::Object(options), &document),
+    );
+
+    json!({ "valid": valid, "verificationMethod": verification_method })
+}
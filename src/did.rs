@@ -0,0 +1,89 @@
+//! `did:key` identifiers for BLS public keys.
+//!
+//! A `did:key` is the multicodec-tagged public key in base58btc multibase. Multicodec only
+//! assigns codes to BLS12-381 keys, so identifiers exist for the BLS12-381 backends, whose
+//! compressed 96-byte 𝔾₂ keys are `bls12_381-g2-pub` (`0xeb`).
+
+use serde_json::{json, Value};
+
+use crate::backend::BackendKind;
+
+const DID_KEY_PREFIX: &str = "did:key:";
+/// Multibase prefix for base58btc.
+const MULTIBASE_BASE58BTC: char = 'z';
+/// Unsigned-varint encoding of the `bls12_381-g2-pub` multicodec.
+const BLS12_381_G2_PUB: [u8; 2] = [0xeb, 0x01];
+const BLS12_381_G2_PUB_LEN: usize = 96;
+
+fn check_backend(backend: BackendKind) {
+    match backend {
+        #[cfg(feature = "blst")]
+        BackendKind::Blst => {}
+        #[cfg(feature = "arkworks")]
+        BackendKind::ArkworksBls12_381 => {}
+        other => panic!("did:key has no multicodec for {} public keys", other.name()),
+    }
+}
+
+/// Encodes a public key as a `did:key` identifier.
+pub fn from_key(backend: BackendKind, public_key: &[u8]) -> String {
+    check_backend(backend);
+    assert_eq!(
+        public_key.len(),
+        BLS12_381_G2_PUB_LEN,
+        "Public key must be {} bytes",
+        BLS12_381_G2_PUB_LEN
+    );
+    let tagged = [BLS12_381_G2_PUB.as_slice(), public_key].concat();
+    format!(
+        "{}{}{}",
+        DID_KEY_PREFIX,
+        MULTIBASE_BASE58BTC,
+        bs58::encode(tagged).into_string()
+    )
+}
+
+/// Recovers the raw public key from a `did:key` identifier.
+pub fn resolve(did: &str) -> Vec<u8> {
+    let encoded = did
+        .strip_prefix(DID_KEY_PREFIX)
+        .expect("Not a did:key identifier")
+        .strip_prefix(MULTIBASE_BASE58BTC)
+        .expect("did:key must be base58btc multibase");
+    let tagged = bs58::decode(encoded)
+        .into_vec()
+        .expect("Invalid base58 in did:key");
+    let public_key = tagged
+        .strip_prefix(BLS12_381_G2_PUB.as_slice())
+        .expect("did:key is not a bls12_381-g2-pub key");
+    assert_eq!(
+        public_key.len(),
+        BLS12_381_G2_PUB_LEN,
+        "did:key public key must be {} bytes",
+        BLS12_381_G2_PUB_LEN
+    );
+    public_key.to_vec()
+}
+
+/// The DID document a `did:key` resolves to, with the key as its only verification method.
+pub fn document(did: &str) -> Value {
+    let fingerprint = did.strip_prefix(DID_KEY_PREFIX).expect("Not a did:key identifier");
+    let method = format!("{}#{}", did, fingerprint);
+    json!({
+        "@context": [
+            "https://www.w3.org/ns/did/v1",
+            "https://w3id.org/security/multikey/v1",
+        ],
+        "id": did,
+        "verificationMethod": [{
+            "id": method,
+            "type": "Multikey",
+            "controller": did,
+            "publicKeyMultibase": fingerprint,
+        }],
+        "authentication": [method],
+        "assertionMethod": [method],
+        "capabilityDelegation": [method],
+        "capabilityInvocation": [method],
+    })
+}
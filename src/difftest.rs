@@ -0,0 +1,183 @@
+//! Differential testing between backends that implement the same curve.
+//!
+//! Backends on one curve share the ciphersuite and the point encodings (`blst` and
+//! `arkworks-bls12-381` both sign under `BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_` with
+//! compressed zcash points), so for any input they must agree byte-for-byte. Each iteration
+//! draws fresh keys and messages, runs the selected operations through every backend of the
+//! curve and panics at the first disagreement, printing the inputs needed to reproduce it.
+//! Curves with a single backend in the build (BN254, BLS12-377) have nothing to compare to and
+//! are skipped.
+
+use std::collections::BTreeMap;
+
+use clap::ValueEnum;
+use rand_core::{OsRng, RngCore};
+use serde_json::{json, Value};
+
+use crate::backend::{Backend, BackendKind};
+
+/// Longest random message, in bytes.
+const MAX_MESSAGE_LEN: u32 = 128;
+/// Most signers in one random aggregate.
+const MAX_SIGNERS: u32 = 8;
+
+/// Operations selectable with `difftest --ops`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Operation {
+    /// Public key derivation and signing
+    Sign,
+    /// Verification of valid and of tampered signatures
+    Verify,
+    /// Key and signature aggregation, and verification of the aggregate
+    Aggregate,
+}
+
+fn random_message() -> Vec<u8> {
+    let mut message = vec![0u8; (OsRng.next_u32() % (MAX_MESSAGE_LEN + 1)) as usize];
+    OsRng.fill_bytes(&mut message);
+    message
+}
+
+/// The backends of one curve, each with its name.
+struct Group {
+    curve: &'static str,
+    backends: Vec<(String, Box<dyn Backend>)>,
+}
+
+impl Group {
+    /// Runs `operation` through every backend and panics unless they all return the same.
+    fn agree(&self, what: &str, inputs: &Value, operation: impl Fn(&dyn Backend) -> Value) {
+        let results: Vec<(&str, Value)> = self
+            .backends
+            .iter()
+            .map(|(name, backend)| (name.as_str(), operation(backend.as_ref())))
+            .collect();
+        let (first_name, first) = &results[0];
+        for (name, result) in &results[1..] {
+            assert!(
+                result == first,
+                "{} backends disagree on {}: {} returned {}, {} returned {}; inputs {}",
+                self.curve,
+                what,
+                first_name,
+                first,
+                name,
+                result,
+                inputs
+            );
+        }
+    }
+
+    fn reference(&self) -> &dyn Backend {
+        self.backends[0].1.as_ref()
+    }
+
+    fn check_sign(&self) {
+        let (secret, _) = self.reference().generate_keys();
+        let message = random_message();
+        let inputs = json!({ "secretKey": hex::encode(&secret), "message": hex::encode(&message) });
+        self.agree("public key", &inputs, |backend| {
+            json!(hex::encode(backend.public_key_from_secret(&secret)))
+        });
+        self.agree("signature", &inputs, |backend| {
+            json!(hex::encode(backend.sign(&secret, &message)))
+        });
+    }
+
+    fn check_verify(&self) {
+        let (secret, public_key) = self.reference().generate_keys();
+        let message = random_message();
+        let signature = self.reference().sign(&secret, &message);
+        let mut other = message.clone();
+        other.push(OsRng.next_u32() as u8);
+        let inputs = json!({
+            "publicKey": hex::encode(&public_key),
+            "message": hex::encode(&message),
+            "signature": hex::encode(&signature),
+            "otherMessage": hex::encode(&other),
+        });
+        self.agree("validity of a signature", &inputs, |backend| {
+            json!(backend.verify(&signature, &public_key, &message))
+        });
+        self.agree("validity of a signature on another message", &inputs, |backend| {
+            json!(backend.verify(&signature, &public_key, &other))
+        });
+    }
+
+    fn check_aggregate(&self) {
+        let signers = 1 + OsRng.next_u32() % MAX_SIGNERS;
+        let message = random_message();
+        let (public_keys, signatures): (Vec<Vec<u8>>, Vec<Vec<u8>>) = (0..signers)
+            .map(|_| {
+                let (secret, public_key) = self.reference().generate_keys();
+                (public_key, self.reference().sign(&secret, &message))
+            })
+            .unzip();
+        let inputs = json!({
+            "publicKeys": public_keys.iter().map(hex::encode).collect::<Vec<_>>(),
+            "signatures": signatures.iter().map(hex::encode).collect::<Vec<_>>(),
+            "message": hex::encode(&message),
+        });
+        self.agree("aggregate public key", &inputs, |backend| {
+            json!(hex::encode(backend.aggregate_public_keys(&public_keys)))
+        });
+        self.agree("aggregate signature", &inputs, |backend| {
+            json!(hex::encode(backend.aggregate_signatures(&signatures)))
+        });
+        let public_key = self.reference().aggregate_public_keys(&public_keys);
+        let signature = self.reference().aggregate_signatures(&signatures);
+        self.agree("validity of an aggregate", &inputs, |backend| {
+            json!(backend.verify(&signature, &public_key, &message))
+        });
+    }
+}
+
+/// Runs `iterations` rounds of every operation in `operations` and reports what was compared.
+pub fn run(operations: &[Operation], iterations: usize) -> Value {
+    let mut by_curve: BTreeMap<&'static str, Vec<BackendKind>> = BTreeMap::new();
+    for kind in BackendKind::value_variants() {
+        by_curve.entry(kind.curve()).or_default().push(*kind);
+    }
+    let groups: Vec<Group> = by_curve
+        .into_iter()
+        .filter(|(_, kinds)| kinds.len() > 1)
+        .map(|(curve, kinds)| Group {
+            curve,
+            backends: kinds
+                .into_iter()
+                .map(|kind| (kind.name(), kind.instantiate()))
+                .collect(),
+        })
+        .collect();
+    assert!(
+        !groups.is_empty(),
+        "No two backends in this build share a curve; build with the blst and arkworks features"
+    );
+    for _ in 0..iterations {
+        for group in &groups {
+            for operation in operations {
+                match operation {
+                    Operation::Sign => group.check_sign(),
+                    Operation::Verify => group.check_verify(),
+                    Operation::Aggregate => group.check_aggregate(),
+                }
+            }
+        }
+    }
+    let compared: BTreeMap<&str, Vec<&str>> = groups
+        .iter()
+        .map(|group| {
+            let names = group.backends.iter().map(|(name, _)| name.as_str()).collect();
+            (group.curve, names)
+        })
+        .collect();
+    json!({
+        "agree": true,
+        "iterations": iterations,
+        "operations": operations
+            .iter()
+            .map(|operation| operation.to_possible_value().unwrap().get_name().to_string())
+            .collect::<Vec<_>>(),
+        "backends": compared,
+    })
+}
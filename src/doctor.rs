@@ -0,0 +1,178 @@
+//! Environment diagnostics for support requests.
+//!
+//! `doctor` prints what a "verification fails on my machine" report needs in one JSON object:
+//! the build (version and features), the backend, curve, suite and DST in effect, the
+//! hash-to-curve parameters, the config file and whether it loads, the health of the operating
+//! system's random number generator, the threads available, and a self-test that generates a
+//! key, signs, verifies, rejects a tampered message and checks an aggregate with the current
+//! backend. Unlike other commands it reports a broken config file instead of stopping at it.
+
+use std::path::Path;
+use std::thread;
+use std::time::Instant;
+
+use rand_core::{OsRng, RngCore};
+use serde_json::{json, Value};
+
+use crate::backend::{Backend, BackendKind};
+use crate::config::{self, Config};
+use crate::hash_to_curve::HashParams;
+#[cfg(feature = "blst")]
+use crate::suite;
+
+/// Bytes drawn from the random number generator for the health check.
+const ENTROPY_SAMPLE_LEN: usize = 4096;
+/// Bounds on the share of one bits in the sample; a healthy source lands within a percent of
+/// one half with overwhelming probability at this sample size.
+const ONES_RATIO_BOUNDS: (f64, f64) = (0.48, 0.52);
+
+const SELF_TEST_MESSAGE: &[u8] = b"bls-tools doctor";
+
+fn features() -> Vec<&'static str> {
+    [
+        ("age", cfg!(feature = "age")),
+        ("arkworks", cfg!(feature = "arkworks")),
+        ("blst", cfg!(feature = "blst")),
+        ("encryption", cfg!(feature = "encryption")),
+        ("poseidon", cfg!(feature = "poseidon")),
+        ("qr", cfg!(feature = "qr")),
+        ("rpc", cfg!(feature = "rpc")),
+        ("timelock", cfg!(feature = "timelock")),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(name, _)| name)
+    .collect()
+}
+
+fn config_report(path: Option<&Path>, config: &Config) -> Value {
+    let location = config::path(path);
+    let exists = location.as_ref().is_some_and(|path| path.exists());
+    let error = if exists || path.is_some() {
+        Config::try_load(path).err()
+    } else {
+        None
+    };
+    #[cfg_attr(not(feature = "age"), allow(unused_mut))]
+    let mut report = json!({
+        "path": location.map(|path| path.display().to_string()),
+        "exists": exists,
+        "valid": error.is_none(),
+        "error": error,
+        "templates": config.templates.keys().collect::<Vec<_>>(),
+    });
+    #[cfg(feature = "blst")]
+    {
+        report["suites"] = json!(config.suites.keys().collect::<Vec<_>>());
+    }
+    #[cfg(feature = "age")]
+    if let Some(identity) = &config.age_identity {
+        report["ageIdentity"] = json!({
+            "path": identity.display().to_string(),
+            "readable": std::fs::File::open(identity).is_ok(),
+        });
+    }
+    report
+}
+
+fn entropy_report() -> Value {
+    let mut first = [0u8; ENTROPY_SAMPLE_LEN];
+    let mut second = [0u8; ENTROPY_SAMPLE_LEN];
+    let drawn = OsRng
+        .try_fill_bytes(&mut first)
+        .and_then(|_| OsRng.try_fill_bytes(&mut second));
+    if let Err(error) = drawn {
+        return json!({ "healthy": false, "error": error.to_string() });
+    }
+    let ones: u32 = first.iter().map(|byte| byte.count_ones()).sum();
+    let ratio = ones as f64 / (8 * ENTROPY_SAMPLE_LEN) as f64;
+    let (low, high) = ONES_RATIO_BOUNDS;
+    json!({
+        "healthy": (low..=high).contains(&ratio) && first != second,
+        "onesRatio": ratio,
+        "repeated": first == second,
+    })
+}
+
+/// Times `check`, which returns whether the step behaved as expected.
+fn step(check: impl FnOnce() -> bool) -> Value {
+    let start = Instant::now();
+    let ok = check();
+    json!({ "ok": ok, "ms": start.elapsed().as_secs_f64() * 1000.0 })
+}
+
+fn self_test(backend: &dyn Backend, aggregates: bool) -> Value {
+    let (secret, public_key) = backend.generate_keys();
+    let (other_secret, other_public_key) = backend.generate_keys();
+    let mut signature = Vec::new();
+    let mut report = json!({
+        "keygen": step(|| backend.public_key_from_secret(&secret) == public_key),
+        "sign": step(|| {
+            signature = backend.sign(&secret, SELF_TEST_MESSAGE);
+            signature == backend.sign(&secret, SELF_TEST_MESSAGE)
+        }),
+        "verify": step(|| backend.verify(&signature, &public_key, SELF_TEST_MESSAGE)),
+        "rejectTampered": step(|| !backend.verify(&signature, &public_key, b"tampered")),
+    });
+    // under message augmentation signers each sign their own key, so aggregates over one
+    // message do not verify against the summed key
+    report["aggregate"] = if aggregates {
+        step(|| {
+            let signatures = [
+                signature.clone(),
+                backend.sign(&other_secret, SELF_TEST_MESSAGE),
+            ];
+            let public_keys = [public_key.clone(), other_public_key.clone()];
+            backend.verify(
+                &backend.aggregate_signatures(&signatures),
+                &backend.aggregate_public_keys(&public_keys),
+                SELF_TEST_MESSAGE,
+            )
+        })
+    } else {
+        json!("skipped")
+    };
+    let passed = ["keygen", "sign", "verify", "rejectTampered"]
+        .iter()
+        .chain(if aggregates { &["aggregate"][..] } else { &[] })
+        .all(|name| report[name]["ok"] == true);
+    report["passed"] = json!(passed);
+    report
+}
+
+/// Everything `doctor` reports, for the backend and settings the command line selected.
+pub fn run(
+    kind: BackendKind,
+    suite: Option<&str>,
+    backend: &dyn Backend,
+    hash_params: HashParams,
+    config_path: Option<&Path>,
+    config: &Config,
+) -> Value {
+    let (dst, scheme) = match suite {
+        #[cfg(feature = "blst")]
+        Some(name) => (suite::dst(name, config), suite::scheme(name, config)),
+        #[cfg(not(feature = "blst"))]
+        Some(_) => unreachable!(),
+        None => (String::from_utf8_lossy(kind.dst()).to_string(), "basic"),
+    };
+    let curve = if suite.is_some() { "bls12-381" } else { kind.curve() };
+    json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "features": features(),
+        "backend": kind.name(),
+        "curve": curve,
+        "suite": suite,
+        "scheme": scheme,
+        "dst": dst,
+        "hashToCurve": (kind == BackendKind::Sylow && suite.is_none()).then(|| json!({
+            "expander": format!("{:?}", hash_params.expander).to_lowercase(),
+            "securityBits": hash_params.security_bits,
+            "expansionLen": hash_params.field_element_len,
+        })),
+        "config": config_report(config_path, config),
+        "entropy": entropy_report(),
+        "threads": thread::available_parallelism().map_or(1, |threads| threads.get()),
+        "selfTest": self_test(backend, scheme != "aug"),
+    })
+}
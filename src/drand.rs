@@ -0,0 +1,89 @@
+//! Verification of drand randomness beacon rounds.
+//!
+//! drand networks sign round numbers with BLS12-381 under one of three schemes:
+//!
+//! - `pedersen-bls-chained`: public key in 𝔾₁, signature in 𝔾₂ over
+//!   `sha256(previous_signature ‖ round)`, so every round depends on the one before it;
+//! - `pedersen-bls-unchained`: the same layout over `sha256(round)`;
+//! - `bls-unchained-g1-rfc9380`: public key in 𝔾₂, signature in 𝔾₁ over `sha256(round)`, with
+//!   the RFC 9380 hash-to-𝔾₁ ciphersuite.
+//!
+//! Rounds are encoded as 8-byte big-endian integers, and the published randomness is
+//! `sha256(signature)`.
+
+use ::blst::BLST_ERROR;
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+const G2_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+const G1_DST: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// The signing scheme of a drand network, as reported in its `/info` endpoint.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Scheme {
+    #[value(name = "pedersen-bls-chained")]
+    PedersenBlsChained,
+    #[value(name = "pedersen-bls-unchained")]
+    PedersenBlsUnchained,
+    #[value(name = "bls-unchained-g1-rfc9380")]
+    BlsUnchainedG1Rfc9380,
+}
+
+fn round_message(round: u64, previous_signature: Option<&[u8]>) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    if let Some(previous) = previous_signature {
+        hasher.update(previous);
+    }
+    hasher.update(round.to_be_bytes());
+    hasher.finalize().into()
+}
+
+fn verify_signature(scheme: Scheme, signature: &[u8], group_key: &[u8], message: &[u8]) -> bool {
+    let result = match scheme {
+        Scheme::PedersenBlsChained | Scheme::PedersenBlsUnchained => {
+            use ::blst::min_pk::{PublicKey, Signature};
+            let key = PublicKey::key_validate(group_key).expect("Invalid group key");
+            let sig = Signature::sig_validate(signature, false).expect("Invalid signature");
+            sig.verify(false, message, G2_DST, &[], &key, false)
+        }
+        Scheme::BlsUnchainedG1Rfc9380 => {
+            use ::blst::min_sig::{PublicKey, Signature};
+            let key = PublicKey::key_validate(group_key).expect("Invalid group key");
+            let sig = Signature::sig_validate(signature, false).expect("Invalid signature");
+            sig.verify(false, message, G1_DST, &[], &key, false)
+        }
+    };
+    result == BLST_ERROR::BLST_SUCCESS
+}
+
+/// Checks a beacon round's signature and that its randomness is derived from it.
+pub fn verify(
+    scheme: Scheme,
+    round: u64,
+    randomness: &[u8],
+    signature: &[u8],
+    previous_signature: Option<&[u8]>,
+    group_key: &[u8],
+) -> Value {
+    let previous_signature = match scheme {
+        Scheme::PedersenBlsChained => Some(
+            previous_signature.expect("Chained rounds need the previous round's signature"),
+        ),
+        _ => {
+            assert!(
+                previous_signature.is_none(),
+                "Unchained rounds do not use a previous signature"
+            );
+            None
+        }
+    };
+    let message = round_message(round, previous_signature);
+    let signature_valid = verify_signature(scheme, signature, group_key, &message);
+    let randomness_valid = Sha256::digest(signature).as_slice() == randomness;
+    json!({
+        "valid": signature_valid && randomness_valid,
+        "signatureValid": signature_valid,
+        "randomnessValid": randomness_valid,
+    })
+}
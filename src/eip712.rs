@@ -0,0 +1,307 @@
+//! EIP-712 typed structured data hashing.
+//!
+//! `sign-typed` signs the same 32-byte digest an EIP-712 wallet would sign with ECDSA,
+//! `keccak256(0x19 ‖ 0x01 ‖ domainSeparator ‖ hashStruct(message))`, so a contract that already
+//! hashes a payload for ECDSA can hand the same digest to a BLS verifier.
+//!
+//! The types file maps struct names to their members, as in `eth_signTypedData_v4`:
+//! `{"Mail": [{"name": "from", "type": "Person"}, …], …}`. When it has no `EIP712Domain` entry
+//! the domain type is made up of the standard fields present in the domain file, in the order
+//! EIP-712 lists them. The primary type is the one struct no other struct refers to, unless
+//! `--primary-type` names it. Integers may be JSON numbers or decimal or `0x` hex strings.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+
+use crate::template;
+
+const DOMAIN_TYPE: &str = "EIP712Domain";
+
+/// The fields a domain may have, in their canonical order.
+const DOMAIN_FIELDS: [(&str, &str); 5] = [
+    ("name", "string"),
+    ("version", "string"),
+    ("chainId", "uint256"),
+    ("verifyingContract", "address"),
+    ("salt", "bytes32"),
+];
+
+#[derive(Clone, Deserialize)]
+struct Member {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+type Types = BTreeMap<String, Vec<Member>>;
+
+pub struct TypedData {
+    types: Types,
+    primary_type: String,
+    domain: Value,
+    message: Value,
+}
+
+fn read_json(path: &Path) -> Value {
+    let contents = fs::read_to_string(path)
+        .unwrap_or_else(|_| panic!("Failed to read {}", path.display()));
+    serde_json::from_str(&contents)
+        .unwrap_or_else(|_| panic!("{} is not valid JSON", path.display()))
+}
+
+fn keccak(bytes: &[u8]) -> [u8; 32] {
+    Keccak256::digest(bytes).into()
+}
+
+/// The struct type `kind` refers to once array suffixes are stripped.
+fn base_type(kind: &str) -> &str {
+    kind.split('[').next().unwrap()
+}
+
+/// Adds `kind` and every struct type it refers to, directly or not, to `found`.
+fn dependencies(types: &Types, kind: &str, found: &mut BTreeSet<String>) {
+    let kind = base_type(kind);
+    let Some(members) = types.get(kind) else {
+        return;
+    };
+    if !found.insert(kind.to_string()) {
+        return;
+    }
+    for member in members {
+        dependencies(types, &member.kind, found);
+    }
+}
+
+/// `encodeType`: the primary struct followed by the structs it refers to, sorted by name.
+fn encode_type(types: &Types, primary: &str) -> String {
+    let mut found = BTreeSet::new();
+    dependencies(types, primary, &mut found);
+    found.remove(primary);
+    std::iter::once(primary)
+        .chain(found.iter().map(String::as_str))
+        .map(|name| {
+            let members: Vec<String> = types[name]
+                .iter()
+                .map(|member| format!("{} {}", member.kind, member.name))
+                .collect();
+            format!("{}({})", name, members.join(","))
+        })
+        .collect()
+}
+
+/// Integers as 32-byte two's complement words, checked against the width of `kind`.
+fn encode_integer(name: &str, kind: &str, value: &Value) -> [u8; 32] {
+    let (signed, bits) = match kind.strip_prefix("int") {
+        Some(bits) => (true, bits),
+        None => (false, &kind["uint".len()..]),
+    };
+    let bits: usize = match bits {
+        "" => 256,
+        bits => bits.parse().unwrap_or_else(|_| panic!("Unknown type {}", kind)),
+    };
+    assert!(
+        bits.is_multiple_of(8) && (8..=256).contains(&bits),
+        "Unknown type {}",
+        kind
+    );
+    let text = match value {
+        Value::Number(number) => number.to_string(),
+        Value::String(text) => text.clone(),
+        _ => panic!("Field {} must be an integer", name),
+    };
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.as_str()),
+    };
+    assert!(signed || !negative, "Field {} must not be negative", name);
+    let magnitude = template::uint(name, digits, 32);
+    // largest magnitude allowed: 2^bits - 1 unsigned, 2^(bits - 1) (- 1 if positive) signed
+    let limit_bits = if signed { bits - 1 } else { bits };
+    let mut limit = [0u8; 32];
+    if limit_bits == 256 {
+        limit = [0xff; 32];
+    } else {
+        limit[31 - limit_bits / 8] = 1 << (limit_bits % 8);
+        if !negative {
+            let mut borrow = true;
+            for byte in limit.iter_mut().rev() {
+                (*byte, borrow) = byte.overflowing_sub(borrow as u8);
+            }
+        }
+    }
+    assert!(magnitude.as_slice() <= limit.as_slice(), "Field {} does not fit {}", name, kind);
+    let mut word: [u8; 32] = magnitude.try_into().unwrap();
+    if negative {
+        let mut carry = true;
+        for byte in word.iter_mut().rev() {
+            (*byte, carry) = (!*byte).overflowing_add(carry as u8);
+        }
+    }
+    word
+}
+
+fn hex_value(name: &str, value: &Value) -> Vec<u8> {
+    let text = value
+        .as_str()
+        .unwrap_or_else(|| panic!("Field {} must be a hex string", name));
+    hex::decode(text.strip_prefix("0x").unwrap_or(text))
+        .unwrap_or_else(|_| panic!("Invalid hex in field {}", name))
+}
+
+/// The 32-byte encoding of one member value.
+fn encode_value(types: &Types, name: &str, kind: &str, value: &Value) -> [u8; 32] {
+    if let Some(element) = kind.strip_suffix(']') {
+        let (element, length) = element.rsplit_once('[').unwrap();
+        let items = value
+            .as_array()
+            .unwrap_or_else(|| panic!("Field {} must be an array", name));
+        if !length.is_empty() {
+            let length: usize = length
+                .parse()
+                .unwrap_or_else(|_| panic!("Unknown type {}", kind));
+            assert_eq!(items.len(), length, "Field {} must have {} items", name, length);
+        }
+        let encoded: Vec<u8> = items
+            .iter()
+            .flat_map(|item| encode_value(types, name, element, item))
+            .collect();
+        return keccak(&encoded);
+    }
+    if types.contains_key(kind) {
+        return hash_struct(types, kind, value);
+    }
+    match kind {
+        "bool" => {
+            let value = value
+                .as_bool()
+                .unwrap_or_else(|| panic!("Field {} must be a boolean", name));
+            let mut word = [0u8; 32];
+            word[31] = value as u8;
+            word
+        }
+        "address" => {
+            let bytes = hex_value(name, value);
+            assert_eq!(bytes.len(), 20, "Field {} must be a 20-byte address", name);
+            let mut word = [0u8; 32];
+            word[12..].copy_from_slice(&bytes);
+            word
+        }
+        "string" => {
+            let text = value
+                .as_str()
+                .unwrap_or_else(|| panic!("Field {} must be a string", name));
+            keccak(text.as_bytes())
+        }
+        "bytes" => keccak(&hex_value(name, value)),
+        _ if kind.starts_with("bytes") => {
+            let width: usize = kind["bytes".len()..]
+                .parse()
+                .unwrap_or_else(|_| panic!("Unknown type {}", kind));
+            assert!((1..=32).contains(&width), "Unknown type {}", kind);
+            let bytes = hex_value(name, value);
+            assert_eq!(bytes.len(), width, "Field {} must be {} bytes", name, width);
+            // fixed-size byte strings are left-aligned
+            let mut word = [0u8; 32];
+            word[..width].copy_from_slice(&bytes);
+            word
+        }
+        _ if kind.starts_with("uint") || kind.starts_with("int") => {
+            encode_integer(name, kind, value)
+        }
+        _ => panic!("Unknown type {}", kind),
+    }
+}
+
+/// `hashStruct`: the hash of the type hash followed by every member's encoding.
+fn hash_struct(types: &Types, kind: &str, value: &Value) -> [u8; 32] {
+    let mut encoded = keccak(encode_type(types, kind).as_bytes()).to_vec();
+    for member in &types[kind] {
+        let field = value
+            .get(&member.name)
+            .unwrap_or_else(|| panic!("{} is missing field {}", kind, member.name));
+        encoded.extend(encode_value(types, &member.name, &member.kind, field));
+    }
+    keccak(&encoded)
+}
+
+impl TypedData {
+    /// Reads the types, the domain and the message from their files.
+    pub fn read(types: &Path, domain: &Path, message: &Path, primary_type: Option<String>) -> Self {
+        let mut types: Types = serde_json::from_value(read_json(types))
+            .expect("Types file must map struct names to lists of {name, type} members");
+        let domain = read_json(domain);
+        let domain_fields = domain.as_object().expect("Domain must be a JSON object");
+        if let Some(unknown) = domain_fields
+            .keys()
+            .find(|key| !DOMAIN_FIELDS.iter().any(|(name, _)| name == key))
+        {
+            panic!("Domain has unknown field {}", unknown);
+        }
+        types.entry(DOMAIN_TYPE.to_string()).or_insert_with(|| {
+            DOMAIN_FIELDS
+                .iter()
+                .filter(|(name, _)| domain_fields.contains_key(*name))
+                .map(|(name, kind)| Member {
+                    name: name.to_string(),
+                    kind: kind.to_string(),
+                })
+                .collect()
+        });
+
+        let primary_type = primary_type.unwrap_or_else(|| {
+            let referenced: BTreeSet<&str> = types
+                .values()
+                .flatten()
+                .map(|member| base_type(&member.kind))
+                .collect();
+            let candidates: Vec<&String> = types
+                .keys()
+                .filter(|name| *name != DOMAIN_TYPE && !referenced.contains(name.as_str()))
+                .collect();
+            match candidates.as_slice() {
+                [primary] => primary.to_string(),
+                _ => panic!("Cannot tell the primary type; pass --primary-type"),
+            }
+        });
+        assert!(
+            types.contains_key(&primary_type),
+            "Types file does not define {}",
+            primary_type
+        );
+        TypedData {
+            types,
+            primary_type,
+            domain,
+            message: read_json(message),
+        }
+    }
+
+    pub fn domain_separator(&self) -> [u8; 32] {
+        hash_struct(&self.types, DOMAIN_TYPE, &self.domain)
+    }
+
+    pub fn struct_hash(&self) -> [u8; 32] {
+        hash_struct(&self.types, &self.primary_type, &self.message)
+    }
+
+    /// The digest that gets signed.
+    pub fn digest(&self) -> [u8; 32] {
+        keccak(&[&[0x19, 0x01], &self.domain_separator()[..], &self.struct_hash()[..]].concat())
+    }
+
+    /// The hashes leading to the digest, for comparing against a contract's.
+    pub fn describe(&self) -> Value {
+        json!({
+            "primaryType": self.primary_type,
+            "encodedType": encode_type(&self.types, &self.primary_type),
+            "domainSeparator": hex::encode(self.domain_separator()),
+            "structHash": hex::encode(self.struct_hash()),
+            "digest": hex::encode(self.digest()),
+        })
+    }
+}
@@ -0,0 +1,406 @@
+//! Self-describing signature envelopes.
+//!
+//! An envelope bundles everything needed to check a signature — the message, the signer's
+//! public key, the signature and the backend that produced them — into one JSON file, so a
+//! signed message can be handed around and verified without copying hex strings by hand.
+//!
+//! An envelope never signs the bare message. What gets signed is the tag
+//! `bls-tools envelope v2` and a newline, then the compact JSON
+//! `{"message":…,"notBefore":…,"notAfter":…,"nonce":…}` with every member present (`null` when
+//! unset), so no envelope's signed bytes are those of another envelope with fields removed, and
+//! none are those of a plain `sign` of some message. Version 1 envelopes signed the bare message
+//! when they had neither window nor nonce, which let an expiring envelope be rewritten into one
+//! without a window; they are no longer accepted.
+//!
+//! `sign --expires-in` adds a validity window, `notBefore` and `notAfter` in Unix seconds, that
+//! `verify --envelope` enforces. The window is signed along with the message, so moving or
+//! dropping either bound invalidates the signature.
+//!
+//! An envelope carries its signer's key, so on its own it only shows that someone signed it.
+//! `verify --envelope` reports that key and its fingerprint, and `--public-key` or
+//! `--fingerprint` pins the signer: an envelope signed by anyone else is not valid.
+//!
+//! `sign --nonce` likewise binds a caller-chosen nonce or request context.
+//! `verify --envelope --seen-nonces <file>`, which needs a pinned signer, rejects an envelope
+//! whose signer already used its nonce, and records the nonce of every envelope it accepts, one
+//! `<key fingerprint> <nonce>` line each, so a captured envelope cannot be replayed. An envelope
+//! without a nonce is reported as not replay-protected and not valid, since nothing stops it
+//! from being presented again.
+//!
+//! `countersign` endorses an envelope by appending a countersignature over the envelope as it
+//! stands, signature and earlier countersignatures included: countersignature `i` signs the tag
+//! `bls-tools countersignature v1` and a newline, then the compact JSON of the envelope holding
+//! only countersignatures `0..i` (keys in sorted order, and no `countersignatures` member at all
+//! for the first). `verify --envelope` checks the whole chain, so no endorsement can be moved to
+//! another envelope or reordered. Version 2 countersignatures signed the JSON without a tag, so a
+//! plain `sign` of that JSON made one; version 2 envelopes are no longer accepted either.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::backend::{Backend, BackendKind};
+use crate::config::Config;
+use crate::credential::format_datetime;
+use crate::fingerprint::fingerprint;
+#[cfg(feature = "blst")]
+use crate::suite;
+
+pub const ENVELOPE_VERSION: u32 = 3;
+
+/// Prefix of the bytes an envelope signature covers.
+const SIGNED_PAYLOAD_TAG: &[u8] = b"bls-tools envelope v2\n";
+
+/// Prefix of the bytes a countersignature covers.
+const COUNTERSIGNED_TAG: &[u8] = b"bls-tools countersignature v1\n";
+
+/// Who an envelope must be signed by.
+pub enum Signer {
+    PublicKey(String),
+    Fingerprint(String),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Envelope {
+    pub version: u32,
+    pub backend: String,
+    /// Set when the signature was made under `--suite`, which then takes precedence over `backend`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suite: Option<String>,
+    pub message: String,
+    /// Unix time before which the signature is not valid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<u64>,
+    /// Unix time after which the signature is not valid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<u64>,
+    /// Caller-chosen value binding the signature to one request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    pub public_key: String,
+    pub signature: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub countersignatures: Vec<Countersignature>,
+}
+
+/// An endorsement of an envelope by a second signer.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Countersignature {
+    pub backend: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suite: Option<String>,
+    pub public_key: String,
+    pub signature: String,
+}
+
+/// What gets signed after the tag; unset fields are signed as `null`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SignedPayload<'a> {
+    message: &'a str,
+    not_before: Option<u64>,
+    not_after: Option<u64>,
+    nonce: Option<&'a str>,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System clock is before the Unix epoch")
+        .as_secs()
+}
+
+/// The backend named by an envelope or countersignature.
+#[cfg_attr(not(feature = "blst"), allow(unused_variables))]
+fn instantiate(backend: &str, suite: Option<&str>, config: &Config) -> Box<dyn Backend> {
+    match suite {
+        #[cfg(feature = "blst")]
+        Some(suite) => suite::instantiate(suite, config),
+        #[cfg(not(feature = "blst"))]
+        Some(suite) => panic!("Envelope was signed under suite {}, which needs blst", suite),
+        None => BackendKind::from_str(backend, true)
+            .expect("Envelope names an unknown backend")
+            .instantiate(),
+    }
+}
+
+fn verify_hex(backend: &dyn Backend, signature: &str, public_key: &str, message: &[u8]) -> bool {
+    let signature = hex::decode(signature).expect("Invalid hex in signature");
+    let public_key = hex::decode(public_key).expect("Invalid hex in public key");
+    backend.verify(&signature, &public_key, message)
+}
+
+/// Parses durations such as `90s`, `15m`, `1h` or `7d` into seconds.
+pub fn parse_duration(value: &str) -> Result<u64, String> {
+    let (amount, unit) = value.split_at(value.trim_end_matches(char::is_alphabetic).len());
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("{} is not a duration like 90s, 15m, 1h or 7d", value))?;
+    let unit = match unit {
+        "s" | "" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        _ => return Err(format!("Unknown duration unit {}; use s, m, h or d", unit)),
+    };
+    amount
+        .checked_mul(unit)
+        .ok_or_else(|| format!("Duration {} is too long", value))
+}
+
+impl Envelope {
+    /// An unsigned envelope; `sign` fills in the signature once every bound field is set.
+    pub fn new(backend: BackendKind, message: String, public_key: &[u8]) -> Self {
+        Envelope {
+            version: ENVELOPE_VERSION,
+            backend: backend.name(),
+            suite: None,
+            message,
+            not_before: None,
+            not_after: None,
+            nonce: None,
+            public_key: hex::encode(public_key),
+            signature: String::new(),
+            countersignatures: Vec::new(),
+        }
+    }
+
+    /// Limits the signature to the next `seconds` seconds.
+    pub fn with_expiry(mut self, seconds: u64) -> Self {
+        let now = unix_now();
+        self.not_before = Some(now);
+        self.not_after = Some(now.checked_add(seconds).expect("Expiry is too far out"));
+        self
+    }
+
+    pub fn with_nonce(mut self, nonce: Option<String>) -> Self {
+        // the seen nonce store keeps one nonce per line
+        assert!(
+            nonce.as_ref().is_none_or(|nonce| !nonce.contains('\n')),
+            "Nonces must fit on one line"
+        );
+        self.nonce = nonce;
+        self
+    }
+
+    /// The bytes the signature covers.
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        let payload = serde_json::to_vec(&SignedPayload {
+            message: &self.message,
+            not_before: self.not_before,
+            not_after: self.not_after,
+            nonce: self.nonce.as_deref(),
+        })
+        .expect("Failed to serialize signed payload");
+        [SIGNED_PAYLOAD_TAG, &payload].concat()
+    }
+
+    pub fn sign(mut self, backend: &dyn Backend, secret: &[u8]) -> Self {
+        self.signature = hex::encode(backend.sign(secret, &self.signed_bytes()));
+        self
+    }
+
+    /// The bytes countersignature `index` covers.
+    fn countersigned_bytes(&self, index: usize) -> Vec<u8> {
+        let mut value = serde_json::to_value(self).expect("Failed to serialize envelope");
+        let object = value.as_object_mut().unwrap();
+        object.remove("countersignatures");
+        if index > 0 {
+            let earlier = serde_json::to_value(&self.countersignatures[..index])
+                .expect("Failed to serialize countersignatures");
+            object.insert("countersignatures".to_string(), earlier);
+        }
+        let envelope = serde_json::to_vec(&value).expect("Failed to serialize envelope");
+        [COUNTERSIGNED_TAG, &envelope].concat()
+    }
+
+    fn countersignature_valid(&self, index: usize, config: &Config) -> bool {
+        let countersignature = &self.countersignatures[index];
+        let backend = instantiate(
+            &countersignature.backend,
+            countersignature.suite.as_deref(),
+            config,
+        );
+        verify_hex(
+            backend.as_ref(),
+            &countersignature.signature,
+            &countersignature.public_key,
+            &self.countersigned_bytes(index),
+        )
+    }
+
+    /// Appends a countersignature by `secret` under `backend` (or `suite`), after checking that
+    /// every signature so far holds.
+    pub fn countersign(
+        &mut self,
+        backend: &str,
+        suite: Option<&str>,
+        secret: &[u8],
+        config: &Config,
+    ) {
+        assert!(
+            verify_hex(
+                self.instantiate(config).as_ref(),
+                &self.signature,
+                &self.public_key,
+                &self.signed_bytes()
+            ),
+            "Envelope signature is not valid; refusing to countersign"
+        );
+        assert!(
+            (0..self.countersignatures.len()).all(|i| self.countersignature_valid(i, config)),
+            "Envelope holds an invalid countersignature; refusing to countersign"
+        );
+        let instance = instantiate(backend, suite, config);
+        let message = self.countersigned_bytes(self.countersignatures.len());
+        let signature = instance.sign(secret, &message);
+        self.countersignatures.push(Countersignature {
+            backend: backend.to_string(),
+            suite: suite.map(str::to_string),
+            public_key: hex::encode(instance.public_key_from_secret(secret)),
+            signature: hex::encode(signature),
+        });
+    }
+
+    /// Checks the signature, the signer if `signer` pins one, the validity window, if any, at
+    /// Unix time `at` (now by default), and every countersignature.
+    pub fn verify(&self, config: &Config, signer: Option<&Signer>, at: Option<u64>) -> Value {
+        let backend = self.instantiate(config);
+        let valid = verify_hex(
+            backend.as_ref(),
+            &self.signature,
+            &self.public_key,
+            &self.signed_bytes(),
+        );
+        // a key that verifies a signature is a valid key to fingerprint
+        let signer_fingerprint = valid.then(|| {
+            let public_key = hex::decode(&self.public_key).expect("Invalid hex in public key");
+            fingerprint(backend.as_ref(), &public_key)
+        });
+        let mut result = json!({
+            "valid": valid,
+            "publicKey": self.public_key,
+            "fingerprint": signer_fingerprint,
+        });
+        let mut all_valid = valid;
+        if let Some(signer) = signer {
+            let pinned = match signer {
+                Signer::PublicKey(public_key) => {
+                    let public_key = hex::decode(public_key).expect("Invalid hex in public key");
+                    fingerprint(backend.as_ref(), &public_key)
+                }
+                Signer::Fingerprint(pinned) => pinned.to_lowercase(),
+            };
+            let signer_matches = signer_fingerprint.as_ref() == Some(&pinned);
+            result["signerMatches"] = json!(signer_matches);
+            all_valid &= signer_matches;
+        }
+        if self.not_before.is_some() || self.not_after.is_some() {
+            let at = at.unwrap_or_else(unix_now);
+            let within_window = self.not_before.is_none_or(|not_before| at >= not_before)
+                && self.not_after.is_none_or(|not_after| at <= not_after);
+            result["signatureValid"] = json!(valid);
+            result["withinWindow"] = json!(within_window);
+            result["at"] = json!(at);
+            all_valid &= within_window;
+        }
+        if !self.countersignatures.is_empty() {
+            let countersignatures: Vec<Value> = self
+                .countersignatures
+                .iter()
+                .enumerate()
+                .map(|(index, countersignature)| {
+                    json!({
+                        "publicKey": countersignature.public_key,
+                        "valid": self.countersignature_valid(index, config),
+                    })
+                })
+                .collect();
+            all_valid &= countersignatures.iter().all(|check| check["valid"] == json!(true));
+            result["countersignatures"] = json!(countersignatures);
+        }
+        result["valid"] = json!(all_valid);
+        result
+    }
+
+    #[cfg(feature = "blst")]
+    pub fn with_suite(mut self, suite: Option<String>) -> Self {
+        self.suite = suite;
+        self
+    }
+
+    pub fn read(path: &Path) -> Self {
+        let contents = fs::read_to_string(path).expect("Failed to read envelope file");
+        let envelope: Envelope =
+            serde_json::from_str(&contents).expect("Envelope file is not a valid envelope");
+        assert!(
+            !(1..ENVELOPE_VERSION).contains(&envelope.version),
+            "Version {} envelopes are no longer accepted; sign the message again",
+            envelope.version
+        );
+        assert_eq!(
+            envelope.version, ENVELOPE_VERSION,
+            "Unsupported envelope version"
+        );
+        envelope
+    }
+
+    pub fn write(&self, path: &Path) {
+        let contents = serde_json::to_string_pretty(self).expect("Failed to serialize envelope");
+        fs::write(path, contents + "\n").expect("Failed to write envelope file");
+    }
+
+    /// The backend that checks this envelope's signature.
+    pub fn instantiate(&self, config: &Config) -> Box<dyn Backend> {
+        instantiate(&self.backend, self.suite.as_deref(), config)
+    }
+
+    /// Whether the signer already used this envelope's nonce according to the `store` file,
+    /// recording it there if not; `None` if the envelope has no nonce to check.
+    pub fn replayed(&self, config: &Config, store: &Path) -> Option<bool> {
+        let backend = self.instantiate(config);
+        let nonce = self.nonce.as_deref()?;
+        let public_key = hex::decode(&self.public_key).expect("Invalid hex in public key");
+        let entry = format!("{} {}", fingerprint(backend.as_ref(), &public_key), nonce);
+        let seen = match fs::read_to_string(store) {
+            Ok(contents) => contents.lines().any(|line| line == entry),
+            Err(_) => false,
+        };
+        if !seen {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(store)
+                .expect("Failed to open seen nonce store");
+            writeln!(file, "{}", entry).expect("Failed to record nonce");
+        }
+        Some(seen)
+    }
+
+    /// Renders the envelope as aligned `field: value` lines for humans.
+    pub fn display(&self) -> String {
+        [
+            ("Version", self.version.to_string()),
+            ("Backend", self.backend.clone()),
+            ("Suite", self.suite.clone().unwrap_or_else(|| "-".to_string())),
+            ("Message", self.message.clone()),
+            ("Not before", self.not_before.map_or("-".to_string(), format_datetime)),
+            ("Not after", self.not_after.map_or("-".to_string(), format_datetime)),
+            ("Nonce", self.nonce.clone().unwrap_or_else(|| "-".to_string())),
+            ("Public key", self.public_key.clone()),
+            ("Signature", self.signature.clone()),
+            ("Cosigners", self.countersignatures.len().to_string()),
+        ]
+        .iter()
+        .map(|(field, value)| format!("{:<12}{}", format!("{}:", field), value))
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+}
@@ -0,0 +1,205 @@
+//! Detection of keys that signed conflicting messages.
+//!
+//! Protocols that have a signer sign once per context (a round, an epoch, a slot) treat two
+//! signatures by one key over different messages in the same context as misbehaviour.
+//! `detect-equivocation` reads collected records, one JSON object per line with `publicKey`,
+//! `context`, `message` and `signature` (hex, text, text and hex), and reports every key that
+//! signed a second, different message in a context, next to the first message it signed there.
+//!
+//! Only records whose signature verifies count, so every reported pair is evidence a third party
+//! can check without trusting the collector; records that do not verify are listed apart.
+//!
+//! `evidence bundle` turns one reported pair into a self-contained artifact for a dispute or
+//! slashing process: the offender's key, the context, both signed messages, the transcript of the
+//! checks that make them evidence (re-run when bundling, which refuses a pair that fails one) and,
+//! on the sylow backend, the calldata handing the pair to a contract (see `evm`).
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::backend::Backend;
+use crate::calldata_profile::CalldataProfile;
+use crate::evm;
+use crate::fingerprint::fingerprint;
+use crate::threads;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedRecord {
+    pub public_key: String,
+    pub context: String,
+    pub message: String,
+    pub signature: String,
+}
+
+impl SignedRecord {
+    pub fn verifies(&self, backend: &dyn Backend) -> bool {
+        match (hex::decode(&self.signature), hex::decode(&self.public_key)) {
+            (Ok(signature), Ok(public_key)) => {
+                backend.verify(&signature, &public_key, self.message.as_bytes())
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Two verified records of one key in one context with different messages.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Equivocation {
+    pub public_key: String,
+    pub context: String,
+    pub first: Signed,
+    pub second: Signed,
+}
+
+/// A message and its signature, with the line of the records file it was found on.
+#[derive(Serialize, Deserialize)]
+pub struct Signed {
+    pub line: usize,
+    pub message: String,
+    pub signature: String,
+}
+
+fn read(path: &Path) -> Vec<(usize, SignedRecord)> {
+    fs::read_to_string(path)
+        .expect("Failed to read records")
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let record = serde_json::from_str(line).unwrap_or_else(|error| {
+                panic!("Line {} is not a signed record: {}", index + 1, error)
+            });
+            (index + 1, record)
+        })
+        .collect()
+}
+
+/// Every equivocation among the records in `path`.
+pub fn detect(backend: &dyn Backend, path: &Path, threads: usize) -> Value {
+    let records = read(path);
+    let valid = threads::map(threads, &records, |(_, record)| record.verifies(backend));
+    let mut first_seen: HashMap<(String, &str), (usize, &SignedRecord)> = HashMap::new();
+    let mut equivocations = Vec::new();
+    let mut invalid = Vec::new();
+    for ((line, record), valid) in records.iter().zip(valid) {
+        if !valid {
+            invalid.push(*line);
+            continue;
+        }
+        let key = (record.public_key.to_lowercase(), record.context.as_str());
+        match first_seen.get(&key) {
+            None => {
+                first_seen.insert(key, (*line, record));
+            }
+            Some((first_line, first)) if first.message != record.message => {
+                equivocations.push(Equivocation {
+                    public_key: key.0,
+                    context: record.context.clone(),
+                    first: Signed {
+                        line: *first_line,
+                        message: first.message.clone(),
+                        signature: first.signature.clone(),
+                    },
+                    second: Signed {
+                        line: *line,
+                        message: record.message.clone(),
+                        signature: record.signature.clone(),
+                    },
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    json!({
+        "records": records.len(),
+        "equivocations": equivocations,
+        "invalidLines": invalid,
+    })
+}
+
+/// Equivocation `index` of a `detect-equivocation` report.
+pub fn read_report(path: &Path, index: usize) -> Equivocation {
+    let contents = fs::read_to_string(path).expect("Failed to read equivocation report");
+    let report: Value = serde_json::from_str(&contents).expect("Malformed equivocation report");
+    let mut equivocations: Vec<Equivocation> = serde_json::from_value(
+        report
+            .get("equivocations")
+            .cloned()
+            .expect("Report has no equivocations"),
+    )
+    .expect("Malformed equivocation in report");
+    assert!(
+        index < equivocations.len(),
+        "Report has {} equivocations, none at index {}",
+        equivocations.len(),
+        index
+    );
+    equivocations.swap_remove(index)
+}
+
+/// The evidence artifact of `equivocation`, with calldata for the contract function in
+/// `calldata`, laid out by its profile, if given.
+pub fn bundle(
+    backend: &dyn Backend,
+    backend_name: String,
+    suite: Option<String>,
+    equivocation: &Equivocation,
+    calldata: Option<(&CalldataProfile, &str)>,
+) -> Value {
+    let public_key = hex::decode(&equivocation.public_key).expect("Invalid hex in public key");
+    let first = hex::decode(&equivocation.first.signature).expect("Invalid hex in signature");
+    let second = hex::decode(&equivocation.second.signature).expect("Invalid hex in signature");
+    let checks = [
+        (
+            "first signature verifies",
+            backend.verify(&first, &public_key, equivocation.first.message.as_bytes()),
+        ),
+        (
+            "second signature verifies",
+            backend.verify(&second, &public_key, equivocation.second.message.as_bytes()),
+        ),
+        (
+            "messages differ",
+            equivocation.first.message != equivocation.second.message,
+        ),
+    ];
+    for (check, passed) in checks {
+        assert!(passed, "Not evidence of equivocation: {} fails", check);
+    }
+    let calldata = calldata.map(|(profile, method)| {
+        let data = evm::evidence_calldata(
+            profile,
+            method,
+            &public_key,
+            equivocation.context.as_bytes(),
+            (equivocation.first.message.as_bytes(), &first),
+            (equivocation.second.message.as_bytes(), &second),
+        );
+        json!({
+            "function": evm::evidence_signature(profile, method),
+            "data": format!("0x{}", hex::encode(data)),
+        })
+    });
+    let transcript: Vec<Value> = checks
+        .iter()
+        .map(|(check, passed)| json!({ "check": check, "passed": passed }))
+        .collect();
+    json!({
+        "kind": "equivocation",
+        "backend": backend_name,
+        "suite": suite,
+        "publicKey": hex::encode(&public_key),
+        "fingerprint": fingerprint(backend, &public_key),
+        "context": equivocation.context,
+        "first": { "message": equivocation.first.message, "signature": hex::encode(&first) },
+        "second": { "message": equivocation.second.message, "signature": hex::encode(&second) },
+        "transcript": transcript,
+        "calldata": calldata,
+    })
+}
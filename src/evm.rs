@@ -0,0 +1,252 @@
+//! Ethereum ABI encoding of BN254 signatures, for handing them to verifier contracts.
+//!
+//! Points are encoded the way the EIP-196/197 precompiles take them: 𝔾₁ as `uint256[2]`
+//! `(x, y)` and 𝔾₂ as `uint256[4]` `(x.c1, x.c0, y.c1, y.c0)`. That is also sylow's byte
+//! layout, so a point splits into 32-byte words unchanged. `--calldata-profile` can lay points
+//! out differently for other verifiers (see `calldata_profile`); the `uint256` arrays in the
+//! function signatures below then have as many words as the profile's points take.
+//!
+//! `submit` calls `<method>(uint256[2] signature, bytes bitfield, bytes32 messageHash)`, where
+//! bit `i` of the bitfield (least significant bit of the first byte first) marks whether the
+//! committee member at index `i` contributed, and the message hash is `keccak256(message)`.
+//! `verify-onchain` reads such a call back from a mined transaction and checks it locally.
+//!
+//! `evidence bundle` calls `<method>(uint256[4] publicKey, bytes context, bytes firstMessage,
+//! uint256[2] firstSignature, bytes secondMessage, uint256[2] secondSignature)`, handing a
+//! dispute contract two signatures by one key over different messages in the same context.
+
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+
+use crate::backend::sylow::{decode_public_key, decode_signature, SylowBackend};
+use crate::backend::Backend;
+use crate::calldata_profile::CalldataProfile;
+use crate::point::Group;
+
+const WORD: usize = 32;
+
+/// An ABI value: a static 32-byte word or a dynamic byte string.
+pub enum Token {
+    Word([u8; WORD]),
+    Bytes(Vec<u8>),
+}
+
+/// The first four bytes of the Keccak-256 hash of a function signature.
+pub fn selector(signature: &str) -> [u8; 4] {
+    let hash = Keccak256::digest(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+fn pad(bytes: &[u8]) -> [u8; WORD] {
+    assert!(bytes.len() <= WORD, "ABI words hold at most 32 bytes");
+    let mut padded = [0u8; WORD];
+    padded[WORD - bytes.len()..].copy_from_slice(bytes);
+    padded
+}
+
+/// A big-endian integer of at most 32 bytes, left-padded to a word.
+pub fn word(bytes: &[u8]) -> Token {
+    Token::Word(pad(bytes))
+}
+
+/// The coordinates of a point as consecutive words, laid out by `profile`.
+pub fn point(profile: &CalldataProfile, bytes: &[u8]) -> Vec<Token> {
+    profile.encode("bn254", bytes).chunks(WORD).map(word).collect()
+}
+
+/// The ABI type of a point of `group` laid out by `profile`.
+fn point_type(profile: &CalldataProfile, group: Group) -> String {
+    format!("uint256[{}]", profile.words(group))
+}
+
+/// Encodes `tokens` as the arguments of a call: static words in place, dynamic values behind
+/// offsets into the tail.
+pub fn encode(tokens: &[Token]) -> Vec<u8> {
+    let mut head = Vec::new();
+    let mut tail = Vec::new();
+    let head_len = tokens.len() * WORD;
+    for token in tokens {
+        match token {
+            Token::Word(value) => head.extend_from_slice(value),
+            Token::Bytes(value) => {
+                head.extend_from_slice(&pad(&(head_len + tail.len()).to_be_bytes()));
+                tail.extend_from_slice(&pad(&value.len().to_be_bytes()));
+                tail.extend_from_slice(value);
+                tail.resize(tail.len().next_multiple_of(WORD), 0);
+            }
+        }
+    }
+    [head, tail].concat()
+}
+
+/// Calldata of `method` submitting an aggregate `signature` made by the members in `bitfield`,
+/// with the signature laid out by `profile`.
+pub fn submit_calldata(
+    profile: &CalldataProfile,
+    method: &str,
+    signature: &[u8],
+    bitfield: &[u8],
+    message: &[u8],
+) -> Vec<u8> {
+    // reject anything that is not a point before it ends up in a transaction
+    decode_signature(signature);
+    let mut tokens = point(profile, signature);
+    tokens.push(Token::Bytes(bitfield.to_vec()));
+    tokens.push(Token::Word(Keccak256::digest(message).into()));
+    [selector(&submit_signature(profile, method)).to_vec(), encode(&tokens)].concat()
+}
+
+/// The full signature of the submission function called `method`.
+pub fn submit_signature(profile: &CalldataProfile, method: &str) -> String {
+    format!("{}({},bytes,bytes32)", method, point_type(profile, Group::G1))
+}
+
+/// Calldata of `method` reporting that `public_key` signed both `first` and `second`, each a
+/// message and its signature, in `context`, with the points laid out by `profile`.
+pub fn evidence_calldata(
+    profile: &CalldataProfile,
+    method: &str,
+    public_key: &[u8],
+    context: &[u8],
+    first: (&[u8], &[u8]),
+    second: (&[u8], &[u8]),
+) -> Vec<u8> {
+    decode_public_key(public_key);
+    decode_signature(first.1);
+    decode_signature(second.1);
+    let mut tokens = point(profile, public_key);
+    tokens.push(Token::Bytes(context.to_vec()));
+    for (message, signature) in [first, second] {
+        tokens.push(Token::Bytes(message.to_vec()));
+        tokens.extend(point(profile, signature));
+    }
+    [selector(&evidence_signature(profile, method)).to_vec(), encode(&tokens)].concat()
+}
+
+/// The full signature of the evidence function called `method`.
+pub fn evidence_signature(profile: &CalldataProfile, method: &str) -> String {
+    let g1 = point_type(profile, Group::G1);
+    format!(
+        "{}({},bytes,bytes,{},bytes,{})",
+        method,
+        point_type(profile, Group::G2),
+        g1,
+        g1
+    )
+}
+
+/// The bitfield of a committee of `size` members marking `signers`.
+pub fn bitfield(signers: &[usize], size: usize) -> Vec<u8> {
+    let mut bitfield = vec![0u8; size.div_ceil(8)];
+    for &index in signers {
+        assert!(index < size, "Member {} is outside the committee", index);
+        bitfield[index / 8] |= 1 << (index % 8);
+    }
+    bitfield
+}
+
+/// Indexes of the committee members marked in `bitfield`.
+pub fn signers(bitfield: &[u8]) -> Vec<usize> {
+    (0..bitfield.len() * 8)
+        .filter(|&index| bitfield[index / 8] >> (index % 8) & 1 == 1)
+        .collect()
+}
+
+fn word_at(data: &[u8], offset: usize) -> &[u8] {
+    data.get(offset..offset + WORD)
+        .expect("Calldata ends in the middle of an argument")
+}
+
+fn usize_at(data: &[u8], offset: usize) -> usize {
+    let value = word_at(data, offset);
+    assert!(
+        value[..WORD - 8].iter().all(|&byte| byte == 0),
+        "Calldata offset or length is out of range"
+    );
+    u64::from_be_bytes(value[WORD - 8..].try_into().unwrap()) as usize
+}
+
+/// The selector, signature, bitfield and message hash of a call made by `submit`.
+pub fn decode_submit_calldata(
+    profile: &CalldataProfile,
+    data: &[u8],
+) -> ([u8; 4], Vec<u8>, Vec<u8>, Vec<u8>) {
+    assert!(data.len() >= 4, "Calldata has no function selector");
+    let (selector, arguments) = data.split_at(4);
+    let words = profile.words(Group::G1);
+    let laid_out: Vec<u8> = (0..words)
+        .flat_map(|index| word_at(arguments, index * WORD).to_vec())
+        .collect();
+    let signature = profile.decode("bn254", &laid_out);
+    let offset = usize_at(arguments, words * WORD);
+    let message_hash = word_at(arguments, (words + 1) * WORD).to_vec();
+    let len = usize_at(arguments, offset);
+    let bitfield = arguments
+        .get(offset + WORD..offset + WORD + len)
+        .expect("Calldata ends in the middle of the bitfield")
+        .to_vec();
+    (
+        selector.try_into().unwrap(),
+        signature,
+        bitfield,
+        message_hash,
+    )
+}
+
+/// Checks a `submit` call against the committee's public keys, in bitfield order, and the
+/// message the signature is supposed to cover.
+pub fn verify_submission(
+    profile: &CalldataProfile,
+    data: &[u8],
+    committee: &[Vec<u8>],
+    message: &[u8],
+) -> Value {
+    let (selector, signature, bitfield, message_hash) = decode_submit_calldata(profile, data);
+    let signers = signers(&bitfield);
+    let public_keys: Vec<Vec<u8>> = signers
+        .iter()
+        .map(|&index| {
+            committee
+                .get(index)
+                .unwrap_or_else(|| panic!("Bitfield marks member {} outside the committee", index))
+                .clone()
+        })
+        .collect();
+    let message_hash_matches = message_hash == Keccak256::digest(message).as_slice();
+    let backend = SylowBackend::default();
+    let signature_valid = !public_keys.is_empty()
+        && backend.verify(
+            &signature,
+            &backend.aggregate_public_keys(&public_keys),
+            message,
+        );
+    json!({
+        "selector": hex::encode(selector),
+        "signature": hex::encode(signature),
+        "signers": signers,
+        "messageHashMatches": message_hash_matches,
+        "signatureValid": signature_valid,
+        "valid": message_hash_matches && signature_valid,
+    })
+}
+
+/// An unsigned transaction calling `contract` with `data`, in JSON-RPC form.
+pub fn transaction(from: Option<&str>, contract: &str, data: &[u8]) -> Value {
+    let mut transaction = json!({
+        "to": address(contract),
+        "data": format!("0x{}", hex::encode(data)),
+        "value": "0x0",
+    });
+    if let Some(from) = from {
+        transaction["from"] = json!(address(from));
+    }
+    transaction
+}
+
+/// Normalizes a hex account address to `0x`-prefixed lowercase.
+fn address(address: &str) -> String {
+    let digits = address.strip_prefix("0x").unwrap_or(address);
+    let bytes = hex::decode(digits).expect("Invalid hex in address");
+    assert_eq!(bytes.len(), 20, "Addresses must be 20 bytes");
+    format!("0x{}", hex::encode(bytes))
+}
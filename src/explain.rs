@@ -0,0 +1,288 @@
+//! Guesses at what an opaque value is, for triaging integration problems.
+//!
+//! `explain` looks at a hex blob (or a JSON document, as hex or as is) and lists every kind of
+//! value it could be in this build — secret keys, 𝔾₁ and 𝔾₂ points on BN254 and, with `blst`,
+//! BLS12-381, `submit` calldata, envelope, PSSM and fixtures files — with whether it actually
+//! decodes as that kind and the commands that take it. It also points out the usual mistakes:
+//! a `0x` prefix where bare hex is expected, an odd number of digits, a length one byte off a
+//! known one, and 𝔾₂ coordinates in `c0`-first order.
+
+use serde_json::{json, Value};
+
+use crate::backend::BackendKind;
+use crate::calldata_profile::CalldataProfile;
+use crate::convert::{self, Format};
+use crate::evm;
+use crate::secret;
+
+/// A kind of value the blob could be.
+struct Candidate {
+    kind: &'static str,
+    valid: bool,
+    commands: Vec<&'static str>,
+}
+
+const SECRET_COMMANDS: &[&str] = &[
+    "sign --secret",
+    "public-key-from-secret",
+    "check-secret",
+    "check-keypair --secret",
+];
+const SIGNATURE_COMMANDS: &[&str] = &[
+    "verify --signature",
+    "aggregate-signatures",
+    "pssm add-signature --signature",
+];
+const PUBLIC_KEY_COMMANDS: &[&str] = &[
+    "verify --public-key",
+    "aggregate-keys",
+    "fingerprint",
+    "did from-key",
+];
+const G1_COMMANDS: &[&str] = &["point add --group g1", "convert"];
+const G2_COMMANDS: &[&str] = &["point add --group g2", "convert"];
+
+/// Byte lengths of the values below, for spotting ones that are a byte off.
+const KNOWN_LENGTHS: [usize; 6] = [32, 48, 64, 96, 128, 192];
+
+fn bn254_candidates(bytes: &[u8], issues: &mut Vec<String>) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    match bytes.len() {
+        32 => {
+            let backend = BackendKind::Sylow.instantiate();
+            let check = secret::check(backend.as_ref(), bytes);
+            if check["belowOrder"] == false {
+                issues.push(
+                    "As a BN254 secret key it is not below the group order; --reduce accepts it"
+                        .to_string(),
+                );
+            }
+            candidates.push(Candidate {
+                kind: "BN254 secret key",
+                valid: check["valid"] == true,
+                commands: SECRET_COMMANDS.to_vec(),
+            });
+        }
+        64 => {
+            candidates.push(Candidate {
+                kind: "BN254 𝔾₁ point: sylow signature, also EIP-197 input",
+                valid: convert::is_valid("bn254", Format::HexUncompressed, bytes),
+                commands: [SIGNATURE_COMMANDS, G1_COMMANDS].concat(),
+            });
+        }
+        128 => {
+            let valid = convert::is_valid("bn254", Format::HexUncompressed, bytes);
+            if !valid && convert::is_valid("bn254", Format::HexUncompressedC0First, bytes) {
+                issues.push(
+                    "The 𝔾₂ coordinates are in c0-first order; fix with `convert --from \
+                     hex-uncompressed-c0-first --to hex-uncompressed`"
+                        .to_string(),
+                );
+            }
+            candidates.push(Candidate {
+                kind: "BN254 𝔾₂ point: sylow public key, also EIP-197 input",
+                valid,
+                commands: [PUBLIC_KEY_COMMANDS, G2_COMMANDS].concat(),
+            });
+        }
+        _ => {}
+    }
+    candidates
+}
+
+#[cfg(feature = "blst")]
+fn bls12_381_candidates(bytes: &[u8], issues: &mut Vec<String>) -> Vec<Candidate> {
+    let compressed = |bytes: &[u8]| convert::is_valid("bls12-381", Format::HexCompressed, bytes);
+    let uncompressed =
+        |bytes: &[u8]| convert::is_valid("bls12-381", Format::HexUncompressed, bytes);
+    // which group holds keys and which signatures depends on the backend or suite
+    let point_commands = || {
+        [
+            SIGNATURE_COMMANDS,
+            PUBLIC_KEY_COMMANDS,
+            &["convert --backend blst"],
+        ]
+        .concat()
+    };
+    let mut candidates = Vec::new();
+    match bytes.len() {
+        32 => {
+            let backend = BackendKind::Blst.instantiate();
+            candidates.push(Candidate {
+                kind: "BLS12-381 secret key",
+                valid: secret::check(backend.as_ref(), bytes)["valid"] == true,
+                commands: SECRET_COMMANDS.to_vec(),
+            });
+        }
+        48 => candidates.push(Candidate {
+            kind: "BLS12-381 compressed 𝔾₁ point: blst signature, or suite public key",
+            valid: compressed(bytes),
+            commands: point_commands(),
+        }),
+        // the compression flag tells the two 96-byte encodings apart
+        96 if bytes[0] & 0x80 != 0 => candidates.push(Candidate {
+            kind: "BLS12-381 compressed 𝔾₂ point: blst public key, or suite signature",
+            valid: compressed(bytes),
+            commands: point_commands(),
+        }),
+        96 => candidates.push(Candidate {
+            kind: "BLS12-381 uncompressed 𝔾₁ point",
+            valid: uncompressed(bytes),
+            commands: point_commands(),
+        }),
+        192 => {
+            let valid = uncompressed(bytes);
+            if !valid && convert::is_valid("bls12-381", Format::HexUncompressedC0First, bytes) {
+                issues.push(
+                    "The 𝔾₂ coordinates are in c0-first order; fix with `convert --backend blst \
+                     --from hex-uncompressed-c0-first --to hex-uncompressed`"
+                        .to_string(),
+                );
+            }
+            candidates.push(Candidate {
+                kind: "BLS12-381 uncompressed 𝔾₂ point",
+                valid,
+                commands: point_commands(),
+            });
+        }
+        _ => {}
+    }
+    candidates
+}
+
+/// Whether `bytes` have the layout of a `submit` call: a selector, the signature words, the
+/// bitfield offset right after the head and a bitfield that fits.
+fn is_submit_calldata(bytes: &[u8]) -> bool {
+    const HEAD: usize = 4 * 32;
+    if bytes.len() < 4 + HEAD + 32 || !(bytes.len() - 4).is_multiple_of(32) {
+        return false;
+    }
+    let word = |offset: usize| &bytes[4 + offset..4 + offset + 32];
+    let small = |word: &[u8]| word[..24].iter().all(|&byte| byte == 0);
+    let offset = word(64);
+    let len = word(HEAD);
+    small(offset)
+        && u64::from_be_bytes(offset[24..].try_into().unwrap()) == HEAD as u64
+        && small(len)
+        && u64::from_be_bytes(len[24..].try_into().unwrap()) <= (bytes.len() - HEAD - 36) as u64
+}
+
+fn json_candidate(document: &Value) -> Option<Candidate> {
+    let has = |field: &str| document.get(field).is_some();
+    if has("partialSignatures") && has("committee") {
+        Some(Candidate {
+            kind: "PSSM file",
+            valid: true,
+            commands: vec!["pssm add-signature", "pssm combine", "pssm finalize"],
+        })
+    } else if has("signature") && has("publicKey") && has("message") {
+        Some(Candidate {
+            kind: "Envelope file",
+            valid: true,
+            commands: vec!["verify --envelope", "envelope show", "countersign"],
+        })
+    } else if has("keys") && has("sign") && has("aggregate") && has("verify") {
+        Some(Candidate {
+            kind: "Fixtures file",
+            valid: true,
+            commands: vec!["import-fixtures", "regress check"],
+        })
+    } else {
+        None
+    }
+}
+
+fn report(length: Option<usize>, candidates: Vec<Candidate>, issues: Vec<String>) -> Value {
+    let candidates: Vec<Value> = candidates
+        .into_iter()
+        .map(|candidate| {
+            json!({
+                "kind": candidate.kind,
+                "valid": candidate.valid,
+                "commands": candidate.commands,
+            })
+        })
+        .collect();
+    json!({
+        "length": length,
+        "candidates": candidates,
+        "issues": issues,
+    })
+}
+
+/// Everything `value` could be, and what looks wrong with it.
+pub fn explain(value: &str) -> Value {
+    let value = value.trim();
+    let mut issues = Vec::new();
+    if value.starts_with('{') {
+        let document: Value = match serde_json::from_str(value) {
+            Ok(document) => document,
+            Err(error) => {
+                issues.push(format!("Invalid JSON: {}", error));
+                return report(None, Vec::new(), issues);
+            }
+        };
+        return report(None, json_candidate(&document).into_iter().collect(), issues);
+    }
+    let digits = match value.strip_prefix("0x") {
+        Some(digits) => {
+            issues.push(
+                "Has a 0x prefix; most commands take bare hex (only `--assertion-id` and \
+                 `--calldata` accept it)"
+                    .to_string(),
+            );
+            digits
+        }
+        None => value,
+    };
+    if let Some(position) = digits.find(|c: char| !c.is_ascii_hexdigit()) {
+        issues.push(format!("Not hex: unexpected character at position {}", position));
+        return report(None, Vec::new(), issues);
+    }
+    if digits.len() % 2 == 1 {
+        issues.push("Odd number of hex digits: one is missing or extra".to_string());
+        return report(None, Vec::new(), issues);
+    }
+    let bytes = hex::decode(digits).unwrap();
+
+    #[cfg_attr(not(feature = "blst"), allow(unused_mut))]
+    let mut candidates = bn254_candidates(&bytes, &mut issues);
+    #[cfg(feature = "blst")]
+    candidates.extend(bls12_381_candidates(&bytes, &mut issues));
+    if is_submit_calldata(&bytes) {
+        let profile = CalldataProfile::default_for("bn254");
+        let (selector, ..) = evm::decode_submit_calldata(&profile, &bytes);
+        let default = evm::selector(&evm::submit_signature(&profile, "submitAggregate"));
+        candidates.push(Candidate {
+            kind: if selector == default {
+                "submitAggregate calldata"
+            } else {
+                "submit calldata for a custom --method"
+            },
+            valid: true,
+            commands: vec!["verify-onchain --calldata"],
+        });
+    }
+    if let Some(candidate) = std::str::from_utf8(&bytes)
+        .ok()
+        .and_then(|text| serde_json::from_str(text).ok())
+        .and_then(|document| json_candidate(&document))
+    {
+        candidates.push(candidate);
+    }
+    if candidates.is_empty() {
+        match KNOWN_LENGTHS
+            .iter()
+            .find(|&&known| known.abs_diff(bytes.len()) == 1)
+        {
+            Some(known) => issues.push(format!(
+                "{} bytes is one byte off {}: a leading zero byte may have been dropped, or an \
+                 extra byte added",
+                bytes.len(),
+                known
+            )),
+            None => issues.push(format!("No known value is {} bytes long", bytes.len())),
+        }
+    }
+    report(Some(bytes.len()), candidates, issues)
+}
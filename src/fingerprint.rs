@@ -0,0 +1,19 @@
+//! Short public key fingerprints for humans.
+//!
+//! A fingerprint is the first 8 bytes of the Keccak-256 hash of the public key's canonical
+//! encoding (compressed where the backend supports compression), written as 16 hex digits.
+//! Canonicalizing first means the compressed and uncompressed forms of one key share a
+//! fingerprint.
+
+use sha3::{Digest, Keccak256};
+
+use crate::backend::Backend;
+
+const FINGERPRINT_LEN: usize = 8;
+
+/// The fingerprint of `public_key`, which must be a valid key for `backend`.
+pub fn fingerprint(backend: &dyn Backend, public_key: &[u8]) -> String {
+    // aggregating a single key re-encodes it canonically
+    let canonical = backend.aggregate_public_keys(&[public_key.to_vec()]);
+    hex::encode(&Keccak256::digest(canonical)[..FINGERPRINT_LEN])
+}
@@ -0,0 +1,74 @@
+//! Fixed-base scalar multiplication for the 𝔾₂ generator.
+//!
+//! Every public key is `s·G2` for the same generator, so instead of running the generic
+//! double-and-add ladder each time we precompute `j·16ⁱ·G2` for every 4-bit window `i` and
+//! digit `j ∈ [1, 8]`. A multiplication then costs one table lookup and one point addition per
+//! window, with no doublings at all. The table is built lazily on first use and shared by the
+//! rest of the process, so it pays for itself as soon as more than a couple of keys are derived.
+
+use std::sync::OnceLock;
+
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+use sylow::{Fp, G2Projective, GroupTrait};
+
+const WINDOW_BITS: usize = 4;
+const WINDOWS: usize = 256 / WINDOW_BITS;
+const TABLE_WIDTH: usize = 1 << (WINDOW_BITS - 1);
+
+type Window = [G2Projective; TABLE_WIDTH];
+
+static G2_TABLE: OnceLock<Vec<Window>> = OnceLock::new();
+
+fn g2_table() -> &'static [Window] {
+    G2_TABLE.get_or_init(|| {
+        let mut base = G2Projective::generator();
+        (0..WINDOWS)
+            .map(|_| {
+                let mut window = [base; TABLE_WIDTH];
+                for j in 1..TABLE_WIDTH {
+                    window[j] = window[j - 1] + base;
+                }
+                // 16·base is twice the largest entry, 8·base
+                base = window[TABLE_WIDTH - 1].double();
+                window
+            })
+            .collect()
+    })
+}
+
+/// Recodes the scalar into signed base-16 digits in `[-8, 7]`, least significant first.
+///
+/// The scalar is below the BN254 base field modulus (< 2²⁵⁴), so the top window is at most 3
+/// and the final carry is always zero.
+fn signed_digits(scalar: &Fp) -> [i8; WINDOWS] {
+    let bytes = scalar.to_be_bytes();
+    let mut digits = [0i8; WINDOWS];
+    let mut carry = 0u8;
+    for (i, digit) in digits.iter_mut().enumerate() {
+        let nibble = (bytes[31 - i / 2] >> ((i % 2) * WINDOW_BITS)) & 0x0f;
+        let value = nibble + carry;
+        carry = (value + 8) >> WINDOW_BITS;
+        *digit = value as i8 - (carry << WINDOW_BITS) as i8;
+    }
+    digits
+}
+
+/// Computes `scalar·G2` using the cached fixed-base table.
+///
+/// The result is identical to `G2Projective::generator() * scalar`. Table entries are selected
+/// by scanning the whole window with constant-time selects, so the memory access pattern does
+/// not depend on the secret digits.
+pub fn g2_generator_mul(scalar: &Fp) -> G2Projective {
+    let mut acc = G2Projective::zero();
+    for (window, digit) in g2_table().iter().zip(signed_digits(scalar)) {
+        let magnitude = digit.unsigned_abs();
+        let mut point = G2Projective::zero();
+        for (j, entry) in window.iter().enumerate() {
+            point.conditional_assign(entry, magnitude.ct_eq(&(j as u8 + 1)));
+        }
+        let negated = -point;
+        point.conditional_assign(&negated, Choice::from((digit as u8) >> 7));
+        acc = acc + point;
+    }
+    acc
+}
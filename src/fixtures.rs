@@ -0,0 +1,256 @@
+//! Test fixtures shared with client libraries.
+//!
+//! `export-fixtures` writes freshly generated keys together with signatures, aggregates and
+//! verification cases computed from them; `import-fixtures` recomputes every value of such a file
+//! (ours or one produced by a client) and lists the ones that disagree. Since BLS signing is
+//! deterministic, two implementations agree on a fixture exactly when they agree on the scheme.
+//!
+//! The `dkgjs` layout is flat JSON with hex keys and signatures and UTF-8 messages:
+//! `keys`, `sign`, `aggregate` and `verify` sections, each a list of cases.
+
+use std::fs;
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::backend::{Backend, BackendKind};
+use crate::config::Config;
+#[cfg(feature = "blst")]
+use crate::suite;
+
+pub const FIXTURES_VERSION: u32 = 1;
+
+/// Fixture layouts selectable with `--format`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FixtureFormat {
+    /// The layout the OriginTrail JS client tests read
+    Dkgjs,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyCase {
+    secret_key: String,
+    public_key: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignCase {
+    secret_key: String,
+    message: String,
+    signature: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AggregateCase {
+    message: String,
+    public_keys: Vec<String>,
+    signatures: Vec<String>,
+    aggregated_public_key: String,
+    aggregated_signature: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VerifyCase {
+    public_key: String,
+    message: String,
+    signature: String,
+    valid: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Fixtures {
+    format: FixtureFormat,
+    version: u32,
+    backend: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    suite: Option<String>,
+    keys: Vec<KeyCase>,
+    sign: Vec<SignCase>,
+    aggregate: Vec<AggregateCase>,
+    verify: Vec<VerifyCase>,
+}
+
+fn decode(value: &str) -> Vec<u8> {
+    hex::decode(value).unwrap_or_else(|_| panic!("Invalid hex {} in fixtures", value))
+}
+
+fn encode_all(values: &[Vec<u8>]) -> Vec<String> {
+    values.iter().map(hex::encode).collect()
+}
+
+impl Fixtures {
+    /// Generates `count` key pairs with `instance` and every case derived from them.
+    pub fn generate(
+        format: FixtureFormat,
+        backend: BackendKind,
+        suite: Option<String>,
+        instance: &dyn Backend,
+        count: usize,
+    ) -> Self {
+        assert!(count > 0, "Fixtures need at least one key");
+        let key_pairs: Vec<(Vec<u8>, Vec<u8>)> =
+            (0..count).map(|_| instance.generate_keys()).collect();
+        Self::from_key_pairs(format, backend, suite, instance, key_pairs)
+    }
+
+    /// Derives every case from the given `(secret_key, public_key)` pairs.
+    pub fn from_key_pairs(
+        format: FixtureFormat,
+        backend: BackendKind,
+        suite: Option<String>,
+        instance: &dyn Backend,
+        key_pairs: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Self {
+        let public_keys: Vec<Vec<u8>> = key_pairs.iter().map(|(_, pk)| pk.clone()).collect();
+
+        let sign: Vec<SignCase> = key_pairs
+            .iter()
+            .enumerate()
+            .map(|(index, (secret_key, _))| {
+                let message = format!("message {}", index + 1);
+                SignCase {
+                    secret_key: hex::encode(secret_key),
+                    signature: hex::encode(instance.sign(secret_key, message.as_bytes())),
+                    message,
+                }
+            })
+            .collect();
+
+        let message = "aggregate".to_string();
+        let signatures: Vec<Vec<u8>> = key_pairs
+            .iter()
+            .map(|(secret_key, _)| instance.sign(secret_key, message.as_bytes()))
+            .collect();
+        let aggregated_public_key = instance.aggregate_public_keys(&public_keys);
+        let aggregated_signature = instance.aggregate_signatures(&signatures);
+
+        // the first signature over its own message and over a different one, plus the aggregate
+        let own = VerifyCase {
+            public_key: hex::encode(&public_keys[0]),
+            message: sign[0].message.clone(),
+            signature: sign[0].signature.clone(),
+            valid: true,
+        };
+        let tampered = VerifyCase {
+            message: "tampered".to_string(),
+            valid: false,
+            ..own.clone()
+        };
+        // under message augmentation signers each sign their own key, so the aggregate does
+        // not verify against the summed key
+        let aggregated = VerifyCase {
+            public_key: hex::encode(&aggregated_public_key),
+            message: message.clone(),
+            signature: hex::encode(&aggregated_signature),
+            valid: instance.verify(
+                &aggregated_signature,
+                &aggregated_public_key,
+                message.as_bytes(),
+            ),
+        };
+        let verify = vec![own, tampered, aggregated];
+
+        Fixtures {
+            format,
+            version: FIXTURES_VERSION,
+            backend: backend.name(),
+            suite,
+            keys: key_pairs
+                .iter()
+                .map(|(secret_key, public_key)| KeyCase {
+                    secret_key: hex::encode(secret_key),
+                    public_key: hex::encode(public_key),
+                })
+                .collect(),
+            sign,
+            aggregate: vec![AggregateCase {
+                message,
+                public_keys: encode_all(&public_keys),
+                signatures: encode_all(&signatures),
+                aggregated_public_key: hex::encode(aggregated_public_key),
+                aggregated_signature: hex::encode(aggregated_signature),
+            }],
+            verify,
+        }
+    }
+
+    pub fn read(path: &Path) -> Self {
+        let contents = fs::read_to_string(path).expect("Failed to read fixtures file");
+        serde_json::from_str(&contents).expect("Fixtures file is not valid")
+    }
+
+    /// Whether this build has the backend or suite the fixtures were made with.
+    pub fn available(&self) -> bool {
+        match &self.suite {
+            Some(_) => cfg!(feature = "blst"),
+            None => BackendKind::from_str(&self.backend, true).is_ok(),
+        }
+    }
+
+    /// The backend the fixtures were made with.
+    #[cfg_attr(not(feature = "blst"), allow(unused_variables))]
+    pub fn instantiate(&self, config: &Config) -> Box<dyn Backend> {
+        match &self.suite {
+            #[cfg(feature = "blst")]
+            Some(name) => suite::instantiate(name, config),
+            #[cfg(not(feature = "blst"))]
+            Some(name) => panic!("Fixtures were made under suite {}, which needs blst", name),
+            None => BackendKind::from_str(&self.backend, true)
+                .expect("Fixtures name an unknown backend")
+                .instantiate(),
+        }
+    }
+
+    /// Recomputes every case with `backend` and reports the values that differ.
+    pub fn check(&self, backend: &dyn Backend) -> Value {
+        assert_eq!(self.version, FIXTURES_VERSION, "Unsupported fixtures version");
+        let mut failures = Vec::new();
+        for (index, case) in self.keys.iter().enumerate() {
+            let public_key = backend.public_key_from_secret(&decode(&case.secret_key));
+            if public_key != decode(&case.public_key) {
+                failures.push(format!("keys[{}].publicKey", index));
+            }
+        }
+        for (index, case) in self.sign.iter().enumerate() {
+            let signature = backend.sign(&decode(&case.secret_key), case.message.as_bytes());
+            if signature != decode(&case.signature) {
+                failures.push(format!("sign[{}].signature", index));
+            }
+        }
+        for (index, case) in self.aggregate.iter().enumerate() {
+            let public_keys: Vec<Vec<u8>> = case.public_keys.iter().map(|k| decode(k)).collect();
+            let signatures: Vec<Vec<u8>> = case.signatures.iter().map(|s| decode(s)).collect();
+            if backend.aggregate_public_keys(&public_keys) != decode(&case.aggregated_public_key) {
+                failures.push(format!("aggregate[{}].aggregatedPublicKey", index));
+            }
+            if backend.aggregate_signatures(&signatures) != decode(&case.aggregated_signature) {
+                failures.push(format!("aggregate[{}].aggregatedSignature", index));
+            }
+        }
+        for (index, case) in self.verify.iter().enumerate() {
+            let valid = backend.verify(
+                &decode(&case.signature),
+                &decode(&case.public_key),
+                case.message.as_bytes(),
+            );
+            if valid != case.valid {
+                failures.push(format!("verify[{}].valid", index));
+            }
+        }
+        let checked =
+            self.keys.len() + self.sign.len() + 2 * self.aggregate.len() + self.verify.len();
+        json!({
+            "valid": failures.is_empty(),
+            "checked": checked,
+            "failures": failures,
+        })
+    }
+}
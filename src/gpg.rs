@@ -0,0 +1,47 @@
+//! OpenPGP-wrapped secret keys, through the local `gpg` binary.
+//!
+//! `key import --gpg` decrypts a file holding a bare hex secret key (armored or binary, as
+//! `gpg --encrypt` writes it) and stores the key in a key pair file; `key export
+//! --gpg-recipient` encrypts a key pair file's secret the other way. Going through `gpg` means
+//! keys, smartcards and agent passphrase prompts all work as they do for the team's other
+//! secrets. Recipients must be trusted in the local keyring, since `gpg` runs in batch mode.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+fn run(args: &[&str], input: &[u8]) -> Vec<u8> {
+    let mut child = Command::new("gpg")
+        .args(["--batch", "--quiet"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to run gpg; is it installed?");
+    // the input is at most one key, far below the pipe buffer, so writing all of it before
+    // reading any output cannot deadlock
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input)
+        .expect("Failed to pass data to gpg");
+    let output = child.wait_with_output().expect("Failed to run gpg");
+    assert!(output.status.success(), "gpg failed: {}", output.status);
+    output.stdout
+}
+
+/// Decrypts the OpenPGP message in `path`.
+pub fn decrypt(path: &Path) -> Vec<u8> {
+    let path = path.to_str().expect("gpg needs a UTF-8 file path");
+    run(&["--decrypt", path], &[])
+}
+
+/// Encrypts `plaintext` to every one of `recipients`, ASCII-armored.
+pub fn encrypt(plaintext: &[u8], recipients: &[String]) -> String {
+    let mut args = vec!["--armor", "--encrypt"];
+    for recipient in recipients {
+        args.extend(["--recipient", recipient.as_str()]);
+    }
+    String::from_utf8(run(&args, plaintext)).expect("Armored OpenPGP messages are ASCII")
+}
@@ -0,0 +1,193 @@
+//! Hashing messages to BN254 𝔾₁ with a choice of message expander.
+//!
+//! sylow's `hash_to_curve` only takes expanders implementing its own trait, whose error type
+//! it does not export, and it fixes the expansion length. This module reimplements the
+//! RFC 9380 pipeline on top of sylow's field arithmetic — `expand_message`, `hash_to_field`
+//! with two elements, the Shallue–van de Woestijne map and the final point addition — so the
+//! expander, the security parameter and the expansion length can be changed. With the default
+//! [`HashParams`] it produces exactly sylow's points.
+
+use clap::ValueEnum;
+use sha3::digest::{ExtendableOutput, Update};
+use sha3::{Keccak256, Shake128, Shake256};
+use sylow::{Expander, Fp, G1Affine, G1Projective, XMDExpander};
+
+const OVERSIZE_DST_PREFIX: &[u8] = b"H2C-OVERSIZE-DST-";
+
+/// Expanders selectable with `--expander`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ExpanderKind {
+    /// expand_message_xmd with Keccak-256
+    #[default]
+    Xmd,
+    /// expand_message_xof with SHAKE128, or SHAKE256 above 128-bit security
+    Xof,
+}
+
+/// Bit length of the BN254 base field modulus.
+const MODULUS_BITS: u64 = 254;
+/// Keccak-256 output bits; expand_message_xmd needs at least twice the security parameter.
+const XMD_HASH_BITS: u64 = 256;
+const MAX_SECURITY_BITS: u64 = 256;
+
+/// Parameters of the hashing pipeline that differ between implementations.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HashParams {
+    pub expander: ExpanderKind,
+    /// The security parameter k
+    pub security_bits: u64,
+    /// Bytes of expanded message per field element, L
+    pub field_element_len: usize,
+}
+
+impl Default for HashParams {
+    fn default() -> Self {
+        HashParams::new(ExpanderKind::Xmd, 128, None)
+    }
+}
+
+impl HashParams {
+    /// Checks the parameters, deriving L = ⌈(⌈log₂ p⌉ + k) / 8⌉ unless it is given explicitly.
+    pub fn new(expander: ExpanderKind, security_bits: u64, field_element_len: Option<usize>) -> Self {
+        assert!(
+            (1..=MAX_SECURITY_BITS).contains(&security_bits),
+            "Security bits must be between 1 and {}",
+            MAX_SECURITY_BITS
+        );
+        if expander == ExpanderKind::Xmd {
+            assert!(
+                2 * security_bits <= XMD_HASH_BITS,
+                "The XMD expander offers at most {} bits of security",
+                XMD_HASH_BITS / 2
+            );
+        }
+        let minimum = (MODULUS_BITS + security_bits).div_ceil(8) as usize;
+        let field_element_len = field_element_len.unwrap_or(minimum);
+        assert!(
+            field_element_len >= minimum,
+            "Expansion length must be at least {} bytes for {}-bit security",
+            minimum,
+            security_bits
+        );
+        assert!(
+            2 * field_element_len <= u16::MAX as usize,
+            "Expansion length is too large"
+        );
+        HashParams {
+            expander,
+            security_bits,
+            field_element_len,
+        }
+    }
+}
+
+fn shake<D: Default + Update + ExtendableOutput>(parts: &[&[u8]], len: usize) -> Vec<u8> {
+    let mut xof = D::default();
+    for part in parts {
+        xof.update(part);
+    }
+    xof.finalize_boxed(len).to_vec()
+}
+
+/// `expand_message_xof` (RFC 9380 §5.3.2) over SHAKE128, or SHAKE256 above 128-bit security.
+fn expand_message_xof(dst: &[u8], security_bits: u64, msg: &[u8], len_in_bytes: usize) -> Vec<u8> {
+    let xof = if security_bits <= 128 {
+        shake::<Shake128>
+    } else {
+        shake::<Shake256>
+    };
+    let len = u16::try_from(len_in_bytes).expect("Expansion length must fit in two bytes");
+    let dst = if dst.len() > 255 {
+        xof(
+            &[OVERSIZE_DST_PREFIX, dst],
+            (2 * security_bits).div_ceil(8) as usize,
+        )
+    } else {
+        dst.to_vec()
+    };
+    xof(&[msg, &len.to_be_bytes(), &dst, &[dst.len() as u8]], len_in_bytes)
+}
+
+/// Reduces a big-endian integer of any length modulo p.
+fn reduce(bytes: &[u8]) -> Fp {
+    let radix = Fp::from(256);
+    bytes
+        .iter()
+        .fold(Fp::ZERO, |acc, &byte| acc * radix + Fp::from(byte as u64))
+}
+
+/// The Shallue–van de Woestijne map to y² = x³ + 3 (RFC 9380 §6.6.1), with sylow's Z = 1.
+pub fn map_to_curve(u: Fp) -> G1Projective {
+    let g = |x: Fp| x * x * x + Fp::THREE;
+    let z = Fp::ONE;
+    let c1 = g(z);
+    let c2 = -z / Fp::TWO;
+    let mut c3 = (-g(z) * (Fp::THREE * z * z))
+        .sqrt()
+        .into_option()
+        .expect("SvdW constant has a square root");
+    if bool::from(c3.sgn0()) {
+        c3 = -c3;
+    }
+    let c4 = Fp::FOUR * -g(z) / (Fp::THREE * z * z);
+
+    let tv1 = u * u * c1;
+    let tv2 = Fp::ONE + tv1;
+    let tv1 = Fp::ONE - tv1;
+    let tv3 = Fp::ONE / (tv1 * tv2);
+    let tv4 = u * tv1 * tv3 * c3;
+    let x1 = c2 - tv4;
+    let x2 = c2 + tv4;
+    let x3 = tv2 * tv2 * tv3;
+    let x3 = x3 * x3 * c4 + z;
+    let x = if bool::from(g(x1).is_square()) {
+        x1
+    } else if bool::from(g(x2).is_square()) {
+        x2
+    } else {
+        x3
+    };
+    let y = g(x).sqrt().into_option().expect("SvdW output is on the curve");
+    let y = if bool::from(u.sgn0()) == bool::from(y.sgn0()) {
+        y
+    } else {
+        -y
+    };
+    G1Affine::new([x, y])
+        .expect("SvdW output is on the curve")
+        .into()
+}
+
+/// A hash-to-curve configuration for BN254 𝔾₁.
+pub struct HashToCurve<'a> {
+    pub params: HashParams,
+    pub dst: &'a [u8],
+}
+
+impl HashToCurve<'_> {
+    pub fn expand_message(&self, msg: &[u8], len_in_bytes: usize) -> Vec<u8> {
+        let security_bits = self.params.security_bits;
+        match self.params.expander {
+            ExpanderKind::Xmd => XMDExpander::<Keccak256>::new(self.dst, security_bits)
+                .expand_message(msg, len_in_bytes)
+                .expect("Message expansion failed"),
+            ExpanderKind::Xof => expand_message_xof(self.dst, security_bits, msg, len_in_bytes),
+        }
+    }
+
+    /// Expands the message and reduces each L-byte chunk into a field element.
+    pub fn hash_to_field(&self, msg: &[u8], count: usize) -> Vec<Fp> {
+        let len = self.params.field_element_len;
+        self.expand_message(msg, count * len)
+            .chunks(len)
+            .map(reduce)
+            .collect()
+    }
+
+    pub fn hash(&self, msg: &[u8]) -> G1Projective {
+        self.hash_to_field(msg, 2)
+            .into_iter()
+            .map(map_to_curve)
+            .fold(G1Projective::zero(), |acc, point| acc + point)
+    }
+}
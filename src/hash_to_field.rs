@@ -0,0 +1,39 @@
+//! Stage-by-stage output of hashing a message to BN254 𝔾₁, for porting the hash-to-curve to
+//! other languages: when a port disagrees, the first differing stage shows where.
+
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use sylow::{G1Affine, G1Projective};
+
+use crate::backend::sylow::DST;
+use crate::hash_to_curve::{map_to_curve, HashParams, HashToCurve};
+
+/// The expanded message, the `count` field elements cut from it and the point each maps to,
+/// plus the hashed point itself when `count` is the two elements hash-to-curve uses.
+pub fn trace(params: HashParams, message: &[u8], count: usize) -> Value {
+    assert!(count >= 1, "Count must be at least 1");
+    let hasher = HashToCurve { params, dst: DST };
+    let expanded = hasher.expand_message(message, count * params.field_element_len);
+    let elements = hasher.hash_to_field(message, count);
+    let mapped: Vec<G1Projective> = elements.iter().map(|u| map_to_curve(*u)).collect();
+    let mut result = json!({
+        "dst": String::from_utf8_lossy(DST),
+        "expander": params.expander.to_possible_value().map(|value| value.get_name().to_string()),
+        "securityBits": params.security_bits,
+        "fieldElementLen": params.field_element_len,
+        "expandedMessage": hex::encode(&expanded),
+        "fieldElements": elements
+            .iter()
+            .map(|u| hex::encode(u.to_be_bytes()))
+            .collect::<Vec<_>>(),
+        "mappedPoints": mapped
+            .iter()
+            .map(|point| hex::encode(G1Affine::from(point).to_be_bytes()))
+            .collect::<Vec<_>>(),
+    });
+    if count == 2 {
+        let point = mapped[0] + mapped[1];
+        result["point"] = json!(hex::encode(G1Affine::from(point).to_be_bytes()));
+    }
+    result
+}
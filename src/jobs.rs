@@ -0,0 +1,222 @@
+//! Declarative job files: a list of operations run in order, for provisioning ceremonies.
+//!
+//! A job file is JSON, like the config file:
+//!
+//! ```json
+//! {
+//!   "variables": { "message": "committee epoch 42" },
+//!   "steps": [
+//!     { "id": "alice", "op": "generate" },
+//!     { "id": "bob", "op": "generate" },
+//!     { "id": "a", "op": "sign", "secretKey": "${alice.secretKey}", "message": "${message}" },
+//!     { "id": "b", "op": "sign", "secretKey": "${bob.secretKey}", "message": "${message}" },
+//!     { "id": "key", "op": "aggregate-keys",
+//!       "publicKeys": ["${alice.publicKey}", "${bob.publicKey}"] },
+//!     { "id": "sig", "op": "aggregate-signatures",
+//!       "signatures": ["${a.signature}", "${b.signature}"] },
+//!     { "op": "verify", "signature": "${sig.signature}", "publicKey": "${key.publicKey}",
+//!       "message": "${message}" },
+//!     { "op": "export", "path": "alice.json", "secretKey": "${alice.secretKey}",
+//!       "publicKey": "${alice.publicKey}" }
+//!   ]
+//! }
+//! ```
+//!
+//! `${name}` in any string is replaced by a variable, and `${id.field}` by a field of the output
+//! of an earlier step with that `id`: `generate` outputs `secretKey`, `publicKey` and
+//! `fingerprint`, `sign` and `aggregate-signatures` a `signature`, `aggregate-keys` a
+//! `publicKey`, `verify` `valid` and `export` the `file` it wrote. Keys are hex and messages text,
+//! as on the command line. A `verify` that fails stops the run, so nothing after it (typically
+//! an export) happens. The report lists every step's output without secret keys, which only
+//! leave the run through `export`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::backend::{Backend, BackendKind};
+use crate::config::Config;
+use crate::fingerprint::fingerprint;
+use crate::keypair::KeyPairFile;
+
+#[derive(Deserialize)]
+struct JobFile {
+    #[serde(default)]
+    variables: Map<String, Value>,
+    steps: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "kebab-case")]
+enum Operation {
+    Generate,
+    #[serde(rename_all = "camelCase")]
+    Sign { secret_key: String, message: String },
+    #[serde(rename_all = "camelCase")]
+    AggregateKeys { public_keys: Vec<String> },
+    AggregateSignatures { signatures: Vec<String> },
+    #[serde(rename_all = "camelCase")]
+    Verify {
+        signature: String,
+        public_key: String,
+        message: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Export {
+        path: PathBuf,
+        secret_key: String,
+        public_key: String,
+    },
+}
+
+/// Replaces every `${name}` and `${id.field}` in `text` with its value in `scope`.
+fn substitute_text(text: &str, scope: &Map<String, Value>, step: &str) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .unwrap_or_else(|| panic!("Step {}: unterminated ${{ in {:?}", step, text));
+        let reference = &rest[start + 2..start + end];
+        let (name, field) = match reference.split_once('.') {
+            Some((name, field)) => (name, Some(field)),
+            None => (reference, None),
+        };
+        let value = scope
+            .get(name)
+            .and_then(|value| match field {
+                Some(field) => value.get(field),
+                None => Some(value),
+            })
+            .unwrap_or_else(|| panic!("Step {}: nothing is called ${{{}}}", step, reference));
+        match value {
+            Value::String(text) => result.push_str(text),
+            other => result.push_str(&other.to_string()),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+fn substitute(value: &Value, scope: &Map<String, Value>, step: &str) -> Value {
+    match value {
+        Value::String(text) => Value::String(substitute_text(text, scope, step)),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| substitute(item, scope, step))
+            .collect(),
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(name, field)| (name.clone(), substitute(field, scope, step)))
+            .collect(),
+        other => other.clone(),
+    }
+}
+
+fn decode(value: &str, what: &str, step: &str) -> Vec<u8> {
+    hex::decode(value).unwrap_or_else(|_| panic!("Step {}: invalid hex in {}", step, what))
+}
+
+fn decode_all(values: &[String], what: &str, step: &str) -> Vec<Vec<u8>> {
+    values.iter().map(|value| decode(value, what, step)).collect()
+}
+
+/// Runs every step of the job file at `path` with `backend`, which key pair files exported by
+/// the job name as `kind` or `suite`.
+pub fn run(
+    path: &Path,
+    kind: BackendKind,
+    suite: Option<String>,
+    backend: &dyn Backend,
+    config: &Config,
+) -> Value {
+    let contents = fs::read_to_string(path).expect("Failed to read job file");
+    let job: JobFile = serde_json::from_str(&contents).expect("Malformed job file");
+    let mut scope = job.variables;
+    let mut report = Vec::new();
+    for (index, step) in job.steps.iter().enumerate() {
+        let id = step.get("id").and_then(Value::as_str).map(str::to_string);
+        let label = id.clone().unwrap_or_else(|| (index + 1).to_string());
+        let mut fields = substitute(step, &scope, &label);
+        if let Some(fields) = fields.as_object_mut() {
+            fields.remove("id");
+        }
+        let operation: Operation = serde_json::from_value(fields.clone())
+            .unwrap_or_else(|error| panic!("Step {} is malformed: {}", label, error));
+        let output = match operation {
+            Operation::Generate => {
+                let (secret_key, public_key) = backend.generate_keys();
+                json!({
+                    "secretKey": hex::encode(secret_key),
+                    "publicKey": hex::encode(&public_key),
+                    "fingerprint": fingerprint(backend, &public_key),
+                })
+            }
+            Operation::Sign {
+                secret_key,
+                message,
+            } => {
+                let secret_key = decode(&secret_key, "secretKey", &label);
+                json!({ "signature": hex::encode(backend.sign(&secret_key, message.as_bytes())) })
+            }
+            Operation::AggregateKeys { public_keys } => {
+                let public_keys = decode_all(&public_keys, "publicKeys", &label);
+                let public_key = backend.aggregate_public_keys(&public_keys);
+                json!({
+                    "publicKey": hex::encode(&public_key),
+                    "fingerprint": fingerprint(backend, &public_key),
+                })
+            }
+            Operation::AggregateSignatures { signatures } => {
+                let signatures = decode_all(&signatures, "signatures", &label);
+                json!({ "signature": hex::encode(backend.aggregate_signatures(&signatures)) })
+            }
+            Operation::Verify {
+                signature,
+                public_key,
+                message,
+            } => {
+                let valid = backend.verify(
+                    &decode(&signature, "signature", &label),
+                    &decode(&public_key, "publicKey", &label),
+                    message.as_bytes(),
+                );
+                assert!(valid, "Step {}: signature does not verify", label);
+                json!({ "valid": valid })
+            }
+            Operation::Export {
+                path,
+                secret_key,
+                public_key,
+            } => {
+                let secret_key = decode(&secret_key, "secretKey", &label);
+                let public_key = decode(&public_key, "publicKey", &label);
+                assert!(
+                    backend.public_key_from_secret(&secret_key) == public_key,
+                    "Step {}: public key does not belong to the secret key",
+                    label
+                );
+                KeyPairFile::new(kind, suite.clone(), config, &secret_key, &public_key, None)
+                    .write(&path);
+                json!({ "file": path })
+            }
+        };
+        let mut shown = output.clone();
+        if let Some(shown) = shown.as_object_mut() {
+            shown.remove("secretKey");
+        }
+        report.push(json!({ "step": label, "op": fields["op"], "output": shown }));
+        if let Some(id) = id {
+            assert!(
+                scope.insert(id.clone(), output).is_none(),
+                "Step {}: the id is already taken",
+                id
+            );
+        }
+    }
+    json!({ "steps": report })
+}
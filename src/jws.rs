@@ -0,0 +1,71 @@
+//! Compact JWS tokens signed with BLS.
+//!
+//! Tokens have the usual `header.payload.signature` shape, each part base64url-encoded without
+//! padding, and the signature covers the ASCII text `header.payload` as RFC 7515 prescribes.
+//! There is no registered JOSE algorithm for BLS, so the protected header names the backend in
+//! a private `alg` value such as `BLS-BLST`. `jws verify` checks tokens with the `--backend`
+//! it is given and rejects any other `alg`, never letting a token pick its own algorithm. As the
+//! `alg` cannot name a suite or hash-to-curve parameters, those flags are rejected.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde_json::{json, Value};
+
+use crate::backend::BackendKind;
+
+const ALG_PREFIX: &str = "BLS-";
+
+fn alg(backend: BackendKind) -> String {
+    format!("{}{}", ALG_PREFIX, backend.name().to_uppercase())
+}
+
+/// Signs `payload` into a compact token.
+pub fn sign(backend: BackendKind, secret: &[u8], payload: &[u8]) -> String {
+    let header = json!({ "alg": alg(backend), "typ": "JWT" });
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header.to_string()),
+        URL_SAFE_NO_PAD.encode(payload)
+    );
+    let signature = backend
+        .instantiate()
+        .sign(secret, signing_input.as_bytes());
+    format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature))
+}
+
+/// Checks a compact token against a public key under `backend`, returning its validity and
+/// payload.
+pub fn verify(backend: BackendKind, token: &str, public_key: &[u8]) -> Value {
+    let parts: Vec<&str> = token.split('.').collect();
+    let [header, payload, signature] = parts[..] else {
+        panic!("Token must have three dot-separated parts");
+    };
+    let decode = |part: &str, what: &str| {
+        URL_SAFE_NO_PAD
+            .decode(part)
+            .unwrap_or_else(|_| panic!("Invalid base64url in token {}", what))
+    };
+    let protected: Value = serde_json::from_slice(&decode(header, "header"))
+        .expect("Token header is not valid JSON");
+    let expected = alg(backend);
+    let alg = protected["alg"].as_str().expect("Token header has no alg");
+    assert!(
+        alg == expected,
+        "Token alg {} is not {}; select the token's backend with --backend",
+        alg,
+        expected
+    );
+    let backend = backend.instantiate();
+
+    let signing_input = &token[..header.len() + 1 + payload.len()];
+    let valid = backend.verify(
+        &decode(signature, "signature"),
+        public_key,
+        signing_input.as_bytes(),
+    );
+    json!({
+        "valid": valid,
+        "header": protected,
+        "payload": String::from_utf8_lossy(&decode(payload, "payload")),
+    })
+}
@@ -0,0 +1,358 @@
+//! Key pair files.
+//!
+//! `generate-keys --out` writes the secret key, the public key and what they are for (backend or
+//! suite, curve, scheme) into one JSON file, together with a Keccak-256 checksum over all other
+//! fields that catches truncation and hand edits. The file is created with owner-only
+//! permissions and moved into place only once complete, so a crash never leaves half a key
+//! behind; `generate-keys --secret-out` writes a bare hex secret the same way. With the
+//! `encryption` feature the secret can be sealed under a passphrase (scrypt,
+//! then ChaCha20-Poly1305).
+//!
+//! Secret keys in use are wiped from memory when dropped and, with the `encryption` feature,
+//! held in page-locked buffers so they never reach swap: a sealed secret is decrypted straight
+//! into one, and a secret given in hex (`--secret`, `--secret-file` or a plain key pair file) is
+//! moved into one as soon as it is decoded, wiping the hex text and every copy on the way. Only
+//! the `--secret` argument itself stays in the process's arguments, out of the tool's reach;
+//! `--key` and `--secret-file` avoid it. When the memory lock limit (`ulimit -l`) is exhausted
+//! this falls back to ordinary memory with a warning.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::ops::Deref;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ValueEnum;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use zeroize::Zeroize;
+
+use crate::backend::{Backend, BackendKind};
+use crate::config::Config;
+#[cfg(feature = "blst")]
+use crate::suite;
+
+pub const KEYPAIR_VERSION: u32 = 1;
+
+/// Secret key bytes that are wiped when dropped.
+pub struct SecretBytes {
+    bytes: Vec<u8>,
+    /// Keeps the pages holding `bytes` out of swap until they are wiped
+    #[cfg(feature = "encryption")]
+    _lock: Option<region::LockGuard>,
+}
+
+impl SecretBytes {
+    /// Moves `bytes` into a page-locked buffer, wiping the original.
+    pub fn new(bytes: Vec<u8>) -> Self {
+        #[cfg(feature = "encryption")]
+        let (bytes, lock) = {
+            let mut original = bytes;
+            let mut locked = Vec::with_capacity(original.len());
+            let lock = lock(&locked);
+            locked.extend_from_slice(&original);
+            original.zeroize();
+            (locked, lock)
+        };
+        SecretBytes {
+            bytes,
+            #[cfg(feature = "encryption")]
+            _lock: lock,
+        }
+    }
+}
+
+/// Locks the pages of `buffer`'s allocation, which must not grow afterwards.
+#[cfg(feature = "encryption")]
+fn lock(buffer: &Vec<u8>) -> Option<region::LockGuard> {
+    region::lock(buffer.as_ptr(), buffer.capacity())
+        .inspect_err(|err| eprintln!("Warning: could not lock secret key memory: {}", err))
+        .ok()
+}
+
+impl Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl AsRef<[u8]> for SecretBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        // runs before the lock guard is dropped, so the pages are wiped while still locked
+        self.bytes.zeroize();
+    }
+}
+
+/// A secret key sealed under a passphrase.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptedSecret {
+    kdf: String,
+    log_n: u8,
+    r: u32,
+    p: u32,
+    salt: String,
+    cipher: String,
+    ciphertext: String,
+}
+
+#[cfg(feature = "encryption")]
+mod sealing {
+    use chacha20poly1305::aead::{Aead, AeadInPlace, KeyInit};
+    use chacha20poly1305::ChaCha20Poly1305;
+    use rand_core::{OsRng, RngCore};
+    use scrypt::Params;
+    use zeroize::Zeroize;
+
+    use super::{EncryptedSecret, SecretBytes};
+
+    const LOG_N: u8 = 15;
+    const R: u32 = 8;
+    const P: u32 = 1;
+
+    fn cipher(passphrase: &[u8], salt: &[u8], log_n: u8, r: u32, p: u32) -> ChaCha20Poly1305 {
+        let params = Params::new(log_n, r, p, 32).expect("Invalid scrypt parameters");
+        let mut key = [0u8; 32];
+        scrypt::scrypt(passphrase, salt, &params, &mut key).expect("Key derivation failed");
+        let cipher = ChaCha20Poly1305::new(&key.into());
+        key.zeroize();
+        cipher
+    }
+
+    pub fn seal(secret: &[u8], passphrase: &[u8]) -> EncryptedSecret {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        // every salt is fresh, so a fixed nonce never repeats under one key
+        let ciphertext = cipher(passphrase, &salt, LOG_N, R, P)
+            .encrypt(&Default::default(), secret)
+            .expect("Secret key encryption failed");
+        EncryptedSecret {
+            kdf: "scrypt".to_string(),
+            log_n: LOG_N,
+            r: R,
+            p: P,
+            salt: hex::encode(salt),
+            cipher: "chacha20-poly1305".to_string(),
+            ciphertext: hex::encode(ciphertext),
+        }
+    }
+
+    pub fn open(sealed: &EncryptedSecret, passphrase: &[u8]) -> SecretBytes {
+        assert_eq!(sealed.kdf, "scrypt", "Unsupported key derivation function");
+        assert_eq!(sealed.cipher, "chacha20-poly1305", "Unsupported cipher");
+        let salt = hex::decode(&sealed.salt).expect("Invalid hex in salt");
+        let ciphertext = hex::decode(&sealed.ciphertext).expect("Invalid hex in ciphertext");
+        // lock the buffer before the plaintext exists; decrypting in place never reallocates it
+        let mut plaintext = Vec::with_capacity(ciphertext.len());
+        let lock = super::lock(&plaintext);
+        plaintext.extend_from_slice(&ciphertext);
+        let mut secret = SecretBytes {
+            bytes: plaintext,
+            _lock: lock,
+        };
+        cipher(passphrase, &salt, sealed.log_n, sealed.r, sealed.p)
+            .decrypt_in_place(&Default::default(), b"", &mut secret.bytes)
+            .expect("Wrong passphrase or corrupted key pair file");
+        secret
+    }
+}
+
+/// Decodes a hex secret key; the caller wipes the result, so no other copy is left behind.
+pub fn decode_secret(secret: &str) -> Vec<u8> {
+    let mut bytes = vec![0u8; secret.len() / 2];
+    hex::decode_to_slice(secret, &mut bytes).expect("Invalid hex in secret key");
+    bytes
+}
+
+/// Reads a passphrase from the first line of a file.
+#[cfg(feature = "encryption")]
+pub fn read_passphrase(path: &Path) -> Vec<u8> {
+    let contents = fs::read_to_string(path).expect("Failed to read passphrase file");
+    contents.lines().next().unwrap_or_default().as_bytes().to_vec()
+}
+
+/// Writes a file holding secret material with owner-only permissions, linking it to `path` only
+/// once it is complete. An existing file is never replaced, since it may hold the only copy of
+/// another key: linking fails if `path` appeared since the check.
+pub fn write_private(path: &Path, contents: &str) {
+    assert!(!path.exists(), "{} already exists", path.display());
+    // a name of its own, so a staging file left behind by a crash never blocks a later write
+    let mut suffix = [0u8; 8];
+    OsRng.fill_bytes(&mut suffix);
+    let mut staging = path.as_os_str().to_owned();
+    staging.push(format!(".{}.tmp", hex::encode(suffix)));
+    let staging = PathBuf::from(staging);
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let result = options
+        .open(&staging)
+        .and_then(|mut file| {
+            file.write_all(format!("{}\n", contents).as_bytes())
+                .and_then(|_| file.sync_all())
+        })
+        .and_then(|_| fs::hard_link(&staging, path));
+    // linked into place or failed, the staging name goes either way
+    let _ = fs::remove_file(&staging);
+    match result {
+        Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+            panic!("{} already exists", path.display())
+        }
+        result => result.expect("Failed to write secret key file"),
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyPairRecord {
+    version: u32,
+    backend: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    suite: Option<String>,
+    curve: String,
+    scheme: String,
+    created_at: u64,
+    public_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secret_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encrypted_secret_key: Option<EncryptedSecret>,
+}
+
+impl KeyPairRecord {
+    fn checksum(&self) -> String {
+        let canonical = serde_json::to_vec(self).expect("Failed to serialize key pair");
+        hex::encode(Keccak256::digest(canonical))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct KeyPairFile {
+    #[serde(flatten)]
+    record: KeyPairRecord,
+    checksum: String,
+}
+
+impl KeyPairFile {
+    /// Bundles a key pair made by `backend`, or by `suite` when one was selected. The secret is
+    /// sealed when a passphrase is given.
+    #[cfg_attr(
+        not(all(feature = "blst", feature = "encryption")),
+        allow(unused_variables)
+    )]
+    pub fn new(
+        backend: BackendKind,
+        suite: Option<String>,
+        config: &Config,
+        secret: &[u8],
+        public_key: &[u8],
+        passphrase: Option<&[u8]>,
+    ) -> Self {
+        let (curve, scheme) = match &suite {
+            #[cfg(feature = "blst")]
+            Some(name) => ("bls12-381", suite::scheme(name, config)),
+            #[cfg(not(feature = "blst"))]
+            Some(name) => panic!("Suite {} needs blst", name),
+            None => (backend.curve(), "basic"),
+        };
+        let (secret_key, encrypted_secret_key) = match passphrase {
+            #[cfg(feature = "encryption")]
+            Some(passphrase) => (None, Some(sealing::seal(secret, passphrase))),
+            #[cfg(not(feature = "encryption"))]
+            Some(_) => panic!("Encrypting key pair files needs the encryption feature"),
+            None => (Some(hex::encode(secret)), None),
+        };
+        let record = KeyPairRecord {
+            version: KEYPAIR_VERSION,
+            backend: backend.name(),
+            suite,
+            curve: curve.to_string(),
+            scheme: scheme.to_string(),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("System clock is before the Unix epoch")
+                .as_secs(),
+            public_key: hex::encode(public_key),
+            secret_key,
+            encrypted_secret_key,
+        };
+        let checksum = record.checksum();
+        KeyPairFile { record, checksum }
+    }
+
+    pub fn read(path: &Path) -> Self {
+        let contents = fs::read_to_string(path).expect("Failed to read key pair file");
+        let file: KeyPairFile =
+            serde_json::from_str(&contents).expect("Key pair file is not a valid key pair");
+        assert_eq!(
+            file.record.version, KEYPAIR_VERSION,
+            "Unsupported key pair version"
+        );
+        assert_eq!(
+            file.record.checksum(),
+            file.checksum,
+            "Key pair file checksum does not match its contents"
+        );
+        file
+    }
+
+    pub fn write(&self, path: &Path) {
+        let contents = serde_json::to_string_pretty(self).expect("Failed to serialize key pair");
+        write_private(path, &contents);
+    }
+
+    /// Name of the backend the key pair was made with.
+    pub fn backend(&self) -> &str {
+        &self.record.backend
+    }
+
+    pub fn suite(&self) -> Option<&str> {
+        self.record.suite.as_deref()
+    }
+
+    pub fn public_key(&self) -> Vec<u8> {
+        hex::decode(&self.record.public_key).expect("Invalid hex in public key")
+    }
+
+    /// The secret key, unsealed with `passphrase` if the file is encrypted.
+    #[cfg_attr(not(feature = "encryption"), allow(unused_variables))]
+    pub fn secret(&self, passphrase: Option<&[u8]>) -> SecretBytes {
+        match (&self.record.secret_key, &self.record.encrypted_secret_key) {
+            (Some(secret), _) => SecretBytes::new(decode_secret(secret)),
+            #[cfg(feature = "encryption")]
+            (None, Some(sealed)) => sealing::open(
+                sealed,
+                passphrase.expect("Key pair file is encrypted; pass --passphrase-file"),
+            ),
+            #[cfg(not(feature = "encryption"))]
+            (None, Some(_)) => panic!("Key pair file is encrypted, which needs the encryption feature"),
+            (None, None) => panic!("Key pair file holds no secret key"),
+        }
+    }
+
+    /// The backend the key pair was made for.
+    #[cfg_attr(not(feature = "blst"), allow(unused_variables))]
+    pub fn instantiate(&self, config: &Config) -> Box<dyn Backend> {
+        match &self.record.suite {
+            #[cfg(feature = "blst")]
+            Some(name) => suite::instantiate(name, config),
+            #[cfg(not(feature = "blst"))]
+            Some(name) => panic!("Key pair was made under suite {}, which needs blst", name),
+            None => BackendKind::from_str(&self.record.backend, true)
+                .expect("Key pair names an unknown backend")
+                .instantiate(),
+        }
+    }
+}
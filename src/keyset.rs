@@ -0,0 +1,128 @@
+//! Merkle commitments over a set of public keys.
+//!
+//! A contract can store one 32-byte root for a whole committee and still check that a given key
+//! belongs to it, from a proof of `depth` sibling hashes. The tree is built with Keccak-256 the
+//! way a Solidity verifier would recompute it:
+//!
+//! - leaf `i` is `keccak256(0x00 ‖ key_i)`, over the key's canonical encoding;
+//! - an inner node is `keccak256(0x01 ‖ left ‖ right)`;
+//! - the leaves are padded with 32 zero bytes up to the next power of two, so the bits of the
+//!   index, lowest first, say whether each sibling sits to the right (0) or the left (1).
+//!
+//! The prefixes keep a leaf from passing for an inner node. Keys are checked to be in the
+//! backend's canonical encoding, since another encoding of the same key would commit to a
+//! different leaf.
+
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+
+use crate::backend::Backend;
+use crate::fingerprint::fingerprint;
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+fn leaf(public_key: &[u8]) -> [u8; 32] {
+    Keccak256::new()
+        .chain_update([LEAF_PREFIX])
+        .chain_update(public_key)
+        .finalize()
+        .into()
+}
+
+fn node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Keccak256::new()
+        .chain_update([NODE_PREFIX])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// Every level of the tree, from the padded leaves up to the root.
+fn levels(backend: &dyn Backend, public_keys: &[Vec<u8>]) -> Vec<Vec<[u8; 32]>> {
+    assert!(!public_keys.is_empty(), "A key set needs at least one key");
+    let mut leaves: Vec<[u8; 32]> = public_keys
+        .iter()
+        .enumerate()
+        .map(|(index, key)| {
+            // aggregating a single key re-encodes it canonically
+            assert!(
+                backend.aggregate_public_keys(std::slice::from_ref(key)) == *key,
+                "Public key {} is not in canonical encoding",
+                index
+            );
+            leaf(key)
+        })
+        .collect();
+    leaves.resize(public_keys.len().next_power_of_two(), [0u8; 32]);
+    let mut levels = vec![leaves];
+    while levels.last().unwrap().len() > 1 {
+        let next = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| node(&pair[0], &pair[1]))
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// The root of the tree over `public_keys`.
+pub fn commit(backend: &dyn Backend, public_keys: &[Vec<u8>]) -> Value {
+    let levels = levels(backend, public_keys);
+    json!({
+        "root": hex::encode(levels.last().unwrap()[0]),
+        "keys": public_keys.len(),
+        "depth": levels.len() - 1,
+    })
+}
+
+/// The proof that key `index` of `public_keys` is in the tree.
+pub fn prove(backend: &dyn Backend, public_keys: &[Vec<u8>], index: usize) -> Value {
+    assert!(
+        index < public_keys.len(),
+        "Index {} is outside the {} keys",
+        index,
+        public_keys.len()
+    );
+    let levels = levels(backend, public_keys);
+    let proof: Vec<String> = levels[..levels.len() - 1]
+        .iter()
+        .enumerate()
+        .map(|(height, level)| hex::encode(level[(index >> height) ^ 1]))
+        .collect();
+    json!({
+        "root": hex::encode(levels.last().unwrap()[0]),
+        "index": index,
+        "publicKey": hex::encode(&public_keys[index]),
+        "fingerprint": fingerprint(backend, &public_keys[index]),
+        "proof": proof,
+    })
+}
+
+/// Whether `proof` shows `public_key` at `index` under `root`.
+pub fn verify_proof(root: &[u8], public_key: &[u8], index: usize, proof: &[Vec<u8>]) -> Value {
+    assert!(
+        index.checked_shr(proof.len() as u32).unwrap_or(0) == 0,
+        "Index {} does not fit a proof of depth {}",
+        index,
+        proof.len()
+    );
+    let computed = proof
+        .iter()
+        .enumerate()
+        .fold(leaf(public_key), |hash, (height, sibling)| {
+            let sibling: [u8; 32] = sibling
+                .as_slice()
+                .try_into()
+                .expect("Proof hashes must be 32 bytes");
+            if (index >> height) & 1 == 0 {
+                node(&hash, &sibling)
+            } else {
+                node(&sibling, &hash)
+            }
+        });
+    json!({ "valid": computed.as_slice() == root })
+}
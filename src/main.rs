@@ -1,155 +1,2529 @@
-use clap::{Parser, Subcommand};
-use sylow::{KeyPair, Fp, G1Projective, G2Projective, G1Affine, G2Affine, GroupTrait, pairing, XMDExpander};
-use serde_json::json;
-use hex;
-use sha3::Keccak256;
+#[cfg(feature = "age")]
+mod age_file;
+mod audit;
+mod backend;
+mod batch;
+mod bbs;
+mod beacon;
+mod bundle;
+mod calldata_profile;
+mod config;
+mod conformance;
+mod convert;
+mod credential;
+mod did;
+mod difftest;
+mod doctor;
+#[cfg(feature = "blst")]
+mod drand;
+mod eip712;
+mod envelope;
+mod equivocation;
+mod evm;
+mod explain;
+mod fingerprint;
+mod fixtures;
+mod frame;
+mod fixed_base;
+mod gpg;
+mod hash_to_curve;
+mod hash_to_field;
+mod jobs;
+mod jws;
+mod keypair;
+mod keyset;
+mod math;
+mod plan;
+mod point;
+mod pop;
+#[cfg(feature = "poseidon")]
+mod prehash;
+mod provenance;
+mod pssm;
+#[cfg(feature = "qr")]
+mod qr;
+mod queue;
+mod regress;
+mod secret;
+#[cfg(feature = "rpc")]
+mod rpc;
+#[cfg(feature = "blst")]
+mod suite;
+mod template;
+mod threads;
+#[cfg(feature = "timelock")]
+mod timelock;
+mod timing;
+mod vanity;
+mod witness;
 
-const DST: &[u8; 30] = b"WARLOCK-CHAOS-V01-CS01-SHA-256";
-const SECURITY_BITS: u64 = 128;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use rand_core::{OsRng, RngCore};
+use serde_json::{json, Value};
+use zeroize::Zeroizing;
+
+use crate::backend::sylow::SylowBackend;
+use crate::backend::{Backend, BackendKind};
+use crate::calldata_profile::CalldataProfile;
+use crate::config::Config;
+use crate::envelope::Envelope;
+use crate::fingerprint::fingerprint;
+use crate::frame::StreamFormat;
+use crate::hash_to_curve::{ExpanderKind, HashParams};
+use crate::keypair::{KeyPairFile, SecretBytes};
 
 #[derive(Parser)]
 #[command(name = "BLS Tool")]
 #[command(version = "1.0")]
 #[command(about = "Tool for BLS key generation, signing, and aggregation")]
 struct Cli {
+    /// Curve backend performing the cryptographic operations
+    #[arg(long, global = true, value_enum, default_value_t = BackendKind::Sylow)]
+    backend: BackendKind,
+
+    /// Named ciphersuite (chia, eth2, filecoin or one from the config file); overrides --backend
+    #[cfg(feature = "blst")]
+    #[arg(long, global = true)]
+    suite: Option<String>,
+
+    /// Message expander for hashing to the curve (sylow only)
+    #[arg(long, global = true, value_enum, default_value_t = ExpanderKind::Xmd)]
+    expander: ExpanderKind,
+
+    /// Security parameter k of hash-to-curve (sylow only)
+    #[arg(long, global = true, default_value_t = 128, hide_short_help = true)]
+    security_bits: u64,
+
+    /// Bytes expanded per field element; derived from --security-bits unless given (sylow only)
+    #[arg(long, global = true, hide_short_help = true)]
+    expansion_len: Option<usize>,
+
+    /// Config file to use instead of ~/.config/bls-tools/config.json
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Reduce secret keys at or above the group order instead of rejecting them
+    #[arg(long, global = true)]
+    reduce: bool,
+
+    /// Print the result as a QR code in the terminal (single values and `envelope show`)
+    #[cfg(feature = "qr")]
+    #[arg(long, global = true)]
+    qr: bool,
+
+    /// Write the result as a QR code PNG image here instead of printing it
+    #[cfg(feature = "qr")]
+    #[arg(long, global = true, conflicts_with = "qr")]
+    qr_png: Option<PathBuf>,
+
+    /// Threads for vanity search, aggregation and batch verification; defaults to one per CPU
+    #[arg(long, global = true)]
+    threads: Option<usize>,
+
+    /// Run only on these CPUs, as a list like `0-3,6` (Linux only)
+    #[arg(long, global = true)]
+    cpu_affinity: Option<String>,
+
+    /// Layout of points in EVM calldata: bn254-precompile, bls12381-eip2537, gnosis or a JSON file
+    #[arg(long, global = true)]
+    calldata_profile: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    GenerateKeys,
+    GenerateKeys {
+        /// Write the key pair into a file instead of printing the secret key
+        #[arg(short, long, group = "key_file")]
+        out: Option<PathBuf>,
+
+        /// Write only the hex secret key into this file and print only the public key
+        #[arg(long, conflicts_with_all = ["out", "count"])]
+        secret_out: Option<PathBuf>,
+
+        /// With --secret-out, print the public key as JSON along with its fingerprint
+        #[arg(long, requires = "secret_out")]
+        json: bool,
+
+        /// Generate this many key pairs into --out-dir and print a manifest of them
+        #[arg(long, requires = "out_dir")]
+        count: Option<usize>,
+
+        /// Directory receiving one key pair file per key, named after its alias
+        #[arg(long, group = "key_file", requires = "count")]
+        out_dir: Option<PathBuf>,
+
+        /// Aliases of batch keys are this prefix followed by 1, 2, 3, ...
+        #[arg(long, default_value = "key-", requires = "count")]
+        prefix: String,
+
+        /// Keep generating until the hex public key starts with this prefix
+        #[arg(long, conflicts_with = "count")]
+        vanity_prefix: Option<String>,
+
+        /// Encrypt the secret key in the file under the passphrase on this file's first line
+        #[cfg(feature = "encryption")]
+        #[arg(long, requires = "key_file")]
+        passphrase_file: Option<PathBuf>,
+    },
     Sign {
-        #[arg(short, long)]
-        secret: String,
+        #[command(flatten)]
+        secret: SecretSource,
+
+        #[arg(short, long, required_unless_present_any = ["stream", "template"])]
+        message: Option<String>,
 
+        /// Write the result into an envelope file instead of printing the signature
         #[arg(short, long)]
-        message: String,
+        out: Option<PathBuf>,
+
+        /// Make the envelope's signature valid only for this long from now, e.g. `15m` or `1h`
+        #[arg(long, requires = "out", value_parser = envelope::parse_duration)]
+        expires_in: Option<u64>,
+
+        /// Bind the envelope's signature to this nonce or request context
+        #[arg(long, requires = "out")]
+        nonce: Option<String>,
+
+        /// Sign every line read from stdin, printing one signature per line as soon as it is ready
+        #[arg(long, conflicts_with_all = ["message", "out"])]
+        stream: bool,
+
+        /// How streamed messages are read
+        #[arg(long, value_enum, default_value_t = StreamFormat::Text, requires = "stream")]
+        input_format: StreamFormat,
+
+        /// How streamed signatures are written
+        #[arg(long, value_enum, default_value_t = StreamFormat::Text, requires = "stream")]
+        output_format: StreamFormat,
+
+        /// Sign the message a config file template lays out for the `--field` values
+        #[arg(long, conflicts_with_all = ["message", "out", "stream"])]
+        template: Option<String>,
+
+        /// Check the key and lay out the message, then report what would be signed instead
+        #[arg(long, conflicts_with = "stream")]
+        dry_run: bool,
+
+        /// Template field value, as `name=value`
+        #[arg(long = "field", requires = "template")]
+        fields: Vec<String>,
+
+        /// Append a hash-chained record of every signature to this log
+        #[arg(long)]
+        audit_log: Option<PathBuf>,
+
+        /// Sign a digest of the message's fields instead of the message itself
+        #[cfg(feature = "poseidon")]
+        #[arg(long, value_enum, conflicts_with_all = ["out", "template"])]
+        prehash: Option<prehash::Prehash>,
     },
     PublicKeyFromSecret {
+        #[command(flatten)]
+        secret: SecretSource,
+        /// Group to express the key in; defaults to the backend's public key group
+        #[arg(long, value_enum)]
+        group: Option<KeyGroup>,
+
+        /// Print the key as JSON along with its fingerprint
+        #[arg(long, conflicts_with = "group")]
+        json: bool,
+    },
+    /// Sign the EIP-712 digest of typed structured data
+    SignTyped {
+        #[command(flatten)]
+        secret: SecretSource,
+        /// JSON object mapping struct names to their `{name, type}` members
+        #[arg(long)]
+        types: PathBuf,
+        /// JSON object with the domain's fields
+        #[arg(long)]
+        domain: PathBuf,
+        /// JSON object with the message's fields
+        #[arg(long)]
+        data: PathBuf,
+        /// Struct the message is an instance of; inferred when only one is unreferenced
+        #[arg(long)]
+        primary_type: Option<String>,
+    },
+    /// Verify a signature made by `sign-typed`
+    VerifyTyped {
         #[arg(short, long)]
-        secret: String,
+        signature: String,
+        #[arg(short, long)]
+        public_key: String,
+        #[arg(long)]
+        types: PathBuf,
+        #[arg(long)]
+        domain: PathBuf,
+        #[arg(long)]
+        data: PathBuf,
+        #[arg(long)]
+        primary_type: Option<String>,
+    },
+    /// Check that a secret key is nonzero and below the group order
+    CheckSecret {
+        #[command(flatten)]
+        secret: SecretSource,
+    },
+    /// Generate keys, signatures and aggregates as test fixtures for client libraries
+    ExportFixtures {
+        #[arg(long, value_enum)]
+        format: fixtures::FixtureFormat,
+        /// Number of key pairs to generate
+        #[arg(long, default_value_t = 3)]
+        keys: usize,
+        /// Write the fixtures here instead of stdout
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Recompute every value of a fixtures file and report the ones that differ
+    ImportFixtures { file: PathBuf },
+    /// Record or check golden outputs of every backend and built-in suite
+    Regress {
+        #[command(subcommand)]
+        command: RegressCommands,
+    },
+    /// Convert a key or signature of the --backend curve between encodings
+    Convert {
+        #[arg(long, value_enum)]
+        from: convert::Format,
+        #[arg(long, value_enum)]
+        to: convert::Format,
+        value: String,
+    },
+    /// Report the backend, settings and environment in use and run a quick self-test
+    Doctor,
+    /// Guess what a hex value (or JSON document) is and which commands take it
+    Explain { value: String },
+    /// Print the short fingerprint of a public key
+    Fingerprint { public_key: String },
+    /// Check that a public key was derived from a secret key
+    CheckKeypair {
+        #[command(flatten)]
+        secret: SecretSource,
+        #[arg(short, long)]
+        public_key: String,
     },
     AggregateKeys {
         #[arg(short, long, num_args=1..)]
         public_keys: Vec<String>,
+
+        /// Print the aggregate key as JSON along with its fingerprint
+        #[arg(long)]
+        json: bool,
     },
     AggregateSignatures {
+        /// Signatures, or `@file` for a file with one signature per line
         #[arg(short, long, num_args=1..)]
         signatures: Vec<String>,
+
+        /// Write a record of every contribution, its source and the aggregate to this file
+        #[arg(long)]
+        provenance: Option<PathBuf>,
+
+        /// Signers' public keys in the order of the signatures, or `@file`, to check each one
+        #[arg(short, long, num_args = 1.., requires = "message")]
+        public_keys: Vec<String>,
+
+        /// Message every signer signed
+        #[arg(short, long, requires = "public_keys")]
+        message: Option<String>,
+
+        /// Leave out signatures that do not verify on their own, and report them
+        #[arg(long, requires = "public_keys")]
+        validate: bool,
     },
     Verify {
+        #[arg(short, long, required_unless_present = "envelope")]
+        signature: Option<String>,
+
+        /// The signer's public key; with --envelope, the key the envelope must be signed by
+        #[arg(short, long, required_unless_present = "envelope", group = "signer")]
+        public_key: Option<String>,
+
+        #[arg(short, long, required_unless_present_any = ["envelope", "template"])]
+        message: Option<String>,
+
+        /// Verify the contents of an envelope file written by `sign --out`
+        #[arg(short, long, conflicts_with_all = ["signature", "message"])]
+        envelope: Option<PathBuf>,
+
+        /// Fingerprint of the key the envelope must be signed by
+        #[arg(long, requires = "envelope", group = "signer")]
+        fingerprint: Option<String>,
+
+        /// Unix time to check the envelope's validity window at, instead of now
+        #[arg(long, requires = "envelope")]
+        at: Option<u64>,
+
+        /// Reject envelopes without a nonce or whose nonce this file lists, and add the nonces of
+        /// accepted ones; needs the signer pinned with --public-key or --fingerprint
+        #[arg(long, requires_all = ["envelope", "signer"])]
+        seen_nonces: Option<PathBuf>,
+
+        /// Verify the message a config file template lays out for the `--field` values
+        #[arg(long, conflicts_with_all = ["message", "envelope"])]
+        template: Option<String>,
+
+        /// Template field value, as `name=value`
+        #[arg(long = "field", requires = "template")]
+        fields: Vec<String>,
+
+        /// Digest the message's fields the same way `sign --prehash` did
+        #[cfg(feature = "poseidon")]
+        #[arg(long, value_enum, conflicts_with_all = ["envelope", "template"])]
+        prehash: Option<prehash::Prehash>,
+    },
+    /// Run the operations of a job file in order
+    Run { job: PathBuf },
+    /// Verify a JSON-lines file of signature, public key and message records
+    BatchVerify {
+        input: PathBuf,
+        /// Write progress to this file every `--checkpoint-every` records and at the end
+        #[arg(long)]
+        checkpoint: Option<PathBuf>,
+        #[arg(long, default_value_t = 10000, requires = "checkpoint")]
+        checkpoint_every: u64,
+        /// Continue the run a checkpoint file was written for
+        #[arg(long)]
+        resume: Option<PathBuf>,
+    },
+    /// Report keys that signed two different messages in the same context
+    DetectEquivocation {
+        /// JSON-lines file of public key, context, message and signature records
+        #[arg(long)]
+        records: PathBuf,
+    },
+    /// Group records by message and aggregate each group's signatures
+    PlanAggregate {
+        /// JSON-lines file of signature, public key and message records, as for `batch-verify`
+        #[arg(long)]
+        records: PathBuf,
+        /// Write each group's aggregate as a `batch-verify` record to this file
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Package misbehaviour evidence for a dispute or slashing process
+    Evidence {
+        #[command(subcommand)]
+        command: EvidenceCommands,
+    },
+    /// Endorse an envelope by appending a countersignature over it
+    Countersign {
+        #[arg(short, long)]
+        envelope: PathBuf,
+        /// Key pair file written by `generate-keys --out` of the endorsing signer
+        #[arg(short, long)]
+        key: PathBuf,
+        /// File whose first line is the passphrase of an encrypted key pair
+        #[cfg(feature = "encryption")]
+        #[arg(long)]
+        passphrase_file: Option<PathBuf>,
+    },
+    /// Print the contents of every QR code in a PNG or JPEG image
+    #[cfg(feature = "qr")]
+    Scan { image: PathBuf },
+    /// Sign in several parties by passing one partially signed message file around
+    Pssm {
+        #[command(subcommand)]
+        command: PssmCommands,
+    },
+    /// Inspect envelope files
+    Envelope {
+        #[command(subcommand)]
+        command: EnvelopeCommands,
+    },
+    /// Commit to a set of public keys with a Merkle root and prove membership in it
+    Keyset {
+        #[command(subcommand)]
+        command: KeysetCommands,
+    },
+    /// Queue signatures while offline and deliver them once connected
+    Queue {
+        #[command(subcommand)]
+        command: QueueCommands,
+    },
+    /// Check signing audit logs
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommands,
+    },
+    /// Work with key pair files written by `generate-keys --out`
+    Key {
+        #[command(subcommand)]
+        command: KeyCommands,
+    },
+    /// BBS signatures over many messages with selective disclosure proofs
+    Bbs {
+        #[command(subcommand)]
+        command: BbsCommands,
+    },
+    /// Sign and verify W3C Verifiable Credentials
+    Vc {
+        #[command(subcommand)]
+        command: VcCommands,
+    },
+    /// Convert between public keys and did:key identifiers
+    Did {
+        #[command(subcommand)]
+        command: DidCommands,
+    },
+    /// Sign and verify compact JWS tokens
+    Jws {
+        #[command(subcommand)]
+        command: JwsCommands,
+    },
+    /// Check public randomness beacons
+    #[cfg(feature = "blst")]
+    Drand {
+        #[command(subcommand)]
+        command: DrandCommands,
+    },
+    /// Encrypt data that opens once a beacon signs a future round
+    #[cfg(feature = "timelock")]
+    Timelock {
+        #[command(subcommand)]
+        command: TimelockCommands,
+    },
+    /// Run a chained threshold randomness beacon
+    Beacon {
+        #[command(subcommand)]
+        command: BeaconCommands,
+    },
+    /// Show the ciphersuites available to --suite
+    #[cfg(feature = "blst")]
+    Suite {
+        #[command(subcommand)]
+        command: SuiteCommands,
+    },
+    /// Generate random well-formed values in the --backend's encodings
+    Random {
+        #[command(subcommand)]
+        command: RandomCommands,
+        /// Number of values to generate, one per line
+        #[arg(short = 'n', long, global = true, default_value_t = 1)]
+        count: usize,
+    },
+    /// BN254 field and scalar arithmetic on hex values
+    Math {
+        #[command(subcommand)]
+        command: MathCommands,
+    },
+    /// BN254 group arithmetic on hex-encoded points
+    Point {
+        #[command(subcommand)]
+        command: PointCommands,
+    },
+    /// Dump every intermediate value of an aggregate verification as circuit inputs (sylow only)
+    ExportWitness {
+        #[arg(short, long)]
+        signature: String,
+        #[arg(short, long, num_args = 1.., required = true)]
+        public_keys: Vec<String>,
+        #[arg(short, long)]
+        message: String,
+    },
+    /// Send an aggregate signature to a verifier contract (sylow only)
+    Submit {
+        /// JSON-RPC endpoint of a node with an unlocked sending account
+        #[arg(long, required_unless_present = "dry_run")]
+        rpc_url: Option<String>,
+        /// Account sending the transaction
+        #[arg(long, required_unless_present = "dry_run")]
+        from: Option<String>,
+        #[arg(long)]
+        contract: String,
+        /// Function name; it is called as `<method>(uint256[2],bytes,bytes32)`
+        #[arg(long)]
+        method: String,
         #[arg(short, long)]
         signature: String,
+        /// Hex bitfield of the committee members whose signatures were aggregated
+        #[arg(long)]
+        bitfield: String,
+        /// Message the signature covers; the contract receives its Keccak-256 hash
+        #[arg(short, long)]
+        message: String,
+        /// Print the unsigned transaction instead of sending it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Aggregate committee signatures over an assertion into one publishable proof (sylow only)
+    BundleProof {
+        /// Assertion id, whose bytes are the signed message
+        #[arg(long)]
+        assertion_id: String,
+        /// Signatures, or `@file` for a file with one signature per line
+        #[arg(short, long, num_args = 1.., required = true)]
+        signatures: Vec<String>,
+        /// JSON array of the committee's public keys, in bitfield order
+        #[arg(long)]
+        committee: PathBuf,
+        /// Contract function receiving the proof, called as in `submit`
+        #[arg(long, default_value = "submitAggregate")]
+        method: String,
+    },
+    /// Prove possession of a secret key before joining a committee (sylow only)
+    PopProve {
+        #[command(flatten)]
+        secret: SecretSource,
+    },
+    /// Check every committee member's proof of possession in one batch (sylow only)
+    PopVerifyBatch {
+        /// JSON array of the committee's public keys
+        #[arg(long)]
+        committee: PathBuf,
+        /// Proofs in committee order, or `@file` for a file with one proof per line
+        #[arg(long, num_args = 1.., required = true)]
+        proofs: Vec<String>,
+    },
+    /// Check an aggregate signature submitted on-chain by `submit` (sylow only)
+    VerifyOnchain {
+        #[arg(long, requires = "tx")]
+        rpc_url: Option<String>,
+        /// Hash of the submitting transaction, fetched from --rpc-url
+        #[arg(long, requires = "rpc_url", required_unless_present = "calldata")]
+        tx: Option<String>,
+        /// Hex input data of the transaction, instead of fetching it
+        #[arg(long, conflicts_with = "tx")]
+        calldata: Option<String>,
+        /// Committee public keys, in the order the bitfield indexes them
+        #[arg(short, long, num_args = 1.., required = true)]
+        public_keys: Vec<String>,
+        #[arg(short, long)]
+        message: String,
+    },
+    /// Print every stage of hashing a message to the curve (sylow only)
+    HashToField {
+        #[arg(short, long)]
+        message: String,
+        /// Number of field elements to derive
+        #[arg(short, long, default_value_t = 2)]
+        count: usize,
+    },
+    /// Run published test vectors through the implementations in this build
+    Conformance {
+        #[command(subcommand)]
+        command: ConformanceCommands,
+    },
+    /// Run random operations through every backend of a curve and compare the results (dev)
+    Difftest {
+        /// Operations to compare
+        #[arg(long, value_delimiter = ',', default_value = "sign,verify,aggregate")]
+        ops: Vec<difftest::Operation>,
+        /// Number of iterations
+        #[arg(long, default_value_t = 1000)]
+        n: usize,
+    },
+    /// Statistically compare signing times across secret-key and message classes (dev)
+    #[command(hide = true)]
+    TimingTest {
+        #[arg(short = 'n', long, default_value_t = 2000)]
+        samples: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum RegressCommands {
+    /// Write golden files computed from fixed keys
+    Record {
+        /// Directory to write the golden files into
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Recompute golden files and report every value that changed
+    Check { directory: PathBuf },
+}
+
+#[derive(Subcommand)]
+enum ConformanceCommands {
+    /// Check hash-to-curve and message expansion against an RFC 9380 vectors file
+    HashToCurve {
+        #[arg(long)]
+        vectors: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum RandomCommands {
+    /// A secret key
+    Scalar,
+    /// A point of 𝔾₁
+    G1,
+    /// A point of 𝔾₂
+    G2,
+    /// Random bytes
+    Message {
+        /// Length in bytes
+        #[arg(long, default_value_t = 32)]
+        len: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum MathCommands {
+    /// Reduce an integer of any length modulo the group order
+    ScalarMod { value: String },
+    /// Invert a scalar modulo the group order
+    ScalarInvert { scalar: String },
+    /// Add two base field elements
+    FpAdd { a: String, b: String },
+    /// Multiply two Fp2 elements given as c1 ‖ c0
+    Fp2Mul { a: String, b: String },
+}
+
+#[derive(Subcommand)]
+enum PointCommands {
+    /// Add points together
+    Add {
+        #[arg(short, long, value_enum)]
+        group: point::Group,
+        #[arg(num_args = 1.., required = true)]
+        points: Vec<String>,
+    },
+    /// Multiply a point by a scalar
+    Mul {
+        #[arg(short, long, value_enum)]
+        group: point::Group,
+        point: String,
+        scalar: String,
+    },
+    /// Negate a point
+    Neg {
+        #[arg(short, long, value_enum)]
+        group: point::Group,
+        point: String,
+    },
+    /// Check that a point is on the curve (and, for G2, in the subgroup)
+    IsOnCurve {
+        #[arg(short, long, value_enum)]
+        group: point::Group,
+        point: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum PssmCommands {
+    /// Start a signing round over a message with the current backend
+    Create {
+        #[arg(short, long)]
+        message: String,
+        /// JSON array of the committee's public keys, in bitfield order
+        #[arg(long)]
+        committee: PathBuf,
+        /// Signatures needed to finalize; all members by default
+        #[arg(long)]
+        threshold: Option<usize>,
+        /// Metadata to carry along, as `key=value`
+        #[arg(long = "meta")]
+        metadata: Vec<String>,
+        #[arg(short, long)]
+        out: PathBuf,
+    },
+    /// Sign the round's message as a committee member, or file a signature made elsewhere
+    AddSignature {
+        file: PathBuf,
+        #[command(flatten)]
+        secret: SecretSource,
+        /// A signature made elsewhere, instead of signing with a secret key
+        #[arg(long, conflicts_with_all = ["secret", "secret_file", "key"])]
+        signature: Option<String>,
+    },
+    /// Merge the partial signatures of copies of one round signed in parallel
+    Combine {
+        #[arg(required = true, num_args = 2..)]
+        files: Vec<PathBuf>,
+        #[arg(short, long)]
+        out: PathBuf,
+    },
+    /// Aggregate the partial signatures once enough members have signed
+    Finalize { file: PathBuf },
+}
+
+#[derive(Subcommand)]
+enum EnvelopeCommands {
+    /// Pretty-print the contents of an envelope
+    Show { envelope: PathBuf },
+}
+
+#[derive(Subcommand)]
+enum EvidenceCommands {
+    /// Bundle an equivocation found by `detect-equivocation` into one artifact
+    Bundle {
+        /// Output of `detect-equivocation`
+        #[arg(long)]
+        report: PathBuf,
+        /// Which of the report's equivocations to bundle, from 0
+        #[arg(long, default_value_t = 0)]
+        index: usize,
+        /// Contract function receiving the evidence (sylow only)
+        #[arg(long, default_value = "reportEquivocation")]
+        method: String,
+    },
+}
 
+#[derive(Subcommand)]
+enum KeysetCommands {
+    /// Print the Merkle root over the keys, in order
+    Commit {
+        /// Public keys, or `@file` for a file with one key per line
+        #[arg(short, long, num_args = 1.., required = true)]
+        public_keys: Vec<String>,
+    },
+    /// Print the proof that the key at an index is in the set
+    Prove {
+        /// Public keys, or `@file` for a file with one key per line
+        #[arg(short, long, num_args = 1.., required = true)]
+        public_keys: Vec<String>,
+        /// Position of the key, from 0
+        #[arg(long)]
+        index: usize,
+    },
+    /// Check a membership proof against a root
+    VerifyProof {
+        #[arg(long)]
+        root: String,
         #[arg(short, long)]
         public_key: String,
+        #[arg(long)]
+        index: usize,
+        /// Sibling hashes from `keyset prove`, lowest first; none for a single-key set
+        #[arg(long, num_args = 0..)]
+        proof: Vec<String>,
+    },
+}
 
+#[derive(Subcommand)]
+enum QueueCommands {
+    /// Sign a message and append the signature to a queue file
+    Sign {
+        #[arg(long)]
+        queue: PathBuf,
+        #[command(flatten)]
+        secret: SecretSource,
         #[arg(short, long)]
         message: String,
     },
+    /// POST every queued entry to a collector, keeping those not delivered
+    Flush {
+        #[arg(long)]
+        queue: PathBuf,
+        #[arg(long)]
+        collector_url: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuditCommands {
+    /// Check that every entry of an audit log chains to the one before it
+    Verify {
+        log: PathBuf,
+
+        /// Head hash reported by an earlier check; the log must still contain that entry, so
+        /// entries dropped from the end since then are caught
+        #[arg(long)]
+        anchor: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeyCommands {
+    /// Check a key pair file and print its public key; commands take the file itself with --key
+    Load {
+        file: PathBuf,
+
+        /// Also print the secret key, in hex
+        #[arg(long)]
+        reveal: bool,
+
+        /// File whose first line is the passphrase of an encrypted key pair
+        #[cfg(feature = "encryption")]
+        #[arg(long)]
+        passphrase_file: Option<PathBuf>,
+    },
+    /// Store a gpg-encrypted hex secret key in a key pair file
+    Import {
+        /// OpenPGP message holding the hex secret key
+        #[arg(long)]
+        gpg: PathBuf,
+
+        #[arg(short, long)]
+        out: PathBuf,
+
+        /// Encrypt the secret key in the file under the passphrase on this file's first line
+        #[cfg(feature = "encryption")]
+        #[arg(long)]
+        passphrase_file: Option<PathBuf>,
+    },
+    /// Write a key pair file's secret key encrypted to age or OpenPGP recipients
+    #[command(group(clap::ArgGroup::new("recipient").required(true)))]
+    Export {
+        file: PathBuf,
+
+        /// age (`age1…`) or SSH public key to encrypt to, for `sign --secret-file`; repeat for
+        /// several
+        #[cfg(feature = "age")]
+        #[arg(long, group = "recipient", conflicts_with = "gpg_recipient")]
+        age_recipient: Vec<String>,
+
+        /// OpenPGP key id to encrypt to with gpg; repeat for several
+        #[arg(long, group = "recipient")]
+        gpg_recipient: Vec<String>,
+
+        #[arg(short, long)]
+        out: PathBuf,
+
+        /// File whose first line is the passphrase of an encrypted key pair
+        #[cfg(feature = "encryption")]
+        #[arg(long)]
+        passphrase_file: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum VcCommands {
+    /// Attach a Data Integrity proof to a credential
+    Sign {
+        #[arg(short, long)]
+        credential: PathBuf,
+        #[command(flatten)]
+        secret: SecretSource,
+        /// Write the signed credential here instead of stdout
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Check the proof attached to a credential
+    Verify {
+        #[arg(short, long)]
+        credential: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum DidCommands {
+    /// Print the did:key identifier and DID document for a public key
+    FromKey {
+        #[arg(short, long)]
+        public_key: String,
+    },
+    /// Print the public key and DID document behind a did:key identifier
+    Resolve { did: String },
+}
+
+#[derive(Subcommand)]
+enum JwsCommands {
+    /// Sign a payload (usually JSON claims) into a `header.payload.signature` token
+    Sign {
+        #[command(flatten)]
+        secret: SecretSource,
+        #[arg(short, long)]
+        payload: String,
+    },
+    /// Check a token and print its header and payload
+    Verify {
+        #[arg(short, long)]
+        token: String,
+        #[arg(short, long)]
+        public_key: String,
+    },
+}
+
+#[cfg(feature = "blst")]
+#[derive(Subcommand)]
+enum DrandCommands {
+    /// Check a round's signature and randomness against the network's group key
+    Verify {
+        #[arg(long, value_enum, default_value_t = drand::Scheme::PedersenBlsChained)]
+        scheme: drand::Scheme,
+        #[arg(long)]
+        round: u64,
+        #[arg(long)]
+        randomness: String,
+        #[arg(long)]
+        signature: String,
+        /// Signature of the previous round (chained scheme only)
+        #[arg(long)]
+        previous_signature: Option<String>,
+        #[arg(long)]
+        group_key: String,
+    },
+}
+
+#[cfg(feature = "timelock")]
+#[derive(Subcommand)]
+enum TimelockCommands {
+    /// Encrypt a message to a beacon round
+    Encrypt {
+        #[arg(long)]
+        round: u64,
+        #[arg(long)]
+        group_key: String,
+        #[arg(short, long)]
+        message: String,
+    },
+    /// Decrypt a message with the beacon's signature on its round
+    Decrypt {
+        #[arg(long)]
+        round_signature: String,
+        #[arg(short, long)]
+        ciphertext: String,
+    },
+}
+
+#[cfg(feature = "blst")]
+#[derive(Subcommand)]
+enum SuiteCommands {
+    /// List built-in and configured suites with their layout, scheme and DST
+    List,
+}
+
+#[derive(Subcommand)]
+enum BeaconCommands {
+    /// Split a fresh group key into threshold shares (trusted dealer)
+    Deal {
+        #[arg(short, long)]
+        threshold: usize,
+        #[arg(short = 'n', long)]
+        shares: usize,
+    },
+    /// Sign a round with one member's secret share
+    Contribute {
+        #[arg(short, long)]
+        secret_share: String,
+        #[arg(long)]
+        round: u64,
+        /// Group signature of the previous round; omit for the first round
+        #[arg(long)]
+        previous_signature: Option<String>,
+    },
+    /// Combine threshold contributions into the round's group signature
+    Combine {
+        /// Contributions as `index:signature`
+        #[arg(short, long, num_args = 1.., required = true)]
+        contributions: Vec<String>,
+    },
+    /// Check a round's group signature and print its randomness
+    Verify {
+        #[arg(short, long)]
+        group_key: String,
+        #[arg(long)]
+        round: u64,
+        #[arg(long)]
+        previous_signature: Option<String>,
+        #[arg(short, long)]
+        signature: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum BbsCommands {
+    /// Generate a BBS key pair
+    Keygen,
+    /// Sign an ordered list of messages
+    Sign {
+        #[arg(short, long)]
+        secret: String,
+        #[arg(short, long, num_args = 1.., required = true)]
+        messages: Vec<String>,
+        /// Application data bound to the signature but never hidden
+        #[arg(long, default_value = "")]
+        header: String,
+    },
+    /// Check a signature over the full list of messages
+    Verify {
+        #[arg(short, long)]
+        signature: String,
+        #[arg(short, long)]
+        public_key: String,
+        #[arg(short, long, num_args = 1.., required = true)]
+        messages: Vec<String>,
+        #[arg(long, default_value = "")]
+        header: String,
+    },
+    /// Prove knowledge of a signature while revealing only some of the messages
+    DeriveProof {
+        #[arg(short, long)]
+        signature: String,
+        #[arg(short, long)]
+        public_key: String,
+        #[arg(short, long, num_args = 1.., required = true)]
+        messages: Vec<String>,
+        /// 1-based positions of the messages to reveal, e.g. `1,3`
+        #[arg(short, long, value_delimiter = ',')]
+        reveal: Vec<usize>,
+        #[arg(long, default_value = "")]
+        header: String,
+        /// Verifier-supplied data (such as a nonce) bound to this proof
+        #[arg(long, default_value = "")]
+        presentation_header: String,
+    },
+    /// Check a proof against the revealed messages
+    VerifyProof {
+        #[arg(long)]
+        proof: String,
+        #[arg(short, long)]
+        public_key: String,
+        /// The revealed messages, in the same order as `--reveal`
+        #[arg(short, long, num_args = 0..)]
+        messages: Vec<String>,
+        #[arg(short, long, value_delimiter = ',')]
+        reveal: Vec<usize>,
+        #[arg(long, default_value = "")]
+        header: String,
+        #[arg(long, default_value = "")]
+        presentation_header: String,
+    },
+}
+
+/// Replaces every `@file` among `values` with the file's non-empty lines.
+fn expand_list(values: &[String]) -> Vec<String> {
+    expand_sourced(values)
+        .into_iter()
+        .map(|(value, _)| value)
+        .collect()
+}
+
+/// Like `expand_list`, pairing every value with where it came from: `file:line` for values
+/// read from a file, `argument n` for the others.
+fn expand_sourced(values: &[String]) -> Vec<(String, String)> {
+    values
+        .iter()
+        .enumerate()
+        .flat_map(|(position, value)| match value.strip_prefix('@') {
+            Some(path) => fs::read_to_string(path)
+                .unwrap_or_else(|_| panic!("Failed to read {}", path))
+                .lines()
+                .enumerate()
+                .map(|(line, value)| (value.trim().to_string(), format!("{}:{}", path, line + 1)))
+                .filter(|(value, _)| !value.is_empty())
+                .collect(),
+            None => vec![(value.clone(), format!("argument {}", position + 1))],
+        })
+        .collect()
+}
+
+/// Where a command takes its secret key from: hex on the command line, a hex secret key file
+/// or a key pair file written by `generate-keys --out`.
+#[derive(Args)]
+struct SecretSource {
+    #[arg(short, long, conflicts_with_all = ["secret_file", "key"])]
+    secret: Option<String>,
+
+    /// Read the hex secret key from this file, which may be age-encrypted
+    #[arg(long, conflicts_with = "key")]
+    secret_file: Option<PathBuf>,
+
+    /// Key pair file written by `generate-keys --out`
+    #[arg(long)]
+    key: Option<PathBuf>,
+
+    /// File whose first line is the passphrase of an encrypted key pair
+    #[cfg(feature = "encryption")]
+    #[arg(long, requires = "key")]
+    passphrase_file: Option<PathBuf>,
+}
+
+impl SecretSource {
+    fn is_given(&self) -> bool {
+        self.secret.is_some() || self.secret_file.is_some() || self.key.is_some()
+    }
+
+    fn hex(&self, config: &Config) -> Zeroizing<String> {
+        match (&self.secret, &self.secret_file) {
+            (Some(secret), _) => Zeroizing::new(secret.clone()),
+            (None, Some(path)) => secret::read_file(path, config),
+            (None, None) => panic!("Pass the secret key with --secret, --secret-file or --key"),
+        }
+    }
+
+    /// The secret key as given, without range checks.
+    fn read(&self, config: &Config) -> SecretBytes {
+        let Some(path) = &self.key else {
+            return SecretBytes::new(keypair::decode_secret(&self.hex(config)));
+        };
+        #[cfg(feature = "encryption")]
+        let passphrase = self.passphrase_file.as_deref().map(keypair::read_passphrase);
+        #[cfg(not(feature = "encryption"))]
+        let passphrase: Option<Vec<u8>> = None;
+        KeyPairFile::read(path).secret(passphrase.as_deref())
+    }
+
+    /// The secret key for `backend`: range-checked (and reduced with `reduce`) when given in hex,
+    /// and checked to belong to the file's public key when taken from a key pair file.
+    fn load(&self, backend: &dyn Backend, config: &Config, reduce: bool) -> SecretBytes {
+        let Some(path) = &self.key else {
+            return secret::load(backend, &self.hex(config), reduce);
+        };
+        let key_pair = KeyPairFile::read(path);
+        let secret_key = self.read(config);
+        assert!(
+            backend.public_key_from_secret(&secret_key) == key_pair.public_key(),
+            "Key pair file {} is for {}; select it with --backend or --suite",
+            path.display(),
+            key_pair.suite().unwrap_or(key_pair.backend())
+        );
+        secret_key
+    }
+}
+
+/// Groups selectable with `public-key-from-secret --group`.
+#[derive(Copy, Clone, ValueEnum)]
+enum KeyGroup {
+    G1,
+    G2,
+    /// Both keys, as a JSON object
+    Both,
+}
+
+/// The bytes actually signed for `message`, after the optional `--prehash` digest.
+#[cfg(feature = "poseidon")]
+fn signed_bytes(message: &str, prehash: Option<prehash::Prehash>) -> Vec<u8> {
+    match prehash {
+        Some(prehash) => prehash.digest(message),
+        None => message.as_bytes().to_vec(),
+    }
+}
+
+/// Converts 1-based `--reveal` positions into sorted, deduplicated 0-based indexes.
+fn reveal_indexes(reveal: &[usize]) -> Vec<usize> {
+    let mut indexes: Vec<usize> = reveal
+        .iter()
+        .map(|&i| i.checked_sub(1).expect("Message positions start at 1"))
+        .collect();
+    indexes.sort_unstable();
+    indexes.dedup();
+    indexes
 }
 
 fn main() {
     let cli = Cli::parse();
+    // doctor reports a broken config file instead of stopping at it
+    let config = match cli.command {
+        Commands::Doctor => Config::try_load(cli.config.as_deref()).unwrap_or_default(),
+        _ => Config::load(cli.config.as_deref()),
+    };
+    if let Some(list) = &cli.cpu_affinity {
+        threads::pin(list);
+    }
+    let threads = threads::count(cli.threads);
+    let hash_params = HashParams::new(cli.expander, cli.security_bits, cli.expansion_len);
+    let backend: Box<dyn Backend> = if hash_params != HashParams::default() {
+        assert_eq!(
+            cli.backend,
+            BackendKind::Sylow,
+            "Only the sylow backend supports other hash-to-curve parameters"
+        );
+        Box::new(SylowBackend::new(hash_params))
+    } else {
+        cli.backend.instantiate()
+    };
+    #[cfg(feature = "blst")]
+    let backend = match &cli.suite {
+        Some(name) => suite::instantiate(name, &config),
+        None => backend,
+    };
+    // for formats that name only the backend, so their verifiers can only rebuild a plain one
+    let assert_plain_backend = |format: &str| {
+        #[cfg(feature = "blst")]
+        assert!(cli.suite.is_none(), "{} cannot record a --suite", format);
+        assert!(
+            hash_params == HashParams::default(),
+            "{} cannot record hash-to-curve parameters; drop --expander, --security-bits and \
+             --expansion-len",
+            format
+        );
+    };
+
+    // calldata layout of points on `curve`, the precompile one unless another was asked for
+    let calldata_profile = |curve: &str| match &cli.calldata_profile {
+        Some(name) => CalldataProfile::load(name),
+        None => CalldataProfile::default_for(curve),
+    };
+
+    // prints a single-value result, as a QR code if one was asked for
+    let emit = |value: &str| {
+        #[cfg(feature = "qr")]
+        if let Some(path) = &cli.qr_png {
+            qr::write_png(value, path);
+            return;
+        }
+        #[cfg(feature = "qr")]
+        if cli.qr {
+            println!("{}", qr::render_terminal(value));
+            return;
+        }
+        println!("{}", value);
+    };
+
+    let key_json = |public_key: &[u8]| {
+        json!({
+            "publicKey": hex::encode(public_key),
+            "fingerprint": fingerprint(backend.as_ref(), public_key),
+        })
+    };
 
     match cli.command {
-        Commands::GenerateKeys => {
-            let key_pair = KeyPair::generate();
+        Commands::GenerateKeys {
+            out,
+            secret_out,
+            json,
+            count,
+            out_dir,
+            prefix,
+            vanity_prefix,
+            #[cfg(feature = "encryption")]
+            passphrase_file,
+        } => {
+            #[cfg(feature = "encryption")]
+            let passphrase = passphrase_file.as_deref().map(keypair::read_passphrase);
+            #[cfg(not(feature = "encryption"))]
+            let passphrase: Option<Vec<u8>> = None;
+            #[cfg(feature = "blst")]
+            let suite = cli.suite.clone();
+            #[cfg(not(feature = "blst"))]
+            let suite = None;
+            let write_key_pair = |path: &Path, secret_key: &[u8], public_key: &[u8]| {
+                KeyPairFile::new(
+                    cli.backend,
+                    suite.clone(),
+                    &config,
+                    secret_key,
+                    public_key,
+                    passphrase.as_deref(),
+                )
+                .write(path)
+            };
+            if let Some(count) = count {
+                // clap guarantees an output directory along with a count
+                let dir = out_dir.unwrap();
+                fs::create_dir_all(&dir).expect("Failed to create output directory");
+                let manifest: Vec<_> = (1..=count)
+                    .map(|index| {
+                        let alias = format!("{}{}", prefix, index);
+                        let path = dir.join(format!("{}.json", alias));
+                        let (secret_key, public_key) = backend.generate_keys();
+                        write_key_pair(&path, &secret_key, &public_key);
+                        json!({
+                            "alias": alias,
+                            "file": path,
+                            "publicKey": hex::encode(&public_key),
+                            "fingerprint": fingerprint(backend.as_ref(), &public_key),
+                        })
+                    })
+                    .collect();
+                println!("{}", json!(manifest));
+                return;
+            }
+            let (secret_key, public_key) = match vanity_prefix {
+                Some(vanity_prefix) => {
+                    #[cfg(feature = "blst")]
+                    let suite = cli.suite.as_deref();
+                    #[cfg(not(feature = "blst"))]
+                    let suite = None;
+                    vanity::search(backend.as_ref(), cli.backend, suite, &vanity_prefix, threads)
+                }
+                None => backend.generate_keys(),
+            };
+            if let Some(path) = secret_out {
+                keypair::write_private(&path, &hex::encode(secret_key));
+                if json {
+                    println!("{}", key_json(&public_key));
+                } else {
+                    println!("{}", hex::encode(public_key));
+                }
+                return;
+            }
+            if let Some(path) = out {
+                write_key_pair(&path, &secret_key, &public_key);
+                let result = json!({
+                    "publicKey": hex::encode(&public_key),
+                    "fingerprint": fingerprint(backend.as_ref(), &public_key),
+                });
+                println!("{}", result);
+                return;
+            }
             let result = json!({
-                "secretKey": hex::encode(key_pair.secret_key.to_be_bytes()),
-                "publicKey": hex::encode(G2Affine::from(key_pair.public_key).to_be_bytes()),
+                "secretKey": hex::encode(secret_key),
+                "publicKey": hex::encode(&public_key),
+                "fingerprint": fingerprint(backend.as_ref(), &public_key),
             });
             println!("{}", result);
         }
-        Commands::PublicKeyFromSecret { secret } => {
-            let secret_key_bytes = hex::decode(secret).expect("Invalid hex in secret key");
-            let secret_key_array: [u8; 32] = secret_key_bytes
-                .try_into()
-                .expect("Secret key must be 32 bytes");
-            let secret_key = Fp::from_be_bytes(&secret_key_array)
-                .expect("Failed to deserialize secret key");
-            
-            let public_key = G2Projective::generator() * secret_key;
-            let public_key_affine = G2Affine::from(public_key);
-            let public_key_bytes = public_key_affine.to_be_bytes();
-
-            println!("{}", hex::encode(public_key_bytes));
-        }
-        Commands::Sign { secret, message } => {
-            let secret_key_bytes = hex::decode(secret).expect("Invalid hex in secret key");
-            let secret_key_array: [u8; 32] = secret_key_bytes
-                .try_into()
-                .expect("Secret key must be 32 bytes");
-            let secret_key = Fp::from_be_bytes(&secret_key_array)
-                .expect("Failed to deserialize secret key");
-            let expander = XMDExpander::<Keccak256>::new(DST, SECURITY_BITS);
-            let hashed_message = G1Projective::hash_to_curve(&expander, message.as_bytes())
-                .expect("Hashing failed");
-            let signature = hashed_message * secret_key;
-            println!("{}", hex::encode(G1Affine::from(signature).to_be_bytes()));
-        }
-        Commands::AggregateKeys { public_keys } => {
-            let mut agg_key = G2Projective::zero();
-            for key_hex in public_keys {
-                let key_bytes = hex::decode(key_hex).expect("Invalid hex in public key");
-                let key_array: [u8; 128] = key_bytes
-                    .try_into()
-                    .expect("Public key must be 128 bytes");
-                let pubkey_affine = G2Affine::from_be_bytes(&key_array)
-                    .into_option()
-                    .expect("Invalid public key");
-                let pubkey = G2Projective::from(pubkey_affine);
-                agg_key = agg_key + pubkey;
-            }
-            println!("{}", hex::encode(G2Affine::from(agg_key).to_be_bytes()));
-        }
-        Commands::AggregateSignatures { signatures } => {
-            let mut agg_sig = G1Projective::zero();
-            for sig_hex in signatures {
-                let sig_bytes = hex::decode(sig_hex).expect("Invalid hex in signature");
-                let sig_array: [u8; 64] = sig_bytes
-                    .try_into()
-                    .expect("Signature must be 64 bytes");
-                let sig_affine = G1Affine::from_be_bytes(&sig_array)
-                    .into_option()
-                    .expect("Invalid signature");
-                let sig = G1Projective::from(sig_affine);
-                agg_sig = agg_sig + sig;
-            }
-            println!("{}", hex::encode(G1Affine::from(agg_sig).to_be_bytes()));
+        Commands::SignTyped {
+            secret,
+            types,
+            domain,
+            data,
+            primary_type,
+        } => {
+            let secret_key = secret.load(backend.as_ref(), &config, cli.reduce);
+            let typed = eip712::TypedData::read(&types, &domain, &data, primary_type);
+            let mut result = typed.describe();
+            result["signature"] = json!(hex::encode(backend.sign(&secret_key, &typed.digest())));
+            println!("{}", result);
+        }
+        Commands::VerifyTyped {
+            signature,
+            public_key,
+            types,
+            domain,
+            data,
+            primary_type,
+        } => {
+            let sig_bytes = hex::decode(signature).expect("Invalid hex in signature");
+            let key_bytes = hex::decode(public_key).expect("Invalid hex in public key");
+            let typed = eip712::TypedData::read(&types, &domain, &data, primary_type);
+            let valid = backend.verify(&sig_bytes, &key_bytes, &typed.digest());
+            println!("{}", json!({ "digest": hex::encode(typed.digest()), "valid": valid }));
+        }
+        Commands::PublicKeyFromSecret {
+            secret,
+            group,
+            json,
+        } => {
+            let secret_key = secret.load(backend.as_ref(), &config, cli.reduce);
+            let in_group = |group| backend.public_key_in_group(&secret_key, group);
+            let public_key = match group {
+                None => backend.public_key_from_secret(&secret_key),
+                Some(KeyGroup::G1) => in_group(point::Group::G1),
+                Some(KeyGroup::G2) => in_group(point::Group::G2),
+                Some(KeyGroup::Both) => {
+                    let result = json!({
+                        "g1": hex::encode(in_group(point::Group::G1)),
+                        "g2": hex::encode(in_group(point::Group::G2)),
+                    });
+                    println!("{}", result);
+                    return;
+                }
+            };
+            if json {
+                println!("{}", key_json(&public_key));
+                return;
+            }
+            emit(&hex::encode(public_key));
+        }
+        Commands::CheckSecret { secret } => {
+            let secret_key = secret.read(&config);
+            println!("{}", secret::check(backend.as_ref(), &secret_key));
+        }
+        Commands::ExportFixtures { format, keys, out } => {
+            #[cfg(feature = "blst")]
+            let suite = cli.suite.clone();
+            #[cfg(not(feature = "blst"))]
+            let suite = None;
+            let fixtures =
+                fixtures::Fixtures::generate(format, cli.backend, suite, backend.as_ref(), keys);
+            let contents =
+                serde_json::to_string_pretty(&fixtures).expect("Failed to serialize fixtures");
+            match out {
+                Some(path) => fs::write(path, contents + "\n").expect("Failed to write fixtures"),
+                None => println!("{}", contents),
+            }
+        }
+        Commands::ImportFixtures { file } => {
+            let fixtures = fixtures::Fixtures::read(&file);
+            println!("{}", fixtures.check(fixtures.instantiate(&config).as_ref()));
+        }
+        Commands::Regress { command } => match command {
+            RegressCommands::Record { out } => println!("{}", regress::record(&out)),
+            RegressCommands::Check { directory } => {
+                println!("{}", regress::check(&directory, &config));
+            }
+        },
+        Commands::Convert { from, to, value } => {
+            let curve = cli.backend.curve();
+            emit(&convert::convert(curve, from, to, &value, &calldata_profile(curve)));
+        }
+        Commands::Doctor => {
+            #[cfg(feature = "blst")]
+            let suite = cli.suite.as_deref();
+            #[cfg(not(feature = "blst"))]
+            let suite = None;
+            let report = doctor::run(
+                cli.backend,
+                suite,
+                backend.as_ref(),
+                hash_params,
+                cli.config.as_deref(),
+                &config,
+            );
+            println!("{}", report);
+        }
+        Commands::Explain { value } => println!("{}", explain::explain(&value)),
+        Commands::Fingerprint { public_key } => {
+            let key_bytes = hex::decode(public_key).expect("Invalid hex in public key");
+            println!("{}", fingerprint(backend.as_ref(), &key_bytes));
+        }
+        Commands::CheckKeypair { secret, public_key } => {
+            let secret_key = secret.load(backend.as_ref(), &config, cli.reduce);
+            let key_bytes = hex::decode(public_key).expect("Invalid hex in public key");
+            println!("{}", secret::check_keypair(backend.as_ref(), &secret_key, &key_bytes));
+        }
+        Commands::Sign {
+            secret,
+            message,
+            out,
+            expires_in,
+            nonce,
+            stream,
+            input_format,
+            output_format,
+            audit_log,
+            template,
+            dry_run,
+            fields,
+            #[cfg(feature = "poseidon")]
+            prehash,
+        } => {
+            #[cfg(feature = "poseidon")]
+            let signed_bytes = |message: &str| signed_bytes(message, prehash);
+            #[cfg(not(feature = "poseidon"))]
+            let signed_bytes = |message: &str| message.as_bytes().to_vec();
+            let secret_key = secret.load(backend.as_ref(), &config, cli.reduce);
+            // only derived when something records who signed
+            let public_key = if out.is_some() || audit_log.is_some() || dry_run {
+                backend.public_key_from_secret(&secret_key)
+            } else {
+                Vec::new()
+            };
+            let audit = |message: &[u8]| {
+                if let Some(path) = &audit_log {
+                    audit::record(path, &public_key, message);
+                }
+            };
+            #[cfg(feature = "blst")]
+            let suite = cli.suite.clone();
+            #[cfg(not(feature = "blst"))]
+            let suite: Option<String> = None;
+            // what a dry run reports instead of signing `signed`
+            let dry_run_report = |signed: &[u8], envelope: Option<&Path>| {
+                json!({
+                    "dryRun": true,
+                    "backend": cli.backend.name(),
+                    "suite": suite,
+                    "publicKey": hex::encode(&public_key),
+                    "fingerprint": fingerprint(backend.as_ref(), &public_key),
+                    "signedBytes": hex::encode(signed),
+                    "envelope": envelope,
+                    "auditLog": audit_log,
+                })
+            };
+            if stream {
+                let mut stdin = io::stdin().lock();
+                let mut stdout = io::stdout().lock();
+                let mut line = String::new();
+                loop {
+                    // the message as received, and the bytes signed for it
+                    let (message, signed) = match input_format {
+                        StreamFormat::Text => {
+                            line.clear();
+                            if stdin.read_line(&mut line).expect("Failed to read from stdin") == 0 {
+                                break;
+                            }
+                            let message = line.strip_suffix('\n').unwrap_or(&line);
+                            let message = message.strip_suffix('\r').unwrap_or(message);
+                            (message.as_bytes().to_vec(), signed_bytes(message))
+                        }
+                        StreamFormat::Bin => {
+                            #[cfg(feature = "poseidon")]
+                            assert!(prehash.is_none(), "--prehash needs text input");
+                            match frame::read(&mut stdin) {
+                                Some(message) => (message.clone(), message),
+                                None => break,
+                            }
+                        }
+                    };
+                    let signature = backend.sign(&secret_key, &signed);
+                    audit(&message);
+                    match output_format {
+                        StreamFormat::Text => writeln!(stdout, "{}", hex::encode(signature)),
+                        StreamFormat::Bin => frame::write(&mut stdout, &signature),
+                    }
+                    .and_then(|_| stdout.flush())
+                    .expect("Failed to write to stdout");
+                }
+                return;
+            }
+            if let Some(template) = template {
+                let message = template::encode(&config, &template, &fields);
+                if dry_run {
+                    println!("{}", dry_run_report(&message, None));
+                    return;
+                }
+                let signature = backend.sign(&secret_key, &message);
+                audit(&message);
+                emit(&hex::encode(signature));
+                return;
+            }
+            // clap guarantees a message unless streaming or filling a template
+            let message = message.unwrap();
+            match out {
+                Some(path) => {
+                    let envelope = Envelope::new(cli.backend, message.clone(), &public_key);
+                    #[cfg(feature = "blst")]
+                    let envelope = envelope.with_suite(suite.clone());
+                    let envelope = match expires_in {
+                        Some(seconds) => envelope.with_expiry(seconds),
+                        None => envelope,
+                    };
+                    let envelope = envelope.with_nonce(nonce);
+                    if dry_run {
+                        println!("{}", dry_run_report(&envelope.signed_bytes(), Some(&path)));
+                        return;
+                    }
+                    audit(message.as_bytes());
+                    envelope.sign(backend.as_ref(), &secret_key).write(&path);
+                }
+                None => {
+                    let signed = signed_bytes(&message);
+                    if dry_run {
+                        println!("{}", dry_run_report(&signed, None));
+                        return;
+                    }
+                    audit(message.as_bytes());
+                    let signature = backend.sign(&secret_key, &signed);
+                    emit(&hex::encode(signature));
+                }
+            }
+        }
+        Commands::AggregateKeys { public_keys, json } => {
+            let keys: Vec<Vec<u8>> = public_keys
+                .iter()
+                .map(|key_hex| hex::decode(key_hex).expect("Invalid hex in public key"))
+                .collect();
+            let aggregate = threads::aggregate(threads, &keys, |keys| {
+                backend.aggregate_public_keys(keys)
+            });
+            if json {
+                println!("{}", key_json(&aggregate));
+                return;
+            }
+            emit(&hex::encode(aggregate));
+        }
+        Commands::AggregateSignatures {
+            signatures,
+            provenance,
+            public_keys,
+            message,
+            validate,
+        } => {
+            assert!(
+                public_keys.is_empty() || validate || provenance.is_some(),
+                "--public-keys and --message are only used with --validate or --provenance"
+            );
+            let contributions: Vec<provenance::Contribution> = expand_sourced(&signatures)
+                .into_iter()
+                .map(|(sig_hex, source)| provenance::Contribution {
+                    signature: hex::decode(sig_hex)
+                        .unwrap_or_else(|_| panic!("Invalid hex in signature from {}", source)),
+                    source,
+                })
+                .collect();
+            let public_keys: Vec<Vec<u8>> = expand_list(&public_keys)
+                .iter()
+                .map(|key_hex| hex::decode(key_hex).expect("Invalid hex in public key"))
+                .collect();
+            // clap guarantees a message along with public keys
+            let signers = message
+                .as_ref()
+                .map(|message| (public_keys.as_slice(), message.as_bytes()));
+            let valid = provenance::check(backend.as_ref(), &contributions, signers, threads);
+            // without --validate every signature is folded in, checked or not
+            let included: Vec<usize> = (0..contributions.len())
+                .filter(|&index| !validate || valid[index] == Some(true))
+                .collect();
+            assert!(!included.is_empty(), "No signature verifies on its own");
+            let sigs: Vec<Vec<u8>> = included
+                .iter()
+                .map(|&index| contributions[index].signature.clone())
+                .collect();
+            let aggregate = threads::aggregate(threads, &sigs, |sigs| {
+                backend.aggregate_signatures(sigs)
+            });
+            if let Some(path) = provenance {
+                let record = provenance::record(&contributions, signers, &valid, &aggregate);
+                provenance::write(&path, &record);
+            }
+            if !validate {
+                emit(&hex::encode(aggregate));
+                return;
+            }
+            let keys: Vec<Vec<u8>> = included
+                .iter()
+                .map(|&index| public_keys[index].clone())
+                .collect();
+            let skipped: Vec<_> = (0..contributions.len())
+                .filter(|&index| valid[index] != Some(true))
+                .map(|index| json!({ "index": index, "source": contributions[index].source }))
+                .collect();
+            println!(
+                "{}",
+                json!({
+                    "aggregateSignature": hex::encode(&aggregate),
+                    "aggregatePublicKey": hex::encode(backend.aggregate_public_keys(&keys)),
+                    "included": included.len(),
+                    "skipped": skipped,
+                })
+            );
         }
         Commands::Verify {
             signature,
             public_key,
             message,
+            envelope,
+            fingerprint: pinned_fingerprint,
+            at,
+            seen_nonces,
+            template,
+            fields,
+            #[cfg(feature = "poseidon")]
+            prehash,
         } => {
+            if let Some(path) = envelope {
+                let envelope = Envelope::read(&path);
+                let signer = match (public_key, pinned_fingerprint) {
+                    (Some(public_key), _) => Some(envelope::Signer::PublicKey(public_key)),
+                    (None, Some(pinned)) => Some(envelope::Signer::Fingerprint(pinned)),
+                    (None, None) => None,
+                };
+                let mut result = envelope.verify(&config, signer.as_ref(), at);
+                // only accepted envelopes use up their nonce
+                if let Some(store) = seen_nonces.filter(|_| result["valid"] == json!(true)) {
+                    match envelope.replayed(&config, &store) {
+                        Some(replayed) => {
+                            result["replayed"] = json!(replayed);
+                            result["valid"] = json!(!replayed);
+                        }
+                        None => {
+                            result["replayProtected"] = json!(false);
+                            result["valid"] = json!(false);
+                        }
+                    }
+                }
+                println!("{}", result);
+                return;
+            }
+            // clap guarantees a signature and key without an envelope, and a message unless
+            // filling a template
+            let (signature, public_key, message) = (
+                signature.unwrap(),
+                public_key.unwrap(),
+                message.unwrap_or_default(),
+            );
             let sig_bytes = hex::decode(signature).expect("Invalid hex in signature");
-            let sig_array: [u8; 64] = sig_bytes
-                    .try_into()
-                    .expect("Signature must be 64 bytes");
-            let agg_signature_affine = G1Affine::from_be_bytes(&sig_array)
-                .into_option()
-                .expect("Invalid signature");
-            let agg_signature = G1Projective::from(agg_signature_affine);
-
             let key_bytes = hex::decode(public_key).expect("Invalid hex in public key");
-            let key_array: [u8; 128] = key_bytes
-                    .try_into()
-                    .expect("Public key must be 128 bytes");
-            let agg_pubkey_affine = G2Affine::from_be_bytes(&key_array)
-                .into_option()
-                .expect("Invalid public key");
-            let agg_pubkey = G2Projective::from(agg_pubkey_affine);
-
-            let expander = XMDExpander::<Keccak256>::new(DST, SECURITY_BITS);
-            let hashed_message = G1Projective::hash_to_curve(&expander, message.as_bytes())
-                .expect("Hashing failed");
-
-            let lhs = pairing(&agg_signature, &G2Projective::generator());
-            let rhs = pairing(&hashed_message, &agg_pubkey);
-
-            println!("{}", json!({ "valid": lhs == rhs }));
+            let message = match template {
+                Some(template) => template::encode(&config, &template, &fields),
+                #[cfg(feature = "poseidon")]
+                None => signed_bytes(&message, prehash),
+                #[cfg(not(feature = "poseidon"))]
+                None => message.into_bytes(),
+            };
+            let valid = backend.verify(&sig_bytes, &key_bytes, &message);
+
+            println!("{}", json!({ "valid": valid }));
+        }
+        Commands::Run { job } => {
+            #[cfg(feature = "blst")]
+            let suite = cli.suite.clone();
+            #[cfg(not(feature = "blst"))]
+            let suite = None;
+            println!(
+                "{}",
+                jobs::run(&job, cli.backend, suite, backend.as_ref(), &config)
+            );
+        }
+        Commands::BatchVerify {
+            input,
+            checkpoint,
+            checkpoint_every,
+            resume,
+        } => {
+            #[cfg(feature = "blst")]
+            let backend_name = match &cli.suite {
+                Some(name) => format!("suite {}", name),
+                None => cli.backend.name(),
+            };
+            #[cfg(not(feature = "blst"))]
+            let backend_name = cli.backend.name();
+            let progress = batch::verify(
+                backend.as_ref(),
+                &backend_name,
+                &input,
+                resume.as_deref().map(batch::Checkpoint::read),
+                checkpoint.as_deref(),
+                checkpoint_every,
+                threads,
+            );
+            println!("{}", json!(progress));
+        }
+        Commands::DetectEquivocation { records } => {
+            println!("{}", equivocation::detect(backend.as_ref(), &records, threads));
+        }
+        Commands::PlanAggregate { records, out } => {
+            println!("{}", plan::plan(backend.as_ref(), &records, out.as_deref(), threads));
+        }
+        Commands::Evidence { command } => match command {
+            EvidenceCommands::Bundle {
+                report,
+                index,
+                method,
+            } => {
+                #[cfg(feature = "blst")]
+                let suite = cli.suite.clone();
+                #[cfg(not(feature = "blst"))]
+                let suite = None;
+                // the calldata encodes BN254 points
+                let profile = calldata_profile("bn254");
+                let calldata = (cli.backend == BackendKind::Sylow && suite.is_none())
+                    .then_some((&profile, method.as_str()));
+                let evidence = equivocation::bundle(
+                    backend.as_ref(),
+                    cli.backend.name(),
+                    suite,
+                    &equivocation::read_report(&report, index),
+                    calldata,
+                );
+                println!("{}", evidence);
+            }
+        },
+        Commands::Random { command, count } => {
+            let random_point = |group| {
+                let (secret_key, _) = backend.generate_keys();
+                backend.public_key_in_group(&secret_key, group)
+            };
+            for _ in 0..count {
+                let value = match &command {
+                    RandomCommands::Scalar => backend.generate_keys().0,
+                    RandomCommands::G1 => random_point(point::Group::G1),
+                    RandomCommands::G2 => random_point(point::Group::G2),
+                    RandomCommands::Message { len } => {
+                        let mut message = vec![0u8; *len];
+                        OsRng.fill_bytes(&mut message);
+                        message
+                    }
+                };
+                println!("{}", hex::encode(value));
+            }
+        }
+        Commands::Math { command } => {
+            let decode = |value: &str| hex::decode(value).expect("Invalid hex in operand");
+            let result = match command {
+                MathCommands::ScalarMod { value } => math::scalar_mod(&decode(&value)),
+                MathCommands::ScalarInvert { scalar } => math::scalar_invert(&decode(&scalar)),
+                MathCommands::FpAdd { a, b } => math::fp_add(&decode(&a), &decode(&b)),
+                MathCommands::Fp2Mul { a, b } => math::fp2_mul(&decode(&a), &decode(&b)),
+            };
+            println!("{}", hex::encode(result));
+        }
+        Commands::Point { command } => {
+            let decode = |value: &str| hex::decode(value).expect("Invalid hex in operand");
+            let result = match command {
+                PointCommands::Add { group, points } => {
+                    let points: Vec<Vec<u8>> = points.iter().map(|p| decode(p)).collect();
+                    point::add(group, &points)
+                }
+                PointCommands::Mul {
+                    group,
+                    point,
+                    scalar,
+                } => point::mul(group, &decode(&point), &decode(&scalar)),
+                PointCommands::Neg { group, point } => point::neg(group, &decode(&point)),
+                PointCommands::IsOnCurve { group, point } => {
+                    let on_curve = point::is_on_curve(group, &decode(&point));
+                    println!("{}", json!({ "onCurve": on_curve }));
+                    return;
+                }
+            };
+            println!("{}", hex::encode(result));
+        }
+        Commands::Submit {
+            rpc_url,
+            from,
+            contract,
+            method,
+            signature,
+            bitfield,
+            message,
+            dry_run,
+        } => {
+            assert_eq!(
+                cli.backend,
+                BackendKind::Sylow,
+                "Only BN254 signatures can be submitted to EVM contracts"
+            );
+            #[cfg(feature = "blst")]
+            assert!(cli.suite.is_none(), "Suite signatures cannot be submitted");
+            let sig_bytes = hex::decode(signature).expect("Invalid hex in signature");
+            let bitfield = hex::decode(bitfield).expect("Invalid hex in bitfield");
+            let data = evm::submit_calldata(
+                &calldata_profile("bn254"),
+                &method,
+                &sig_bytes,
+                &bitfield,
+                message.as_bytes(),
+            );
+            let transaction = evm::transaction(from.as_deref(), &contract, &data);
+            if dry_run {
+                println!("{}", transaction);
+                return;
+            }
+            // clap guarantees an endpoint unless it is a dry run
+            let rpc_url = rpc_url.unwrap();
+            #[cfg(feature = "rpc")]
+            println!("{}", rpc::send_transaction(&rpc_url, &transaction));
+            #[cfg(not(feature = "rpc"))]
+            panic!("Sending to {} needs the rpc feature; use --dry-run", rpc_url);
+        }
+        Commands::BundleProof {
+            assertion_id,
+            signatures,
+            committee,
+            method,
+        } => {
+            assert_eq!(
+                cli.backend,
+                BackendKind::Sylow,
+                "Proof bundles are only made for the sylow backend"
+            );
+            let assertion_id = hex::decode(assertion_id.trim_start_matches("0x"))
+                .expect("Invalid hex in assertion id");
+            let signatures: Vec<Vec<u8>> = expand_list(&signatures)
+                .iter()
+                .map(|sig| hex::decode(sig).expect("Invalid hex in signature"))
+                .collect();
+            let committee = bundle::read_committee(&committee);
+            let profile = calldata_profile("bn254");
+            println!(
+                "{}",
+                bundle::bundle(&profile, &assertion_id, &signatures, &committee, &method)
+            );
+        }
+        Commands::PopProve { secret } => {
+            assert_eq!(
+                cli.backend,
+                BackendKind::Sylow,
+                "Proofs of possession are only made for the sylow backend"
+            );
+            let secret_key = secret.load(backend.as_ref(), &config, cli.reduce);
+            let public_key = backend.public_key_from_secret(&secret_key);
+            emit(&hex::encode(pop::prove(&secret_key, &public_key)));
+        }
+        Commands::PopVerifyBatch { committee, proofs } => {
+            assert_eq!(
+                cli.backend,
+                BackendKind::Sylow,
+                "Proofs of possession are only made for the sylow backend"
+            );
+            let proofs: Vec<Vec<u8>> = expand_list(&proofs)
+                .iter()
+                .map(|proof| hex::decode(proof).expect("Invalid hex in proof"))
+                .collect();
+            let committee = bundle::read_committee(&committee);
+            println!("{}", pop::verify_batch(&committee, &proofs));
+        }
+        Commands::VerifyOnchain {
+            rpc_url,
+            tx,
+            calldata,
+            public_keys,
+            message,
+        } => {
+            let committee: Vec<Vec<u8>> = public_keys
+                .iter()
+                .map(|key| hex::decode(key).expect("Invalid hex in public key"))
+                .collect();
+            let data = match (calldata, rpc_url, tx) {
+                (Some(calldata), _, _) => hex::decode(calldata.trim_start_matches("0x"))
+                    .expect("Invalid hex in calldata"),
+                #[cfg(feature = "rpc")]
+                (None, Some(rpc_url), Some(tx)) => rpc::transaction_input(&rpc_url, &tx),
+                #[cfg(not(feature = "rpc"))]
+                (None, Some(_), Some(_)) => panic!("Fetching transactions needs the rpc feature"),
+                // clap guarantees calldata or an endpoint with a transaction
+                _ => unreachable!(),
+            };
+            let profile = calldata_profile("bn254");
+            println!(
+                "{}",
+                evm::verify_submission(&profile, &data, &committee, message.as_bytes())
+            );
+        }
+        Commands::ExportWitness {
+            signature,
+            public_keys,
+            message,
+        } => {
+            assert_eq!(
+                cli.backend,
+                BackendKind::Sylow,
+                "Witnesses are only exported for the sylow backend"
+            );
+            #[cfg(feature = "blst")]
+            assert!(cli.suite.is_none(), "Witnesses are not exported for suites");
+            let sig_bytes = hex::decode(signature).expect("Invalid hex in signature");
+            let keys: Vec<Vec<u8>> = public_keys
+                .iter()
+                .map(|key_hex| hex::decode(key_hex).expect("Invalid hex in public key"))
+                .collect();
+            let witness = witness::export(&sig_bytes, &keys, message.as_bytes());
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&witness).expect("Failed to serialize witness")
+            );
+        }
+        Commands::HashToField { message, count } => {
+            assert_eq!(
+                cli.backend,
+                BackendKind::Sylow,
+                "Hashing is only traced for the sylow backend"
+            );
+            let trace = hash_to_field::trace(hash_params, message.as_bytes(), count);
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&trace).expect("Failed to serialize trace")
+            );
+        }
+        Commands::Countersign {
+            envelope: path,
+            key,
+            #[cfg(feature = "encryption")]
+            passphrase_file,
+        } => {
+            let key_pair = KeyPairFile::read(&key);
+            #[cfg(feature = "encryption")]
+            let passphrase = passphrase_file.as_deref().map(keypair::read_passphrase);
+            #[cfg(not(feature = "encryption"))]
+            let passphrase: Option<Vec<u8>> = None;
+            let secret_key = key_pair.secret(passphrase.as_deref());
+            let mut envelope = Envelope::read(&path);
+            envelope.countersign(key_pair.backend(), key_pair.suite(), &secret_key, &config);
+            envelope.write(&path);
+        }
+        #[cfg(feature = "qr")]
+        Commands::Scan { image } => {
+            for contents in qr::scan(&image) {
+                println!("{}", contents);
+            }
+        }
+        Commands::Pssm { command } => match command {
+            PssmCommands::Create {
+                message,
+                committee,
+                threshold,
+                metadata,
+                out,
+            } => {
+                #[cfg(feature = "blst")]
+                let suite = cli.suite.clone();
+                #[cfg(not(feature = "blst"))]
+                let suite = None;
+                let metadata = metadata
+                    .iter()
+                    .map(|entry| {
+                        let (key, value) = entry
+                            .split_once('=')
+                            .unwrap_or_else(|| panic!("Metadata {} is not key=value", entry));
+                        (key.to_string(), value.to_string())
+                    })
+                    .collect();
+                let committee = bundle::read_committee(&committee);
+                let pssm = pssm::Pssm::create(
+                    cli.backend,
+                    suite,
+                    message,
+                    &committee,
+                    threshold,
+                    metadata,
+                );
+                pssm.write(&out);
+                println!("{}", pssm.status());
+            }
+            PssmCommands::AddSignature {
+                file,
+                secret,
+                signature,
+            } => {
+                let mut pssm = pssm::Pssm::read(&file);
+                let backend = pssm.instantiate(&config);
+                match signature {
+                    Some(signature) => {
+                        let sig_bytes = hex::decode(signature).expect("Invalid hex in signature");
+                        pssm.add_signature(backend.as_ref(), &sig_bytes)
+                    }
+                    None => {
+                        assert!(
+                            secret.is_given(),
+                            "Pass a secret key with --secret, --secret-file or --key, or --signature"
+                        );
+                        let secret_key = secret.load(backend.as_ref(), &config, cli.reduce);
+                        pssm.sign(backend.as_ref(), &secret_key)
+                    }
+                };
+                pssm.write(&file);
+                println!("{}", pssm.status());
+            }
+            PssmCommands::Combine { files, out } => {
+                let mut copies = files.iter().map(|file| pssm::Pssm::read(file));
+                // clap guarantees at least two files
+                let mut pssm = copies.next().unwrap();
+                let backend = pssm.instantiate(&config);
+                for copy in copies {
+                    pssm.combine(backend.as_ref(), copy);
+                }
+                pssm.write(&out);
+                println!("{}", pssm.status());
+            }
+            PssmCommands::Finalize { file } => {
+                let mut pssm = pssm::Pssm::read(&file);
+                let backend = pssm.instantiate(&config);
+                let result = pssm.finalize(backend.as_ref());
+                pssm.write(&file);
+                println!("{}", result);
+            }
+        },
+        Commands::Envelope { command } => match command {
+            EnvelopeCommands::Show { envelope } => {
+                let envelope = Envelope::read(&envelope);
+                // a QR code carries the envelope itself, to be saved as a file on the other side
+                #[cfg(feature = "qr")]
+                if cli.qr || cli.qr_png.is_some() {
+                    emit(&serde_json::to_string(&envelope).expect("Failed to serialize envelope"));
+                    return;
+                }
+                println!("{}", envelope.display());
+            }
+        },
+        Commands::Keyset { command } => {
+            let decode_keys = |public_keys: &[String]| -> Vec<Vec<u8>> {
+                expand_list(public_keys)
+                    .iter()
+                    .map(|key| hex::decode(key).expect("Invalid hex in public key"))
+                    .collect()
+            };
+            let result = match command {
+                KeysetCommands::Commit { public_keys } => {
+                    keyset::commit(backend.as_ref(), &decode_keys(&public_keys))
+                }
+                KeysetCommands::Prove { public_keys, index } => {
+                    keyset::prove(backend.as_ref(), &decode_keys(&public_keys), index)
+                }
+                KeysetCommands::VerifyProof {
+                    root,
+                    public_key,
+                    index,
+                    proof,
+                } => {
+                    let proof: Vec<Vec<u8>> = proof
+                        .iter()
+                        .map(|hash| hex::decode(hash).expect("Invalid hex in proof"))
+                        .collect();
+                    keyset::verify_proof(
+                        &hex::decode(root).expect("Invalid hex in root"),
+                        &hex::decode(public_key).expect("Invalid hex in public key"),
+                        index,
+                        &proof,
+                    )
+                }
+            };
+            println!("{}", result);
+        }
+        Commands::Queue { command } => match command {
+            QueueCommands::Sign {
+                queue,
+                secret,
+                message,
+            } => {
+                let secret_key = secret.load(backend.as_ref(), &config, cli.reduce);
+                let public_key = backend.public_key_from_secret(&secret_key);
+                let signature = backend.sign(&secret_key, message.as_bytes());
+                #[cfg(feature = "blst")]
+                let suite = cli.suite.clone();
+                #[cfg(not(feature = "blst"))]
+                let suite = None;
+                let entry =
+                    queue::Entry::new(cli.backend.name(), suite, &public_key, message, &signature);
+                println!("{}", queue::push(&queue, &entry));
+            }
+            QueueCommands::Flush {
+                queue,
+                collector_url,
+            } => {
+                #[cfg(feature = "rpc")]
+                println!(
+                    "{}",
+                    queue::flush(&queue, |body| rpc::post(&collector_url, body))
+                );
+                #[cfg(not(feature = "rpc"))]
+                panic!(
+                    "Flushing {} to {} needs the rpc feature",
+                    queue.display(),
+                    collector_url
+                );
+            }
+        },
+        Commands::Audit { command } => match command {
+            AuditCommands::Verify { log, anchor } => {
+                println!("{}", audit::verify(&log, anchor.as_deref()));
+            }
+        },
+        Commands::Key { command } => match command {
+            KeyCommands::Load {
+                file,
+                reveal,
+                #[cfg(feature = "encryption")]
+                passphrase_file,
+            } => {
+                let key_pair = KeyPairFile::read(&file);
+                #[cfg(feature = "encryption")]
+                let passphrase = passphrase_file.as_deref().map(keypair::read_passphrase);
+                #[cfg(not(feature = "encryption"))]
+                let passphrase: Option<Vec<u8>> = None;
+                let secret_key = key_pair.secret(passphrase.as_deref());
+                let public_key = key_pair.public_key();
+                let backend = key_pair.instantiate(&config);
+                assert_eq!(
+                    backend.public_key_from_secret(&secret_key),
+                    public_key,
+                    "Key pair file holds a public key that does not belong to its secret key"
+                );
+                let mut result = json!({
+                    "publicKey": hex::encode(&public_key),
+                    "fingerprint": fingerprint(backend.as_ref(), &public_key),
+                });
+                if !reveal {
+                    println!("{}", result);
+                    return;
+                }
+                result["secretKey"] = json!(hex::encode(&*secret_key));
+                let output = Zeroizing::new(result.to_string());
+                if let Some(Value::String(secret_hex)) = result.get_mut("secretKey").map(Value::take)
+                {
+                    drop(Zeroizing::new(secret_hex));
+                }
+                println!("{}", *output);
+            }
+            KeyCommands::Import {
+                gpg,
+                out,
+                #[cfg(feature = "encryption")]
+                passphrase_file,
+            } => {
+                #[cfg(feature = "encryption")]
+                let passphrase = passphrase_file.as_deref().map(keypair::read_passphrase);
+                #[cfg(not(feature = "encryption"))]
+                let passphrase: Option<Vec<u8>> = None;
+                #[cfg(feature = "blst")]
+                let suite = cli.suite.clone();
+                #[cfg(not(feature = "blst"))]
+                let suite = None;
+                let plaintext = Zeroizing::new(gpg::decrypt(&gpg));
+                let secret_hex = std::str::from_utf8(&plaintext).expect("Decrypted key is not hex");
+                let secret_key = secret::load(backend.as_ref(), secret_hex.trim(), cli.reduce);
+                let public_key = backend.public_key_from_secret(&secret_key);
+                KeyPairFile::new(
+                    cli.backend,
+                    suite,
+                    &config,
+                    &secret_key,
+                    &public_key,
+                    passphrase.as_deref(),
+                )
+                .write(&out);
+                let result = json!({
+                    "publicKey": hex::encode(&public_key),
+                    "fingerprint": fingerprint(backend.as_ref(), &public_key),
+                });
+                println!("{}", result);
+            }
+            KeyCommands::Export {
+                file,
+                #[cfg(feature = "age")]
+                age_recipient,
+                gpg_recipient,
+                out,
+                #[cfg(feature = "encryption")]
+                passphrase_file,
+            } => {
+                let key_pair = KeyPairFile::read(&file);
+                #[cfg(feature = "encryption")]
+                let passphrase = passphrase_file.as_deref().map(keypair::read_passphrase);
+                #[cfg(not(feature = "encryption"))]
+                let passphrase: Option<Vec<u8>> = None;
+                let secret_key = key_pair.secret(passphrase.as_deref());
+                let secret_hex = Zeroizing::new(hex::encode(&secret_key));
+                // clap guarantees recipients of exactly one kind
+                #[cfg(feature = "age")]
+                if !age_recipient.is_empty() {
+                    let armored = age_file::encrypt(secret_hex.as_bytes(), &age_recipient);
+                    keypair::write_private(&out, armored.trim_end());
+                    return;
+                }
+                let armored = gpg::encrypt(secret_hex.as_bytes(), &gpg_recipient);
+                keypair::write_private(&out, armored.trim_end());
+            }
+        },
+        Commands::Beacon { command } => match command {
+            BeaconCommands::Deal { threshold, shares } => {
+                println!("{}", beacon::deal(threshold, shares));
+            }
+            BeaconCommands::Contribute {
+                secret_share,
+                round,
+                previous_signature,
+            } => {
+                let share = secret::load(&SylowBackend::default(), &secret_share, cli.reduce);
+                let previous = previous_signature
+                    .map(|sig| hex::decode(sig).expect("Invalid hex in previous signature"))
+                    .unwrap_or_default();
+                println!("{}", hex::encode(beacon::contribute(&share, round, &previous)));
+            }
+            BeaconCommands::Combine { contributions } => {
+                let contributions: Vec<(u64, Vec<u8>)> = contributions
+                    .iter()
+                    .map(|contribution| {
+                        let (index, signature) = contribution
+                            .split_once(':')
+                            .expect("Contributions must be index:signature");
+                        (
+                            index.parse().expect("Invalid share index"),
+                            hex::decode(signature).expect("Invalid hex in contribution"),
+                        )
+                    })
+                    .collect();
+                println!("{}", hex::encode(beacon::combine(&contributions)));
+            }
+            BeaconCommands::Verify {
+                group_key,
+                round,
+                previous_signature,
+                signature,
+            } => {
+                let key_bytes = hex::decode(group_key).expect("Invalid hex in group key");
+                let previous = previous_signature
+                    .map(|sig| hex::decode(sig).expect("Invalid hex in previous signature"))
+                    .unwrap_or_default();
+                let sig_bytes = hex::decode(signature).expect("Invalid hex in signature");
+                println!("{}", beacon::verify(&key_bytes, round, &previous, &sig_bytes));
+            }
+        },
+        #[cfg(feature = "blst")]
+        Commands::Suite { command } => match command {
+            SuiteCommands::List => println!("{}", suite::list(&config)),
+        },
+        Commands::Bbs { command } => match command {
+            BbsCommands::Keygen => {
+                let (secret_key, public_key) = bbs::keygen();
+                let result = json!({
+                    "secretKey": hex::encode(secret_key),
+                    "publicKey": hex::encode(public_key),
+                });
+                println!("{}", result);
+            }
+            BbsCommands::Sign {
+                secret,
+                messages,
+                header,
+            } => {
+                let secret_key = hex::decode(secret).expect("Invalid hex in secret key");
+                let scalars = bbs::messages_to_scalars(&messages);
+                let signature = bbs::sign(&secret_key, header.as_bytes(), &scalars);
+                println!("{}", hex::encode(signature));
+            }
+            BbsCommands::Verify {
+                signature,
+                public_key,
+                messages,
+                header,
+            } => {
+                let sig_bytes = hex::decode(signature).expect("Invalid hex in signature");
+                let key_bytes = hex::decode(public_key).expect("Invalid hex in public key");
+                let scalars = bbs::messages_to_scalars(&messages);
+                let valid = bbs::verify(&key_bytes, &sig_bytes, header.as_bytes(), &scalars);
+                println!("{}", json!({ "valid": valid }));
+            }
+            BbsCommands::DeriveProof {
+                signature,
+                public_key,
+                messages,
+                reveal,
+                header,
+                presentation_header,
+            } => {
+                let sig_bytes = hex::decode(signature).expect("Invalid hex in signature");
+                let key_bytes = hex::decode(public_key).expect("Invalid hex in public key");
+                let disclosed = reveal_indexes(&reveal);
+                assert!(
+                    disclosed.iter().all(|&i| i < messages.len()),
+                    "Revealed position is past the last message"
+                );
+                let scalars = bbs::messages_to_scalars(&messages);
+                let proof = bbs::derive_proof(
+                    &key_bytes,
+                    &sig_bytes,
+                    header.as_bytes(),
+                    presentation_header.as_bytes(),
+                    &scalars,
+                    &disclosed,
+                );
+                println!("{}", hex::encode(proof));
+            }
+            BbsCommands::VerifyProof {
+                proof,
+                public_key,
+                messages,
+                reveal,
+                header,
+                presentation_header,
+            } => {
+                let proof_bytes = hex::decode(proof).expect("Invalid hex in proof");
+                let key_bytes = hex::decode(public_key).expect("Invalid hex in public key");
+                assert_eq!(
+                    messages.len(),
+                    reveal.len(),
+                    "Expected one message per revealed position"
+                );
+                let mut pairs: Vec<(usize, String)> = reveal
+                    .iter()
+                    .map(|&i| i.checked_sub(1).expect("Message positions start at 1"))
+                    .zip(messages)
+                    .collect();
+                pairs.sort_by_key(|(i, _)| *i);
+                assert!(
+                    pairs.windows(2).all(|w| w[0].0 != w[1].0),
+                    "Revealed positions must be distinct"
+                );
+                let (indexes, revealed): (Vec<usize>, Vec<String>) = pairs.into_iter().unzip();
+                let disclosed: Vec<_> = indexes
+                    .into_iter()
+                    .zip(bbs::messages_to_scalars(&revealed))
+                    .collect();
+                let valid = bbs::verify_proof(
+                    &key_bytes,
+                    &proof_bytes,
+                    header.as_bytes(),
+                    presentation_header.as_bytes(),
+                    &disclosed,
+                );
+                println!("{}", json!({ "valid": valid }));
+            }
+        },
+        Commands::Vc { command } => match command {
+            VcCommands::Sign {
+                credential,
+                secret,
+                out,
+            } => {
+                assert_plain_backend("A credential proof");
+                let secret_key = secret.load(backend.as_ref(), &config, cli.reduce);
+                let unsigned = credential::read(&credential);
+                let signed = credential::sign(cli.backend, &secret_key, &unsigned);
+                let contents =
+                    serde_json::to_string_pretty(&signed).expect("Failed to serialize credential");
+                match out {
+                    Some(path) => {
+                        fs::write(path, contents + "\n").expect("Failed to write credential file");
+                    }
+                    None => println!("{}", contents),
+                }
+            }
+            VcCommands::Verify { credential } => {
+                assert_plain_backend("A credential proof");
+                println!("{}", credential::verify(&credential::read(&credential)));
+            }
+        },
+        Commands::Did { command } => match command {
+            DidCommands::FromKey { public_key } => {
+                let key_bytes = hex::decode(public_key).expect("Invalid hex in public key");
+                let did = did::from_key(cli.backend, &key_bytes);
+                println!("{}", json!({ "document": did::document(&did), "did": did }));
+            }
+            DidCommands::Resolve { did } => {
+                let public_key = did::resolve(&did);
+                let result = json!({
+                    "publicKey": hex::encode(public_key),
+                    "document": did::document(&did),
+                });
+                println!("{}", result);
+            }
+        },
+        Commands::Jws { command } => match command {
+            JwsCommands::Sign { secret, payload } => {
+                assert_plain_backend("A JWS token");
+                let secret_key = secret.load(backend.as_ref(), &config, cli.reduce);
+                println!("{}", jws::sign(cli.backend, &secret_key, payload.as_bytes()));
+            }
+            JwsCommands::Verify { token, public_key } => {
+                assert_plain_backend("A JWS token");
+                let key_bytes = hex::decode(public_key).expect("Invalid hex in public key");
+                println!("{}", jws::verify(cli.backend, &token, &key_bytes));
+            }
+        },
+        #[cfg(feature = "blst")]
+        Commands::Drand { command } => match command {
+            DrandCommands::Verify {
+                scheme,
+                round,
+                randomness,
+                signature,
+                previous_signature,
+                group_key,
+            } => {
+                let randomness = hex::decode(randomness).expect("Invalid hex in randomness");
+                let sig_bytes = hex::decode(signature).expect("Invalid hex in signature");
+                let previous = previous_signature
+                    .map(|sig| hex::decode(sig).expect("Invalid hex in previous signature"));
+                let key_bytes = hex::decode(group_key).expect("Invalid hex in group key");
+                let result = drand::verify(
+                    scheme,
+                    round,
+                    &randomness,
+                    &sig_bytes,
+                    previous.as_deref(),
+                    &key_bytes,
+                );
+                println!("{}", result);
+            }
+        },
+        #[cfg(feature = "timelock")]
+        Commands::Timelock { command } => match command {
+            TimelockCommands::Encrypt {
+                round,
+                group_key,
+                message,
+            } => {
+                let key_bytes = hex::decode(group_key).expect("Invalid hex in group key");
+                let ciphertext = timelock::encrypt(&key_bytes, round, message.as_bytes());
+                println!("{}", hex::encode(ciphertext));
+            }
+            TimelockCommands::Decrypt {
+                round_signature,
+                ciphertext,
+            } => {
+                let sig_bytes =
+                    hex::decode(round_signature).expect("Invalid hex in round signature");
+                let ct_bytes = hex::decode(ciphertext).expect("Invalid hex in ciphertext");
+                let message = timelock::decrypt(&sig_bytes, &ct_bytes);
+                println!("{}", String::from_utf8_lossy(&message));
+            }
+        },
+        Commands::Conformance { command } => match command {
+            ConformanceCommands::HashToCurve { vectors } => {
+                println!("{}", conformance::hash_to_curve(&vectors));
+            }
+        },
+        Commands::Difftest { ops, n } => {
+            println!("{}", difftest::run(&ops, n));
+        }
+        Commands::TimingTest { samples } => {
+            println!("{}", timing::run(backend.as_ref(), samples));
         }
     }
 }
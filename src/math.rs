@@ -0,0 +1,58 @@
+//! BN254 field and scalar arithmetic on hex encodings, for checking test vectors by hand.
+//!
+//! Scalars live in 𝔽ᵣ (the group order) and base field elements in 𝔽ₚ; both are 32 bytes
+//! big-endian. 𝔽ₚ² elements are 64 bytes, `c1 ‖ c0` for `c0 + c1·u`, the same order the
+//! coordinates of public keys use.
+
+use sylow::{Fp, Fp2, Fr};
+
+fn decode_scalar(bytes: &[u8]) -> Fr {
+    let array: [u8; 32] = bytes.try_into().expect("Scalar must be 32 bytes");
+    Fr::from_be_bytes(&array)
+        .into_option()
+        .expect("Scalar is not below the group order")
+}
+
+/// Scalars are below the group order, which is below the field modulus, so they encode as the
+/// base field element with the same value.
+fn encode_scalar(scalar: Fr) -> Vec<u8> {
+    Fp::from(scalar).to_be_bytes().to_vec()
+}
+
+fn decode_fp(bytes: &[u8]) -> Fp {
+    let array: [u8; 32] = bytes.try_into().expect("Field element must be 32 bytes");
+    Fp::from_be_bytes(&array)
+        .into_option()
+        .expect("Field element is not below the field modulus")
+}
+
+fn decode_fp2(bytes: &[u8]) -> Fp2 {
+    let array: [u8; 64] = bytes.try_into().expect("Fp2 element must be 64 bytes");
+    Fp2::from_be_bytes(&array)
+        .into_option()
+        .expect("Fp2 coefficient is not below the field modulus")
+}
+
+/// Reduces a big-endian integer of any length modulo the group order.
+pub fn scalar_mod(value: &[u8]) -> Vec<u8> {
+    let radix = Fr::from(256);
+    encode_scalar(
+        value
+            .iter()
+            .fold(Fr::ZERO, |acc, &byte| acc * radix + Fr::from(byte as u64)),
+    )
+}
+
+pub fn scalar_invert(scalar: &[u8]) -> Vec<u8> {
+    let scalar = decode_scalar(scalar);
+    assert!(scalar != Fr::ZERO, "Zero has no inverse");
+    encode_scalar(Fr::ONE / scalar)
+}
+
+pub fn fp_add(a: &[u8], b: &[u8]) -> Vec<u8> {
+    (decode_fp(a) + decode_fp(b)).to_be_bytes().to_vec()
+}
+
+pub fn fp2_mul(a: &[u8], b: &[u8]) -> Vec<u8> {
+    (decode_fp2(a) * decode_fp2(b)).to_be_bytes().to_vec()
+}
@@ -0,0 +1,129 @@
+//! Planning the aggregation of collected signatures.
+//!
+//! Signatures over the same message fold into one aggregate that verifies against the aggregate
+//! of the signers' keys, so N collected signatures over K distinct messages compress to K
+//! aggregates. `plan-aggregate` reads the same `{"signature", "publicKey", "message"}` records as
+//! `batch-verify`, groups them by message in order of first appearance and aggregates each
+//! group. Every record is verified first and the ones that do not verify are left out, since one
+//! of them would make its whole group fail; their lines are reported.
+//!
+//! Each group in the plan is itself such a record, with the aggregate signature and aggregate
+//! public key, so `--out` writes a file `batch-verify` checks directly.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::backend::Backend;
+use crate::fingerprint::fingerprint;
+use crate::threads;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Record {
+    signature: String,
+    public_key: String,
+    message: String,
+}
+
+/// Signatures over one message, with the lines they came from.
+struct Group<'a> {
+    message: &'a str,
+    lines: Vec<usize>,
+    signatures: Vec<Vec<u8>>,
+    public_keys: Vec<Vec<u8>>,
+}
+
+/// The plan for the records in `input`, writing the aggregates as verification records to `out`
+/// if given.
+pub fn plan(backend: &dyn Backend, input: &Path, out: Option<&Path>, threads: usize) -> Value {
+    let contents = fs::read_to_string(input).expect("Failed to read records");
+    let records: Vec<(usize, Record)> = contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let record = serde_json::from_str(line).unwrap_or_else(|error| {
+                panic!("Line {} is not a verification record: {}", index + 1, error)
+            });
+            (index + 1, record)
+        })
+        .collect();
+    let decoded = threads::map(threads, &records, |(_, record)| {
+        let signature = hex::decode(&record.signature).ok()?;
+        let public_key = hex::decode(&record.public_key).ok()?;
+        backend
+            .verify(&signature, &public_key, record.message.as_bytes())
+            .then_some((signature, public_key))
+    });
+
+    let mut groups: Vec<Group> = Vec::new();
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    let mut invalid = Vec::new();
+    for ((line, record), decoded) in records.iter().zip(decoded) {
+        let Some((signature, public_key)) = decoded else {
+            invalid.push(*line);
+            continue;
+        };
+        let position = *positions.entry(&record.message).or_insert_with(|| {
+            groups.push(Group {
+                message: &record.message,
+                lines: Vec::new(),
+                signatures: Vec::new(),
+                public_keys: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        let group = &mut groups[position];
+        group.lines.push(*line);
+        group.signatures.push(signature);
+        group.public_keys.push(public_key);
+    }
+
+    let aggregate_keys: Vec<Vec<u8>> = groups
+        .iter()
+        .map(|group| backend.aggregate_public_keys(&group.public_keys))
+        .collect();
+    let aggregates: Vec<Value> = groups
+        .iter()
+        .zip(&aggregate_keys)
+        .map(|(group, public_key)| {
+            json!({
+                "signature": hex::encode(backend.aggregate_signatures(&group.signatures)),
+                "publicKey": hex::encode(public_key),
+                "message": group.message,
+            })
+        })
+        .collect();
+    if let Some(out) = out {
+        let contents: String = aggregates
+            .iter()
+            .map(|aggregate| aggregate.to_string() + "\n")
+            .collect();
+        fs::write(out, contents).expect("Failed to write verification records");
+    }
+    let groups: Vec<Value> = groups
+        .iter()
+        .zip(aggregates)
+        .zip(&aggregate_keys)
+        .map(|((group, mut aggregate), public_key)| {
+            aggregate["fingerprint"] = json!(fingerprint(backend, public_key));
+            aggregate["lines"] = json!(group.lines);
+            aggregate["publicKeys"] = json!(group
+                .public_keys
+                .iter()
+                .map(hex::encode)
+                .collect::<Vec<String>>());
+            aggregate
+        })
+        .collect();
+    json!({
+        "records": records.len(),
+        "aggregates": groups.len(),
+        "groups": groups,
+        "invalidLines": invalid,
+    })
+}
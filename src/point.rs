@@ -0,0 +1,119 @@
+//! BN254 group arithmetic on hex-encoded points, for replaying aggregation and verification
+//! steps by hand.
+//!
+//! Points use the sylow encodings: 𝔾₁ as `x ‖ y` (64 bytes) and 𝔾₂ as
+//! `x.c1 ‖ x.c0 ‖ y.c1 ‖ y.c0` (128 bytes), with the top bit of the first byte marking the
+//! point at infinity. Scalars are 32-byte big-endian integers below the group order.
+
+use clap::ValueEnum;
+use sylow::{FieldExtensionTrait, Fp, Fp2, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+
+/// Groups selectable with `--group`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Group {
+    /// 𝔾₁, where signatures live
+    G1,
+    /// 𝔾₂, where public keys live
+    G2,
+}
+
+fn try_decode_g1(bytes: &[u8]) -> Option<G1Projective> {
+    let array: [u8; 64] = bytes.try_into().expect("G1 points must be 64 bytes");
+    G1Affine::from_be_bytes(&array).into_option()
+}
+
+/// Whether the coordinates of a 𝔾₂ encoding satisfy the twist equation `y² = x³ + b'`. sylow
+/// runs its subgroup check before the curve check and panics on points off the curve, so this
+/// has to come first.
+fn satisfies_g2_equation(bytes: &[u8; 128]) -> bool {
+    if bytes[0] & 0x80 != 0 {
+        return true;
+    }
+    let element = |offset: usize| {
+        let mut array: [u8; 32] = bytes[offset..offset + 32].try_into().unwrap();
+        if offset == 0 {
+            array[0] &= 0x7f;
+        }
+        Fp::from_be_bytes(&array).into_option()
+    };
+    let coordinates = (element(0), element(32), element(64), element(96));
+    let (Some(x1), Some(x0), Some(y1), Some(y0)) = coordinates else {
+        return false;
+    };
+    let x = Fp2::new(&[x0, x1]);
+    let y = Fp2::new(&[y0, y1]);
+    y * y == x * x * x + <Fp2 as FieldExtensionTrait<2, 2>>::curve_constant()
+}
+
+fn try_decode_g2(bytes: &[u8]) -> Option<G2Projective> {
+    let array: [u8; 128] = bytes.try_into().expect("G2 points must be 128 bytes");
+    if !satisfies_g2_equation(&array) {
+        return None;
+    }
+    G2Affine::from_be_bytes(&array).into_option()
+}
+
+fn decode_g1(bytes: &[u8]) -> G1Projective {
+    try_decode_g1(bytes).expect("Invalid G1 point")
+}
+
+fn decode_g2(bytes: &[u8]) -> G2Projective {
+    try_decode_g2(bytes).expect("Invalid G2 point")
+}
+
+fn encode_g1(point: G1Projective) -> Vec<u8> {
+    G1Affine::from(point).to_be_bytes().to_vec()
+}
+
+fn encode_g2(point: G2Projective) -> Vec<u8> {
+    G2Affine::from(point).to_be_bytes().to_vec()
+}
+
+fn decode_scalar(bytes: &[u8]) -> Fp {
+    let array: [u8; 32] = bytes.try_into().expect("Scalar must be 32 bytes");
+    let scalar = Fr::from_be_bytes(&array)
+        .into_option()
+        .expect("Scalar is not below the group order");
+    Fp::from(scalar)
+}
+
+/// Adds up any number of points of one group.
+pub fn add(group: Group, points: &[Vec<u8>]) -> Vec<u8> {
+    match group {
+        Group::G1 => encode_g1(
+            points
+                .iter()
+                .fold(G1Projective::zero(), |acc, p| acc + decode_g1(p)),
+        ),
+        Group::G2 => encode_g2(
+            points
+                .iter()
+                .fold(G2Projective::zero(), |acc, p| acc + decode_g2(p)),
+        ),
+    }
+}
+
+pub fn mul(group: Group, point: &[u8], scalar: &[u8]) -> Vec<u8> {
+    let scalar = decode_scalar(scalar);
+    match group {
+        Group::G1 => encode_g1(decode_g1(point) * scalar),
+        Group::G2 => encode_g2(decode_g2(point) * scalar),
+    }
+}
+
+pub fn neg(group: Group, point: &[u8]) -> Vec<u8> {
+    match group {
+        Group::G1 => encode_g1(-decode_g1(point)),
+        Group::G2 => encode_g2(-decode_g2(point)),
+    }
+}
+
+/// Whether the bytes decode to a point of the group. For 𝔾₂ this includes membership in the
+/// prime-order subgroup, which sylow checks together with the curve equation; every point on
+/// the 𝔾₁ curve is in its group.
+pub fn is_on_curve(group: Group, point: &[u8]) -> bool {
+    match group {
+        Group::G1 => try_decode_g1(point).is_some(),
+        Group::G2 => try_decode_g2(point).is_some(),
+    }
+}
@@ -0,0 +1,109 @@
+//! Proofs of possession for BN254 committee keys.
+//!
+//! Plain key aggregation, as `bundle-proof` and `pssm` do it, is only sound when every member
+//! has shown they hold the secret behind their public key; otherwise one member can pick a key
+//! that cancels the others' out. A proof of possession is a signature over the member's own
+//! serialized public key, hashed under a DST of its own so that it can never pass as a
+//! signature over a message.
+//!
+//! `pop-verify-batch` checks a whole committee with one multi-pairing: each proof is weighted
+//! by a fresh random 128-bit scalar, so a forged proof cannot be cancelled out by another. Only
+//! when the batch fails are the proofs checked one at a time, to name the members at fault.
+
+use rand_core::{OsRng, RngCore};
+use serde_json::{json, Value};
+use sha3::Keccak256;
+use sylow::{Fp, G1Affine, G1Projective, G2Affine, G2Projective, GroupTrait, XMDExpander};
+
+use crate::backend::sylow::{pairing_check, SECURITY_BITS};
+
+const POP_DST: &[u8] = b"WARLOCK-CHAOS-V01-CS01-SHA-256-POP";
+
+fn hash_public_key(public_key: &[u8]) -> G1Projective {
+    let expander = XMDExpander::<Keccak256>::new(POP_DST, SECURITY_BITS);
+    G1Projective::hash_to_curve(&expander, public_key).expect("Hashing failed")
+}
+
+/// The proof of possession for the key pair of `secret`.
+pub fn prove(secret: &[u8], public_key: &[u8]) -> Vec<u8> {
+    let secret: [u8; 32] = secret.try_into().expect("Secret key must be 32 bytes");
+    let secret = Fp::from_be_bytes(&secret)
+        .into_option()
+        .expect("Failed to deserialize secret key");
+    G1Affine::from(hash_public_key(public_key) * secret)
+        .to_be_bytes()
+        .to_vec()
+}
+
+/// A member's key and proof, or `None` when either does not decode to a point.
+fn decode(public_key: &[u8], proof: &[u8]) -> Option<(G2Projective, G1Projective)> {
+    let key: [u8; 128] = public_key.try_into().ok()?;
+    let proof: [u8; 64] = proof.try_into().ok()?;
+    let key = G2Affine::from_be_bytes(&key).into_option()?;
+    let proof = G1Affine::from_be_bytes(&proof).into_option()?;
+    Some((G2Projective::from(key), G1Projective::from(proof)))
+}
+
+fn random_weight() -> Fp {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes[16..]);
+    Fp::from_be_bytes(&bytes).into_option().unwrap()
+}
+
+/// Checks every `(public key, proof)` pair as e(Σ rᵢ·πᵢ, −g₂)·∏ e(rᵢ·H(pkᵢ), pkᵢ) = 1.
+fn batch_check(members: &[(Vec<u8>, G2Projective, G1Projective)]) -> bool {
+    let mut proofs = G1Projective::zero();
+    let mut pairs = Vec::with_capacity(members.len() + 1);
+    for (bytes, key, proof) in members {
+        let weight = random_weight();
+        proofs = proofs + *proof * weight;
+        pairs.push((hash_public_key(bytes) * weight, *key));
+    }
+    pairs.push((proofs, -G2Projective::generator()));
+    pairing_check(&pairs)
+}
+
+/// Verifies the proof of every committee member, given in committee order, and reports which
+/// members are safe to aggregate.
+pub fn verify_batch(committee: &[Vec<u8>], proofs: &[Vec<u8>]) -> Value {
+    assert_eq!(
+        committee.len(),
+        proofs.len(),
+        "There must be one proof per committee member"
+    );
+    let decoded: Vec<Option<(G2Projective, G1Projective)>> = committee
+        .iter()
+        .zip(proofs)
+        .map(|(key, proof)| decode(key, proof))
+        .collect();
+    let members: Vec<(Vec<u8>, G2Projective, G1Projective)> = committee
+        .iter()
+        .zip(&decoded)
+        .filter_map(|(bytes, point)| point.map(|(key, proof)| (bytes.clone(), key, proof)))
+        .collect();
+    let batch_valid = decoded.iter().all(Option::is_some) && batch_check(&members);
+    let valid: Vec<bool> = if batch_valid {
+        vec![true; committee.len()]
+    } else {
+        committee
+            .iter()
+            .zip(&decoded)
+            .map(|(bytes, point)| {
+                point.is_some_and(|(key, proof)| {
+                    pairing_check(&[
+                        (proof, -G2Projective::generator()),
+                        (hash_public_key(bytes), key),
+                    ])
+                })
+            })
+            .collect()
+    };
+    let safe: Vec<usize> = (0..committee.len()).filter(|&i| valid[i]).collect();
+    let rejected: Vec<usize> = (0..committee.len()).filter(|&i| !valid[i]).collect();
+    json!({
+        "valid": batch_valid,
+        "committeeSize": committee.len(),
+        "safe": safe,
+        "rejected": rejected,
+    })
+}
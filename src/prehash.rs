@@ -0,0 +1,56 @@
+//! Message digests computed before hash-to-curve.
+//!
+//! With `--prehash poseidon` the message is a comma-separated list of up to 12 elements of the
+//! BN254 scalar field, given in decimal or as `0x` hex, and what gets signed is the 32-byte
+//! big-endian Poseidon hash of those elements. The parameters are circomlib's, so a circuit can
+//! recompute the same commitment with a single `Poseidon(n)` template.
+
+use std::str::FromStr;
+
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use clap::ValueEnum;
+use light_poseidon::{Poseidon, PoseidonHasher};
+
+/// Digests selectable with `--prehash`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Prehash {
+    /// circomlib Poseidon over comma-separated BN254 scalar field elements
+    Poseidon,
+}
+
+fn parse_element(element: &str) -> Fr {
+    let element = element.trim();
+    match element.strip_prefix("0x") {
+        Some(digits) => {
+            let bytes = hex::decode(digits).expect("Invalid hex in message field");
+            let value = Fr::from_be_bytes_mod_order(&bytes);
+            let mut padded = vec![0u8; 32usize.saturating_sub(bytes.len())];
+            padded.extend_from_slice(&bytes);
+            assert_eq!(
+                value.into_bigint().to_bytes_be(),
+                padded,
+                "Message field {} is not below the scalar field modulus",
+                element
+            );
+            value
+        }
+        None => Fr::from_str(element)
+            .unwrap_or_else(|_| panic!("Message field {} is not a field element", element)),
+    }
+}
+
+impl Prehash {
+    /// The bytes that get signed in place of `message`.
+    pub fn digest(self, message: &str) -> Vec<u8> {
+        match self {
+            Prehash::Poseidon => {
+                let inputs: Vec<Fr> = message.split(',').map(parse_element).collect();
+                let hash = Poseidon::<Fr>::new_circom(inputs.len())
+                    .and_then(|mut poseidon| poseidon.hash(&inputs))
+                    .expect("Poseidon takes between 1 and 12 message fields");
+                hash.into_bigint().to_bytes_be()
+            }
+        }
+    }
+}
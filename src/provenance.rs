@@ -0,0 +1,87 @@
+//! Provenance records of aggregate signatures.
+//!
+//! An aggregate that fails to verify says nothing about which contribution broke it. With
+//! `aggregate-signatures --provenance`, the tool writes alongside the aggregate a JSON record of
+//! every contributing signature in folding order, where it came from (`file:line` for `@file`
+//! lists, the argument position otherwise) and, when the signers' public keys and the message are
+//! given, whether it verifies on its own. Disputes over a bad aggregate can then be traced to the
+//! contributions marked invalid. With `--validate` those are left out of the aggregate, and the
+//! record shows which ones were.
+
+use std::fs;
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::backend::Backend;
+use crate::threads;
+
+/// A signature folded into an aggregate, and where it came from.
+pub struct Contribution {
+    pub source: String,
+    pub signature: Vec<u8>,
+}
+
+/// Whether each contribution verifies against the public key at the same position of
+/// `signers`, or `None` for all of them when the signers and the message are not known.
+pub fn check(
+    backend: &dyn Backend,
+    contributions: &[Contribution],
+    signers: Option<(&[Vec<u8>], &[u8])>,
+    threads: usize,
+) -> Vec<Option<bool>> {
+    if let Some((public_keys, _)) = signers {
+        assert!(
+            public_keys.len() == contributions.len(),
+            "{} public keys given for {} signatures",
+            public_keys.len(),
+            contributions.len()
+        );
+    }
+    let indexes: Vec<usize> = (0..contributions.len()).collect();
+    threads::map(threads, &indexes, |&index| {
+        signers.map(|(public_keys, message)| {
+            backend.verify(&contributions[index].signature, &public_keys[index], message)
+        })
+    })
+}
+
+/// The provenance of `aggregate`, with the results of `check` for every contribution.
+pub fn record(
+    contributions: &[Contribution],
+    signers: Option<(&[Vec<u8>], &[u8])>,
+    valid: &[Option<bool>],
+    aggregate: &[u8],
+) -> Value {
+    let entries: Vec<Value> = contributions
+        .iter()
+        .zip(valid)
+        .enumerate()
+        .map(|(index, (contribution, valid))| {
+            json!({
+                "index": index,
+                "source": contribution.source,
+                "signature": hex::encode(&contribution.signature),
+                "publicKey": signers.map(|(public_keys, _)| hex::encode(&public_keys[index])),
+                "valid": valid,
+            })
+        })
+        .collect();
+    let invalid: Vec<&str> = contributions
+        .iter()
+        .zip(valid)
+        .filter(|(_, valid)| **valid == Some(false))
+        .map(|(contribution, _)| contribution.source.as_str())
+        .collect();
+    json!({
+        "message": signers.map(|(_, message)| String::from_utf8_lossy(message).to_string()),
+        "contributions": entries,
+        "invalid": signers.map(|_| invalid),
+        "aggregate": hex::encode(aggregate),
+    })
+}
+
+pub fn write(path: &Path, record: &Value) {
+    let contents = serde_json::to_string_pretty(record).expect("Failed to serialize provenance");
+    fs::write(path, contents + "\n").expect("Failed to write provenance record");
+}
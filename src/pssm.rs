@@ -0,0 +1,206 @@
+//! Partially signed BLS messages.
+//!
+//! A PSSM file carries one multi-party signing round from start to end, the way a PSBT does
+//! for Bitcoin transactions: `pssm create` fixes the message, the committee (public keys in
+//! bitfield order), how many signatures are needed and free-form metadata; each participant
+//! runs `pssm add-signature` on the file, which checks the partial signature and files it under
+//! the member who made it; `pssm combine` merges copies that were signed in parallel; and
+//! `pssm finalize` aggregates the partial signatures once there are enough of them.
+//!
+//! Partial signatures are checked when they are added and again when they are combined, so a
+//! finalized aggregate never includes a signature that does not verify.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::backend::{Backend, BackendKind};
+use crate::config::Config;
+#[cfg(feature = "blst")]
+use crate::suite;
+
+pub const PSSM_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Aggregate {
+    signers: Vec<usize>,
+    signature: String,
+    public_key: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pssm {
+    version: u32,
+    backend: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    suite: Option<String>,
+    message: String,
+    committee: Vec<String>,
+    threshold: usize,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, String>,
+    /// Partial signatures by committee index
+    partial_signatures: BTreeMap<usize, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    aggregate: Option<Aggregate>,
+}
+
+fn decode(value: &str, what: &str) -> Vec<u8> {
+    hex::decode(value).unwrap_or_else(|_| panic!("Invalid hex in {}", what))
+}
+
+impl Pssm {
+    pub fn create(
+        backend: BackendKind,
+        suite: Option<String>,
+        message: String,
+        committee: &[Vec<u8>],
+        threshold: Option<usize>,
+        metadata: BTreeMap<String, String>,
+    ) -> Self {
+        assert!(!committee.is_empty(), "The committee needs at least one member");
+        let threshold = threshold.unwrap_or(committee.len());
+        assert!(
+            (1..=committee.len()).contains(&threshold),
+            "Threshold must be between 1 and the committee size"
+        );
+        Pssm {
+            version: PSSM_VERSION,
+            backend: backend.name(),
+            suite,
+            message,
+            committee: committee.iter().map(hex::encode).collect(),
+            threshold,
+            metadata,
+            partial_signatures: BTreeMap::new(),
+            aggregate: None,
+        }
+    }
+
+    pub fn read(path: &Path) -> Self {
+        let contents = fs::read_to_string(path).expect("Failed to read PSSM file");
+        let pssm: Pssm = serde_json::from_str(&contents).expect("PSSM file is not valid");
+        assert_eq!(pssm.version, PSSM_VERSION, "Unsupported PSSM version");
+        pssm
+    }
+
+    pub fn write(&self, path: &Path) {
+        let contents = serde_json::to_string_pretty(self).expect("Failed to serialize PSSM");
+        fs::write(path, contents + "\n").expect("Failed to write PSSM file");
+    }
+
+    /// The backend the round signs with.
+    #[cfg_attr(not(feature = "blst"), allow(unused_variables))]
+    pub fn instantiate(&self, config: &Config) -> Box<dyn Backend> {
+        match &self.suite {
+            #[cfg(feature = "blst")]
+            Some(name) => suite::instantiate(name, config),
+            #[cfg(not(feature = "blst"))]
+            Some(name) => panic!("PSSM was made under suite {}, which needs blst", name),
+            None => BackendKind::from_str(&self.backend, true)
+                .expect("PSSM names an unknown backend")
+                .instantiate(),
+        }
+    }
+
+    /// Index of the committee member whose key verifies `signature`.
+    fn signer(&self, backend: &dyn Backend, signature: &[u8]) -> usize {
+        self.committee
+            .iter()
+            .position(|key| {
+                backend.verify(signature, &decode(key, "committee key"), self.message.as_bytes())
+            })
+            .expect("Signature is not by any committee member over this message")
+    }
+
+    fn assert_open(&self) {
+        assert!(self.aggregate.is_none(), "PSSM is already finalized");
+    }
+
+    /// Signs the message with `secret`, which must belong to a committee member.
+    pub fn sign(&mut self, backend: &dyn Backend, secret: &[u8]) -> usize {
+        let signature = backend.sign(secret, self.message.as_bytes());
+        self.add_signature(backend, &signature)
+    }
+
+    /// Files a partial signature under the member who made it, returning their index.
+    pub fn add_signature(&mut self, backend: &dyn Backend, signature: &[u8]) -> usize {
+        self.assert_open();
+        let index = self.signer(backend, signature);
+        self.partial_signatures.insert(index, hex::encode(signature));
+        index
+    }
+
+    /// Merges the partial signatures of `other`, another copy of the same round.
+    pub fn combine(&mut self, backend: &dyn Backend, other: Pssm) {
+        self.assert_open();
+        assert!(
+            other.backend == self.backend
+                && other.suite == self.suite
+                && other.message == self.message
+                && other.committee == self.committee
+                && other.threshold == self.threshold,
+            "PSSM files belong to different signing rounds"
+        );
+        for (index, signature) in other.partial_signatures {
+            let bytes = decode(&signature, "partial signature");
+            assert_eq!(
+                self.signer(backend, &bytes),
+                index,
+                "Partial signature is filed under the wrong member"
+            );
+            self.partial_signatures.insert(index, signature);
+        }
+    }
+
+    /// Aggregates the partial signatures once at least `threshold` members have signed.
+    pub fn finalize(&mut self, backend: &dyn Backend) -> Value {
+        self.assert_open();
+        assert!(
+            self.partial_signatures.len() >= self.threshold,
+            "Only {} of the {} needed signatures are present",
+            self.partial_signatures.len(),
+            self.threshold
+        );
+        let signers: Vec<usize> = self.partial_signatures.keys().copied().collect();
+        let signatures: Vec<Vec<u8>> = self
+            .partial_signatures
+            .values()
+            .map(|signature| decode(signature, "partial signature"))
+            .collect();
+        let public_keys: Vec<Vec<u8>> = signers
+            .iter()
+            .map(|&index| decode(&self.committee[index], "committee key"))
+            .collect();
+        let signature = backend.aggregate_signatures(&signatures);
+        let public_key = backend.aggregate_public_keys(&public_keys);
+        assert!(
+            backend.verify(&signature, &public_key, self.message.as_bytes()),
+            "Aggregate signature does not verify"
+        );
+        let aggregate = Aggregate {
+            signers,
+            signature: hex::encode(signature),
+            public_key: hex::encode(public_key),
+        };
+        let result = serde_json::to_value(&aggregate).expect("Failed to serialize aggregate");
+        self.aggregate = Some(aggregate);
+        result
+    }
+
+    /// Progress of the round.
+    pub fn status(&self) -> Value {
+        json!({
+            "committeeSize": self.committee.len(),
+            "threshold": self.threshold,
+            "signers": self.partial_signatures.keys().collect::<Vec<_>>(),
+            "finalized": self.aggregate.is_some(),
+        })
+    }
+}
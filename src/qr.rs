@@ -0,0 +1,67 @@
+//! QR codes for moving keys, envelopes and signatures across an air gap.
+//!
+//! `--qr` prints a command's result as a QR code in the terminal instead of text, and
+//! `--qr-png` writes it as a PNG image; both apply to commands that print a single value
+//! (signatures, public keys, aggregates) and to `envelope show`, which then encodes the whole
+//! envelope file so a signing request or its answer can travel as one code. `scan` reads every
+//! QR code in a PNG or JPEG image, such as a photo of another machine's screen.
+
+use std::path::Path;
+
+use image::{GrayImage, Luma};
+use qrcode::render::unicode::Dense1x2;
+use qrcode::{Color, QrCode};
+
+/// Modules of light border around the code, as the QR specification asks for.
+const QUIET_ZONE: u32 = 4;
+/// Pixels per module in PNG output.
+const SCALE: u32 = 8;
+
+fn encode(text: &str) -> QrCode {
+    QrCode::new(text.as_bytes()).expect("Too much data for a QR code")
+}
+
+/// `text` as a QR code drawn with half-block characters, light on dark so it scans from a
+/// terminal with a dark background.
+pub fn render_terminal(text: &str) -> String {
+    encode(text)
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build()
+}
+
+/// Writes `text` as a QR code into a PNG image at `path`.
+pub fn write_png(text: &str, path: &Path) {
+    let code = encode(text);
+    let width = code.width() as u32;
+    let colors = code.to_colors();
+    let size = (width + 2 * QUIET_ZONE) * SCALE;
+    let image = GrayImage::from_fn(size, size, |x, y| {
+        let (column, row) = (x / SCALE, y / SCALE);
+        let modules = QUIET_ZONE..QUIET_ZONE + width;
+        if !modules.contains(&column) || !modules.contains(&row) {
+            return Luma([255]);
+        }
+        let index = ((row - QUIET_ZONE) * width + column - QUIET_ZONE) as usize;
+        if colors[index] == Color::Dark {
+            Luma([0])
+        } else {
+            Luma([255])
+        }
+    });
+    image.save(path).expect("Failed to write QR code image");
+}
+
+/// The contents of every QR code found in the image at `path`.
+pub fn scan(path: &Path) -> Vec<String> {
+    let image = image::open(path)
+        .expect("Failed to read image")
+        .to_luma8();
+    let mut prepared = rqrr::PreparedImage::prepare(image);
+    prepared
+        .detect_grids()
+        .iter()
+        .map(|grid| grid.decode().expect("Found a QR code but could not decode it").1)
+        .collect()
+}
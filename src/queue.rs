@@ -0,0 +1,118 @@
+//! Durable local queue of signatures for nodes that are only intermittently online.
+//!
+//! `queue sign` signs as `sign` does and appends the result to a JSON-lines queue file, synced
+//! to disk before the command returns. `queue flush --collector-url` later POSTs every queued
+//! entry, oldest first, as a JSON body to a collector service (the `rpc` feature provides the
+//! HTTP client). Delivered entries leave the queue; at the first failure flushing stops and the
+//! undelivered rest stays queued, in order, for the next flush. The queue is rewritten through a
+//! temporary file and a rename, so a crash mid-flush never loses entries, though one may be
+//! delivered twice.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    backend: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suite: Option<String>,
+    public_key: String,
+    message: String,
+    signature: String,
+    queued_at: u64,
+}
+
+impl Entry {
+    pub fn new(
+        backend: String,
+        suite: Option<String>,
+        public_key: &[u8],
+        message: String,
+        signature: &[u8],
+    ) -> Self {
+        Entry {
+            backend,
+            suite,
+            public_key: hex::encode(public_key),
+            message,
+            signature: hex::encode(signature),
+            queued_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("System clock is before the Unix epoch")
+                .as_secs(),
+        }
+    }
+}
+
+fn read(path: &Path) -> Vec<Entry> {
+    if !path.exists() {
+        return Vec::new();
+    }
+    fs::read_to_string(path)
+        .expect("Failed to read queue")
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).expect("Malformed queue entry"))
+        .collect()
+}
+
+#[cfg(feature = "rpc")]
+fn rewrite(path: &Path, entries: &[Entry]) {
+    let temporary = path.with_extension("tmp");
+    let contents: String = entries
+        .iter()
+        .map(|entry| serde_json::to_string(entry).expect("Failed to serialize queue entry") + "\n")
+        .collect();
+    let mut file = fs::File::create(&temporary).expect("Failed to write queue");
+    file.write_all(contents.as_bytes())
+        .and_then(|_| file.sync_all())
+        .expect("Failed to write queue");
+    fs::rename(&temporary, path).expect("Failed to write queue");
+}
+
+/// Appends `entry` to the queue at `path` and returns how many entries are pending.
+pub fn push(path: &Path, entry: &Entry) -> Value {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .expect("Failed to open queue");
+    let line = serde_json::to_string(entry).expect("Failed to serialize queue entry");
+    writeln!(file, "{}", line)
+        .and_then(|_| file.sync_all())
+        .expect("Failed to write queue");
+    json!({ "signature": entry.signature, "pending": read(path).len() })
+}
+
+/// Delivers queued entries with `send`, oldest first, until one fails; returns what was sent.
+#[cfg(feature = "rpc")]
+pub fn flush(path: &Path, send: impl Fn(&Value) -> Result<(), String>) -> Value {
+    let mut entries = read(path);
+    let mut sent = 0;
+    let mut error = None;
+    for entry in &entries {
+        let body = serde_json::to_value(entry).expect("Failed to serialize queue entry");
+        match send(&body) {
+            Ok(()) => sent += 1,
+            Err(message) => {
+                error = Some(message);
+                break;
+            }
+        }
+    }
+    entries.drain(..sent);
+    if sent > 0 {
+        rewrite(path, &entries);
+    }
+    json!({
+        "sent": sent,
+        "pending": entries.len(),
+        "error": error,
+    })
+}
@@ -0,0 +1,106 @@
+//! Golden-file regression checks between releases.
+//!
+//! `regress record` writes, for every backend and built-in suite in the build, a fixtures file
+//! (see [`crate::fixtures`]) computed from a fixed set of secret keys, so the files are the
+//! same on every run of the same release. `regress check` recomputes each file with the
+//! current build and lists every value that changed: a diff there means keys, signatures or
+//! aggregates are no longer encoded or hashed the way they were when the files were recorded.
+//!
+//! Files for backends or suites missing from the current build are reported as skipped rather
+//! than failed, so golden files recorded with every feature still check in a minimal build.
+
+use std::fs;
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+use sha3::{Digest, Keccak256};
+
+use crate::backend::{Backend, BackendKind};
+use crate::config::Config;
+use crate::fixtures::{FixtureFormat, Fixtures};
+#[cfg(feature = "blst")]
+use crate::suite;
+
+/// Key pairs in every golden file.
+const KEY_COUNT: usize = 3;
+
+/// The fixed key pairs, derived from hashes of their index so that every backend can reduce
+/// them to a valid secret key.
+fn key_pairs(backend: &dyn Backend) -> Vec<(Vec<u8>, Vec<u8>)> {
+    (1..=KEY_COUNT)
+        .map(|index| {
+            let seed = Keccak256::digest(format!("bls-tools regression key {}", index));
+            let secret = backend.reduce_secret(&seed);
+            let public_key = backend.public_key_from_secret(&secret);
+            (secret, public_key)
+        })
+        .collect()
+}
+
+fn write(directory: &Path, name: &str, fixtures: &Fixtures) -> String {
+    let file = format!("{}.json", name);
+    let contents = serde_json::to_string_pretty(fixtures).expect("Failed to serialize fixtures");
+    fs::write(directory.join(&file), contents + "\n").expect("Failed to write golden file");
+    file
+}
+
+/// Writes a golden file for every backend and built-in suite into `directory`.
+pub fn record(directory: &Path) -> Value {
+    fs::create_dir_all(directory).expect("Failed to create golden directory");
+    let mut files = Vec::new();
+    for kind in BackendKind::value_variants() {
+        let backend = kind.instantiate();
+        let fixtures = Fixtures::from_key_pairs(
+            FixtureFormat::Dkgjs,
+            *kind,
+            None,
+            backend.as_ref(),
+            key_pairs(backend.as_ref()),
+        );
+        files.push(write(directory, &kind.name(), &fixtures));
+    }
+    #[cfg(feature = "blst")]
+    for name in suite::BUILTIN {
+        let backend = suite::instantiate(name, &Config::default());
+        let fixtures = Fixtures::from_key_pairs(
+            FixtureFormat::Dkgjs,
+            BackendKind::Blst,
+            Some(name.to_string()),
+            backend.as_ref(),
+            key_pairs(backend.as_ref()),
+        );
+        files.push(write(directory, &format!("suite-{}", name), &fixtures));
+    }
+    json!({ "recorded": files })
+}
+
+/// Recomputes every golden file in `directory` and reports the values that changed.
+pub fn check(directory: &Path, config: &Config) -> Value {
+    let mut paths: Vec<_> = fs::read_dir(directory)
+        .expect("Failed to read golden directory")
+        .map(|entry| entry.expect("Failed to read golden directory").path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "Golden directory has no files");
+    let mut results = Map::new();
+    let mut skipped = Vec::new();
+    let mut valid = true;
+    for path in paths {
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        let fixtures = Fixtures::read(&path);
+        if !fixtures.available() {
+            skipped.push(name);
+            continue;
+        }
+        let result = fixtures.check(fixtures.instantiate(config).as_ref());
+        valid &= result["valid"] == true;
+        results.insert(name, result);
+    }
+    json!({
+        "valid": valid,
+        "files": results,
+        "skipped": skipped,
+    })
+}
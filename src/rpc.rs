@@ -0,0 +1,43 @@
+//! Minimal Ethereum JSON-RPC client.
+//!
+//! Transactions are sent with `eth_sendTransaction`, so the node signs them with one of its own
+//! unlocked accounts; the tool never holds an Ethereum key. Queued signatures are delivered to
+//! collector services with a plain JSON POST.
+
+use serde_json::{json, Value};
+
+/// Calls `method` on the node at `url` and returns the result.
+pub fn call(url: &str, method: &str, params: Value) -> Value {
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+    let response: Value = ureq::post(url)
+        .send_json(request)
+        .unwrap_or_else(|err| panic!("RPC request to {} failed: {}", url, err))
+        .into_json()
+        .expect("RPC response is not JSON");
+    if let Some(error) = response.get("error") {
+        panic!("RPC call {} failed: {}", method, error);
+    }
+    response["result"].clone()
+}
+
+/// Sends `transaction` and returns its hash.
+pub fn send_transaction(url: &str, transaction: &Value) -> Value {
+    call(url, "eth_sendTransaction", json!([transaction]))
+}
+
+/// The input data of the transaction with hash `tx`.
+pub fn transaction_input(url: &str, tx: &str) -> Vec<u8> {
+    let transaction = call(url, "eth_getTransactionByHash", json!([tx]));
+    let input = transaction["input"]
+        .as_str()
+        .unwrap_or_else(|| panic!("Transaction {} not found", tx));
+    hex::decode(input.trim_start_matches("0x")).expect("Invalid hex in transaction input")
+}
+
+/// POSTs `body` to `url`, failing on transport errors and non-success statuses.
+pub fn post(url: &str, body: &Value) -> Result<(), String> {
+    ureq::post(url)
+        .send_json(body)
+        .map(|_| ())
+        .map_err(|error| error.to_string())
+}
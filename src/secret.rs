@@ -0,0 +1,105 @@
+//! Range checks on secret keys given on the command line.
+//!
+//! A secret key is a scalar and only meaningful in `[1, r)`. Encodings at or above the group
+//! order `r` are rejected unless `--reduce` is given, in which case they are reduced modulo `r`
+//! and the key actually used is reported, since it no longer matches what was passed in.
+//!
+//! `check-keypair` catches the other common mistake, a secret stored next to the wrong public
+//! key, and tells apart a key that was merely derived in the other group or from the secret's
+//! bytes in the opposite order.
+
+use std::fs;
+use std::path::Path;
+
+use serde_json::{json, Value};
+use zeroize::Zeroizing;
+
+#[cfg(feature = "age")]
+use crate::age_file;
+use crate::backend::Backend;
+use crate::config::Config;
+use crate::keypair::{self, SecretBytes};
+use crate::point::Group;
+
+fn is_zero(secret: &[u8]) -> bool {
+    secret.iter().all(|&byte| byte == 0)
+}
+
+/// Whether `secret` is a usable secret key for `backend`.
+pub fn check(backend: &dyn Backend, secret: &[u8]) -> Value {
+    let reduced = backend.reduce_secret(secret);
+    let non_zero = !is_zero(secret);
+    let below_order = reduced == secret;
+    let mut result = json!({
+        "valid": non_zero && below_order,
+        "nonZero": non_zero,
+        "belowOrder": below_order,
+    });
+    if !below_order {
+        result["reduced"] = json!(hex::encode(reduced));
+    }
+    result
+}
+
+/// Reads a hex secret key from a file, decrypting it first if it is an age file.
+#[cfg_attr(not(feature = "age"), allow(unused_variables))]
+pub fn read_file(path: &Path, config: &Config) -> Zeroizing<String> {
+    let contents = Zeroizing::new(fs::read(path).expect("Failed to read secret key file"));
+    #[cfg(feature = "age")]
+    let contents = if age_file::is_age(&contents) {
+        Zeroizing::new(age_file::decrypt(&contents, config))
+    } else {
+        contents
+    };
+    #[cfg(not(feature = "age"))]
+    assert!(
+        !contents.starts_with(b"age-encryption.org/") && !contents.starts_with(b"-----BEGIN AGE"),
+        "Secret key file is age-encrypted, which needs the age feature"
+    );
+    let secret = std::str::from_utf8(&contents).expect("Secret key file is not hex");
+    Zeroizing::new(secret.trim().to_string())
+}
+
+/// Decodes a hex secret key, rejecting it if it is out of range, or reducing it modulo the group
+/// order if `reduce` is set.
+pub fn load(backend: &dyn Backend, secret: &str, reduce: bool) -> SecretBytes {
+    let secret = SecretBytes::new(keypair::decode_secret(secret));
+    assert!(!is_zero(&secret), "Secret key must not be zero");
+    let reduced = SecretBytes::new(backend.reduce_secret(&secret));
+    if *reduced == *secret {
+        return secret;
+    }
+    assert!(
+        reduce,
+        "Secret key is not below the group order; pass --reduce to use it modulo the order"
+    );
+    assert!(!is_zero(&reduced), "Secret key is zero modulo the group order");
+    eprintln!(
+        "Warning: secret key reduced modulo the group order; the effective key is {}",
+        *Zeroizing::new(hex::encode(&*reduced))
+    );
+    reduced
+}
+
+/// Whether `public_key` belongs to `secret`, and if it only does in the other group or with the
+/// secret's bytes reversed, which of those.
+pub fn check_keypair(backend: &dyn Backend, secret: &[u8], public_key: &[u8]) -> Value {
+    let expected = backend.public_key_from_secret(secret);
+    let reversed: Vec<u8> = secret.iter().rev().copied().collect();
+    let mut orders = vec![("as-given", secret.to_vec())];
+    // the reversed bytes are only a candidate if they form a usable key themselves
+    if !is_zero(&reversed) && backend.reduce_secret(&reversed) == reversed {
+        orders.push(("reversed", reversed));
+    }
+    let found = orders.iter().find_map(|(order, secret)| {
+        [(Group::G1, "g1"), (Group::G2, "g2")]
+            .into_iter()
+            .find(|&(group, _)| backend.public_key_in_group(secret, group) == public_key)
+            .map(|(_, group)| json!({ "group": group, "secretByteOrder": order }))
+    });
+    json!({
+        "matches": public_key == expected,
+        "expectedPublicKey": hex::encode(expected),
+        "matchedAs": found,
+    })
+}
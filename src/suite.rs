@@ -0,0 +1,194 @@
+//! Named ciphersuites that pin the curve, signature layout, scheme and DST of another
+//! ecosystem, so keys and signatures made here interoperate with its libraries byte-for-byte.
+//!
+//! A suite replaces `--backend` for the commands that take raw keys and signatures. The
+//! built-in suites can be complemented with BLS12-381 suites of one's own under `suites` in the
+//! config file; built-in names always win.
+
+use ::blst::min_pk::SecretKey;
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::backend::blst::{BlstBackend, BlstMinPkBackend};
+use crate::backend::Backend;
+use crate::config::Config;
+use crate::point::Group;
+
+const CHIA_DST: &str = "BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_AUG_";
+const ETH2_DST: &str = "BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+const FILECOIN_DST: &str = "BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// CIDv1 header for dag-cbor content under a 32-byte blake2b-256 multihash.
+const FILECOIN_CID_PREFIX: [u8; 6] = [0x01, 0x71, 0xa0, 0xe4, 0x02, 0x20];
+
+pub const BUILTIN: [&str; 3] = ["chia", "eth2", "filecoin"];
+
+/// Which group holds the public keys.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Layout {
+    /// Public keys in 𝔾₁ (48 bytes), signatures in 𝔾₂ (96 bytes)
+    MinPk,
+    /// Signatures in 𝔾₁ (48 bytes), public keys in 𝔾₂ (96 bytes)
+    MinSig,
+}
+
+/// The three schemes of the IETF BLS signature draft. Proof-of-possession signing is the basic
+/// scheme under a `_POP_` DST; only augmentation changes what is hashed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scheme {
+    Basic,
+    Aug,
+    Pop,
+}
+
+/// A BLS12-381 ciphersuite, as written in the config file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SuiteDefinition {
+    pub layout: Layout,
+    pub scheme: Scheme,
+    pub dst: String,
+}
+
+impl SuiteDefinition {
+    fn instantiate(&self, key_gen: fn(&[u8]) -> SecretKey) -> Box<dyn Backend> {
+        let dst = self.dst.as_bytes().to_vec();
+        let augment = self.scheme == Scheme::Aug;
+        match self.layout {
+            Layout::MinPk => Box::new(BlstMinPkBackend::new(dst, augment, key_gen)),
+            Layout::MinSig => Box::new(BlstBackend::new(dst, augment)),
+        }
+    }
+}
+
+fn builtin(name: &str) -> Option<SuiteDefinition> {
+    let (scheme, dst) = match name {
+        "chia" => (Scheme::Aug, CHIA_DST),
+        "eth2" => (Scheme::Pop, ETH2_DST),
+        "filecoin" => (Scheme::Basic, FILECOIN_DST),
+        _ => return None,
+    };
+    Some(SuiteDefinition {
+        layout: Layout::MinPk,
+        scheme,
+        dst: dst.to_string(),
+    })
+}
+
+fn definition(name: &str, config: &Config) -> SuiteDefinition {
+    builtin(name)
+        .or_else(|| config.suites.get(name).cloned())
+        .unwrap_or_else(|| panic!("Unknown suite {}", name))
+}
+
+/// Chia derives keys with the draft-03 KeyGen (unhashed `BLS-SIG-KEYGEN-SALT-`, no key info).
+fn chia_key_gen(ikm: &[u8]) -> SecretKey {
+    SecretKey::key_gen_v3(ikm, &[]).expect("Key generation failed")
+}
+
+fn default_key_gen(ikm: &[u8]) -> SecretKey {
+    SecretKey::key_gen(ikm, &[]).expect("Key generation failed")
+}
+
+/// Filecoin's conventions on top of the basic min-pk scheme: secret keys are stored
+/// little-endian, and what gets signed is the CID of the serialized message rather than the
+/// message itself.
+struct FilecoinBackend(Box<dyn Backend>);
+
+impl FilecoinBackend {
+    fn cid(message: &[u8]) -> Vec<u8> {
+        let digest = Blake2b::<U32>::digest(message);
+        [&FILECOIN_CID_PREFIX[..], &digest[..]].concat()
+    }
+
+    /// Converts between Filecoin's little-endian secrets and blst's big-endian ones.
+    fn flip(secret: &[u8]) -> Vec<u8> {
+        secret.iter().rev().copied().collect()
+    }
+}
+
+impl Backend for FilecoinBackend {
+    fn generate_keys(&self) -> (Vec<u8>, Vec<u8>) {
+        let (secret_key, public_key) = self.0.generate_keys();
+        (Self::flip(&secret_key), public_key)
+    }
+
+    fn public_key_from_secret(&self, secret: &[u8]) -> Vec<u8> {
+        self.0.public_key_from_secret(&Self::flip(secret))
+    }
+
+    fn public_key_in_group(&self, secret: &[u8], group: Group) -> Vec<u8> {
+        self.0.public_key_in_group(&Self::flip(secret), group)
+    }
+
+    fn reduce_secret(&self, secret: &[u8]) -> Vec<u8> {
+        Self::flip(&self.0.reduce_secret(&Self::flip(secret)))
+    }
+
+    fn sign(&self, secret: &[u8], message: &[u8]) -> Vec<u8> {
+        self.0.sign(&Self::flip(secret), &Self::cid(message))
+    }
+
+    fn aggregate_public_keys(&self, public_keys: &[Vec<u8>]) -> Vec<u8> {
+        self.0.aggregate_public_keys(public_keys)
+    }
+
+    fn aggregate_signatures(&self, signatures: &[Vec<u8>]) -> Vec<u8> {
+        self.0.aggregate_signatures(signatures)
+    }
+
+    fn verify(&self, signature: &[u8], public_key: &[u8], message: &[u8]) -> bool {
+        self.0.verify(signature, public_key, &Self::cid(message))
+    }
+}
+
+/// The backend implementing the suite called `name`.
+pub fn instantiate(name: &str, config: &Config) -> Box<dyn Backend> {
+    let definition = definition(name, config);
+    match name {
+        "chia" => definition.instantiate(chia_key_gen),
+        "filecoin" => Box::new(FilecoinBackend(definition.instantiate(default_key_gen))),
+        _ => definition.instantiate(default_key_gen),
+    }
+}
+
+/// The name of the scheme the suite called `name` signs under.
+pub fn scheme(name: &str, config: &Config) -> &'static str {
+    match definition(name, config).scheme {
+        Scheme::Basic => "basic",
+        Scheme::Aug => "aug",
+        Scheme::Pop => "pop",
+    }
+}
+
+/// The DST of the suite called `name`.
+pub fn dst(name: &str, config: &Config) -> String {
+    definition(name, config).dst
+}
+
+/// Every suite `--suite` accepts, built-in ones first.
+pub fn list(config: &Config) -> Value {
+    let configured = config
+        .suites
+        .keys()
+        .filter(|name| !BUILTIN.contains(&name.as_str()));
+    let suites: Vec<Value> = BUILTIN
+        .iter()
+        .map(|name| (name.to_string(), true))
+        .chain(configured.map(|name| (name.clone(), false)))
+        .map(|(name, builtin)| {
+            let definition = definition(&name, config);
+            json!({
+                "name": name,
+                "builtin": builtin,
+                "layout": definition.layout,
+                "scheme": definition.scheme,
+                "dst": definition.dst,
+            })
+        })
+        .collect();
+    json!(suites)
+}
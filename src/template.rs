@@ -0,0 +1,157 @@
+//! Structured messages built from named fields.
+//!
+//! A template, defined under `templates` in the config file, lists typed fields in order:
+//!
+//! ```json
+//! { "templates": { "epoch-root": { "fields": [
+//!     { "name": "epoch", "type": "uint64" },
+//!     { "name": "root", "type": "bytes32" }
+//! ] } } }
+//! ```
+//!
+//! `sign --template epoch-root --field epoch=42 --field root=0xabc…` then signs
+//!
+//! ```text
+//! u32(len(name)) ‖ name ‖ field₁ ‖ … ‖ fieldₙ
+//! ```
+//!
+//! with the fields in template order, whatever order they were given in. `uintN` is N/8 bytes
+//! big-endian (decimal or `0x` hex input), `bool` one byte `0x00`/`0x01`, `address` 20 bytes and
+//! `bytes32` 32 bytes (hex input of exactly that length), while `bytes` (hex input) and `string`
+//! (UTF-8) are a `u32` length followed by their contents. All lengths are big-endian. Leading
+//! with the template name keeps two templates with the same field types from signing the same
+//! bytes.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::config::Config;
+
+/// Types a template field can have.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    Uint8,
+    Uint16,
+    Uint32,
+    Uint64,
+    Uint128,
+    Uint256,
+    Bool,
+    Address,
+    Bytes32,
+    Bytes,
+    String,
+}
+
+#[derive(Deserialize)]
+pub struct FieldDefinition {
+    name: String,
+    #[serde(rename = "type")]
+    kind: FieldType,
+}
+
+#[derive(Deserialize)]
+pub struct Template {
+    fields: Vec<FieldDefinition>,
+}
+
+fn length_prefixed(bytes: &[u8]) -> Vec<u8> {
+    let len = u32::try_from(bytes.len()).expect("Template fields hold at most 4 GiB");
+    [len.to_be_bytes().as_slice(), bytes].concat()
+}
+
+fn decode_hex(name: &str, value: &str) -> Vec<u8> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    hex::decode(digits).unwrap_or_else(|_| panic!("Invalid hex in field {}", name))
+}
+
+/// `value` (decimal or `0x` hex) as a `width`-byte big-endian unsigned integer; `name` labels
+/// errors.
+pub fn uint(name: &str, value: &str, width: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; width];
+    match value.strip_prefix("0x") {
+        Some(_) => {
+            let digits = decode_hex(name, value);
+            let start = digits.iter().position(|&byte| byte != 0).unwrap_or(digits.len());
+            let significant = &digits[start..];
+            assert!(significant.len() <= width, "Field {} does not fit {} bytes", name, width);
+            bytes[width - significant.len()..].copy_from_slice(significant);
+        }
+        None => {
+            for digit in value.chars() {
+                let digit = digit
+                    .to_digit(10)
+                    .unwrap_or_else(|| panic!("Field {} is not a number", name));
+                // bytes = bytes * 10 + digit, least significant byte first
+                let mut carry = digit;
+                for byte in bytes.iter_mut().rev() {
+                    let product = *byte as u32 * 10 + carry;
+                    *byte = product as u8;
+                    carry = product >> 8;
+                }
+                assert_eq!(carry, 0, "Field {} does not fit {} bytes", name, width);
+            }
+        }
+    }
+    bytes
+}
+
+fn fixed(name: &str, value: &str, width: usize) -> Vec<u8> {
+    let bytes = decode_hex(name, value);
+    assert_eq!(bytes.len(), width, "Field {} must be {} bytes", name, width);
+    bytes
+}
+
+impl FieldType {
+    fn encode(self, name: &str, value: &str) -> Vec<u8> {
+        match self {
+            FieldType::Uint8 => uint(name, value, 1),
+            FieldType::Uint16 => uint(name, value, 2),
+            FieldType::Uint32 => uint(name, value, 4),
+            FieldType::Uint64 => uint(name, value, 8),
+            FieldType::Uint128 => uint(name, value, 16),
+            FieldType::Uint256 => uint(name, value, 32),
+            FieldType::Bool => match value {
+                "true" => vec![1],
+                "false" => vec![0],
+                _ => panic!("Field {} must be true or false", name),
+            },
+            FieldType::Address => fixed(name, value, 20),
+            FieldType::Bytes32 => fixed(name, value, 32),
+            FieldType::Bytes => length_prefixed(&decode_hex(name, value)),
+            FieldType::String => length_prefixed(value.as_bytes()),
+        }
+    }
+}
+
+/// The message `template` from `config` lays out for `fields`, given as `name=value`.
+pub fn encode(config: &Config, template: &str, fields: &[String]) -> Vec<u8> {
+    let definition = config
+        .templates
+        .get(template)
+        .unwrap_or_else(|| panic!("No template named {} in the config file", template));
+    let mut values = BTreeMap::new();
+    for field in fields {
+        let (name, value) = field
+            .split_once('=')
+            .unwrap_or_else(|| panic!("Field {} is not of the form name=value", field));
+        assert!(
+            values.insert(name, value).is_none(),
+            "Field {} is given more than once",
+            name
+        );
+    }
+    let mut message = length_prefixed(template.as_bytes());
+    for field in &definition.fields {
+        let value = values
+            .remove(field.name.as_str())
+            .unwrap_or_else(|| panic!("Template {} needs field {}", template, field.name));
+        message.extend(field.kind.encode(&field.name, value));
+    }
+    if let Some(name) = values.keys().next() {
+        panic!("Template {} has no field {}", template, name);
+    }
+    message
+}
@@ -0,0 +1,101 @@
+//! Thread count and CPU pinning for the parallel code paths.
+//!
+//! `--threads` sets how many threads vanity search, aggregation and `batch-verify` use, one per
+//! available CPU by default. `--cpu-affinity` pins the whole process, and so every thread it
+//! starts, to a list of CPUs such as `0-3,6`, which keeps the tool off the cores that node
+//! software sharing the machine relies on; the default thread count then follows the list.
+
+use std::thread;
+
+/// Inputs below this size are not worth splitting across threads.
+const PARALLEL_THRESHOLD: usize = 256;
+
+/// `requested` threads, or one per CPU the process may run on.
+pub fn count(requested: Option<usize>) -> usize {
+    let threads = requested
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |threads| threads.get()));
+    assert!(threads > 0, "At least one thread is needed");
+    threads
+}
+
+/// Parses a CPU list of numbers and inclusive ranges, as in `0-3,6`.
+fn parse_cpu_list(list: &str) -> Vec<usize> {
+    let parse = |cpu: &str| cpu.trim().parse::<usize>().expect("Invalid CPU number in list");
+    let cpus: Vec<usize> = list
+        .split(',')
+        .flat_map(|part| match part.split_once('-') {
+            Some((first, last)) => (parse(first)..=parse(last)).collect::<Vec<_>>(),
+            None => vec![parse(part)],
+        })
+        .collect();
+    assert!(!cpus.is_empty(), "CPU list is empty");
+    cpus
+}
+
+/// Restricts the process, and every thread it starts from now on, to the CPUs in `list`.
+#[cfg(target_os = "linux")]
+pub fn pin(list: &str) {
+    let cpus = parse_cpu_list(list);
+    // SAFETY: cpu_set_t is a plain bit set for which all zeros is the empty set, and
+    // CPU_SET stays within it for CPU numbers below CPU_SETSIZE, which is checked first
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for cpu in cpus {
+            assert!(
+                cpu < libc::CPU_SETSIZE as usize,
+                "CPU {} is beyond the largest supported CPU number",
+                cpu
+            );
+            libc::CPU_SET(cpu, &mut set);
+        }
+        let result = libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set);
+        assert!(
+            result == 0,
+            "Failed to set CPU affinity: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin(list: &str) {
+    parse_cpu_list(list);
+    panic!("CPU affinity is only supported on Linux");
+}
+
+/// Applies `f` to every item, splitting `items` into one run per thread.
+fn split<T: Sync, R: Send>(threads: usize, items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let chunk_len = items.len().div_ceil(threads);
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_len)
+            .map(|chunk| scope.spawn(|| chunk.iter().map(&f).collect::<Vec<R>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("Worker thread panicked"))
+            .collect()
+    })
+}
+
+/// Applies `f` to every item on up to `threads` threads, keeping the order of `items`.
+pub fn map<T: Sync, R: Send>(threads: usize, items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    if threads == 1 || items.len() < PARALLEL_THRESHOLD {
+        return items.iter().map(f).collect();
+    }
+    split(threads, items, f)
+}
+
+/// Aggregates `items` with `aggregate` on up to `threads` threads: each thread aggregates a
+/// share of them, and the shares are aggregated in turn.
+pub fn aggregate(
+    threads: usize,
+    items: &[Vec<u8>],
+    aggregate: impl Fn(&[Vec<u8>]) -> Vec<u8> + Sync,
+) -> Vec<u8> {
+    if threads == 1 || items.len() < PARALLEL_THRESHOLD {
+        return aggregate(items);
+    }
+    let shares: Vec<&[Vec<u8>]> = items.chunks(items.len().div_ceil(threads)).collect();
+    aggregate(&split(threads, &shares, |share| aggregate(share)))
+}
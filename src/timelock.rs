@@ -0,0 +1,229 @@
+//! Time-lock encryption toward a drand-style threshold beacon.
+//!
+//! A beacon's signature on round `n` is the identity-based private key for the identity
+//! `sha256(n)` under the beacon's group key, so anything encrypted to that identity becomes
+//! decryptable once round `n` is published. The IBE layer is the Boneh–Franklin variant with
+//! the Fujisaki–Okamoto transform used by drand's tlock, byte for byte: a random 16-byte file
+//! key is encrypted as `U ‖ V ‖ W`, and the group key's size decides the layout (a 𝔾₁ key with
+//! 𝔾₂ round signatures, or a 𝔾₂ key with RFC 9380 𝔾₁ signatures, as on quicknet).
+//!
+//! The data itself is sealed with ChaCha20-Poly1305 under `sha256(PAYLOAD_LABEL ‖ file key)`,
+//! so arbitrary-length messages can be locked. The ciphertext is `U ‖ V ‖ W ‖ payload`; its
+//! first part is exactly what tlock produces for the file key.
+
+use ark_bls12_381::{g1, g2, Bls12_381, Fr, G1Affine, G2Affine};
+use ark_ec::hashing::curve_maps::wb::WBMap;
+use ark_ec::hashing::map_to_curve_hasher::MapToCurveBasedHasher;
+use ark_ec::hashing::HashToCurve;
+use ark_ec::pairing::{Pairing, PairingOutput};
+use ark_ec::short_weierstrass::Projective;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::field_hashers::DefaultFieldHasher;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::ChaCha20Poly1305;
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+const G1_DST: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_";
+const G2_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+const PAYLOAD_LABEL: &[u8] = b"bls-tools-timelock-payload";
+
+const G1_LEN: usize = 48;
+const G2_LEN: usize = 96;
+const KEY_LEN: usize = 16;
+
+/// Which group the beacon's public key lives in; round signatures live in the other one.
+#[derive(Copy, Clone)]
+enum Layout {
+    G1Key,
+    G2Key,
+}
+
+impl Layout {
+    fn from_group_key(group_key: &[u8]) -> Self {
+        match group_key.len() {
+            G1_LEN => Layout::G1Key,
+            G2_LEN => Layout::G2Key,
+            _ => panic!("Group key must be {} or {} bytes", G1_LEN, G2_LEN),
+        }
+    }
+
+    fn from_signature(signature: &[u8]) -> Self {
+        match signature.len() {
+            G2_LEN => Layout::G1Key,
+            G1_LEN => Layout::G2Key,
+            _ => panic!("Round signature must be {} or {} bytes", G1_LEN, G2_LEN),
+        }
+    }
+
+    /// Size of `U`, which lives in the same group as the key.
+    fn u_len(self) -> usize {
+        match self {
+            Layout::G1Key => G1_LEN,
+            Layout::G2Key => G2_LEN,
+        }
+    }
+}
+
+fn deserialize<T: CanonicalDeserialize>(bytes: &[u8], what: &str) -> T {
+    T::deserialize_compressed(bytes).unwrap_or_else(|_| panic!("Invalid {}", what))
+}
+
+fn serialize(value: &impl CanonicalSerialize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    value
+        .serialize_compressed(&mut bytes)
+        .expect("Serialization failed");
+    bytes
+}
+
+fn round_identity(round: u64) -> [u8; 32] {
+    Sha256::digest(round.to_be_bytes()).into()
+}
+
+type Hasher<C> = MapToCurveBasedHasher<Projective<C>, DefaultFieldHasher<Sha256, 128>, WBMap<C>>;
+
+fn hash_to_g1(identity: &[u8]) -> G1Affine {
+    Hasher::<g1::Config>::new(G1_DST)
+        .and_then(|hasher| hasher.hash(identity))
+        .expect("Hashing failed")
+}
+
+fn hash_to_g2(identity: &[u8]) -> G2Affine {
+    Hasher::<g2::Config>::new(G2_DST)
+        .and_then(|hasher| hasher.hash(identity))
+        .expect("Hashing failed")
+}
+
+fn xor(a: &[u8], b: &[u8]) -> [u8; KEY_LEN] {
+    let mut out = [0u8; KEY_LEN];
+    for (o, (x, y)) in out.iter_mut().zip(a.iter().zip(b)) {
+        *o = x ^ y;
+    }
+    out
+}
+
+/// H₂: 𝔾_T → {0,1}¹²⁸, over the big-endian encoding of the target group element.
+fn h2(gt: &PairingOutput<Bls12_381>) -> [u8; 32] {
+    let mut bytes = serialize(gt);
+    bytes.reverse();
+    Sha256::new()
+        .chain_update(b"IBE-H2")
+        .chain_update(bytes)
+        .finalize()
+        .into()
+}
+
+/// H₃: derives the encryption randomness from σ and the file key by rejection sampling.
+fn h3(sigma: &[u8], key: &[u8]) -> Fr {
+    let seed = Sha256::new()
+        .chain_update(b"IBE-H3")
+        .chain_update(sigma)
+        .chain_update(key)
+        .finalize();
+    for i in 1..u16::MAX {
+        let mut h: [u8; 32] = Sha256::new()
+            .chain_update(i.to_le_bytes())
+            .chain_update(seed)
+            .finalize()
+            .into();
+        // the scalar field is 255 bits wide
+        h[0] >>= 1;
+        h.reverse();
+        if let Ok(r) = Fr::deserialize_compressed(h.as_slice()) {
+            return r;
+        }
+    }
+    unreachable!("rejection sampling ran out of counters")
+}
+
+/// H₄: masks the file key with σ.
+fn h4(sigma: &[u8]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(b"IBE-H4")
+        .chain_update(sigma)
+        .finalize()
+        .into()
+}
+
+/// `r·P` for the generator of the key's group.
+fn commit(layout: Layout, r: Fr) -> Vec<u8> {
+    match layout {
+        Layout::G1Key => serialize(&(G1Affine::generator() * r).into_affine()),
+        Layout::G2Key => serialize(&(G2Affine::generator() * r).into_affine()),
+    }
+}
+
+fn payload_cipher(file_key: &[u8]) -> ChaCha20Poly1305 {
+    let key = Sha256::new()
+        .chain_update(PAYLOAD_LABEL)
+        .chain_update(file_key)
+        .finalize();
+    ChaCha20Poly1305::new(&key)
+}
+
+/// Encrypts `message` so that it opens with the beacon's signature on `round`.
+pub fn encrypt(group_key: &[u8], round: u64, message: &[u8]) -> Vec<u8> {
+    let layout = Layout::from_group_key(group_key);
+    let identity = round_identity(round);
+    let gid = match layout {
+        Layout::G1Key => Bls12_381::pairing(
+            deserialize::<G1Affine>(group_key, "group key"),
+            hash_to_g2(&identity),
+        ),
+        Layout::G2Key => Bls12_381::pairing(
+            hash_to_g1(&identity),
+            deserialize::<G2Affine>(group_key, "group key"),
+        ),
+    };
+
+    let mut file_key = [0u8; KEY_LEN];
+    let mut sigma = [0u8; KEY_LEN];
+    OsRng.fill_bytes(&mut file_key);
+    OsRng.fill_bytes(&mut sigma);
+    let r = h3(&sigma, &file_key);
+
+    let u = commit(layout, r);
+    let v = xor(&sigma, &h2(&(gid * r)));
+    let w = xor(&file_key, &h4(&sigma));
+    // every file key is fresh, so a fixed nonce never repeats under one key
+    let payload = payload_cipher(&file_key)
+        .encrypt(&Default::default(), message)
+        .expect("Payload encryption failed");
+    [u, v.to_vec(), w.to_vec(), payload].concat()
+}
+
+/// Opens a ciphertext with the beacon's signature on the round it was locked to.
+pub fn decrypt(round_signature: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    let layout = Layout::from_signature(round_signature);
+    let u_len = layout.u_len();
+    assert!(
+        ciphertext.len() >= u_len + 2 * KEY_LEN,
+        "Ciphertext is too short"
+    );
+    let (u, rest) = ciphertext.split_at(u_len);
+    let (v, rest) = rest.split_at(KEY_LEN);
+    let (w, payload) = rest.split_at(KEY_LEN);
+
+    let gid = match layout {
+        Layout::G1Key => Bls12_381::pairing(
+            deserialize::<G1Affine>(u, "ciphertext"),
+            deserialize::<G2Affine>(round_signature, "round signature"),
+        ),
+        Layout::G2Key => Bls12_381::pairing(
+            deserialize::<G1Affine>(round_signature, "round signature"),
+            deserialize::<G2Affine>(u, "ciphertext"),
+        ),
+    };
+    let sigma = xor(v, &h2(&gid));
+    let file_key = xor(w, &h4(&sigma));
+    assert!(
+        commit(layout, h3(&sigma, &file_key)) == u,
+        "Decryption failed: the signature is not for the round this was locked to"
+    );
+
+    payload_cipher(&file_key)
+        .decrypt(&Default::default(), payload)
+        .expect("Payload failed authentication")
+}
@@ -0,0 +1,150 @@
+//! dudect-style timing leakage detection for signing.
+//!
+//! Each experiment interleaves two input classes at random — a fixed, low-Hamming-weight value
+//! and fresh random values — times every signing call, and compares the two timing
+//! distributions with Welch's t-test. As in dudect, the test is repeated on measurements
+//! cropped at several upper percentiles to suppress noise from interrupts and other outliers,
+//! and the largest |t| is reported. Values above [`T_THRESHOLD`] suggest that signing time
+//! depends on the input class, e.g. because a backend introduced a data-dependent branch.
+
+use std::time::Instant;
+
+use rand_core::{OsRng, RngCore};
+use serde_json::{json, Value};
+
+use crate::backend::Backend;
+
+/// |t| above which dudect considers the two classes distinguishable.
+pub const T_THRESHOLD: f64 = 4.5;
+
+/// Upper percentiles at which measurements are cropped before re-running the t-test.
+const CROP_PERCENTILES: [f64; 6] = [1.0, 0.99, 0.95, 0.9, 0.75, 0.5];
+
+const MESSAGE_LEN: usize = 32;
+
+#[derive(Default)]
+struct Welford {
+    n: f64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Welford {
+    fn push(&mut self, x: f64) {
+        self.n += 1.0;
+        let delta = x - self.mean;
+        self.mean += delta / self.n;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    fn variance(&self) -> f64 {
+        self.m2 / (self.n - 1.0)
+    }
+}
+
+fn welch_t(fixed: &Welford, random: &Welford) -> f64 {
+    let se = (fixed.variance() / fixed.n + random.variance() / random.n).sqrt();
+    if se == 0.0 {
+        0.0
+    } else {
+        (fixed.mean - random.mean) / se
+    }
+}
+
+/// Times `op` on `samples` inputs of randomly chosen classes, returning `(is_fixed, nanos)`
+/// pairs. Inputs are produced by `input` outside of the timed region.
+fn measure<T>(
+    samples: usize,
+    mut input: impl FnMut(bool) -> T,
+    mut op: impl FnMut(&T),
+) -> Vec<(bool, f64)> {
+    (0..samples)
+        .map(|_| {
+            let is_fixed = OsRng.next_u32() & 1 == 0;
+            let value = input(is_fixed);
+            let start = Instant::now();
+            op(&value);
+            (is_fixed, start.elapsed().as_nanos() as f64)
+        })
+        .collect()
+}
+
+fn max_t(measurements: &[(bool, f64)]) -> f64 {
+    let mut sorted: Vec<f64> = measurements.iter().map(|&(_, t)| t).collect();
+    sorted.sort_by(f64::total_cmp);
+
+    CROP_PERCENTILES
+        .iter()
+        .map(|&percentile| {
+            let index = ((sorted.len() as f64 * percentile) as usize).min(sorted.len() - 1);
+            let cutoff = sorted[index];
+            let (mut fixed, mut random) = (Welford::default(), Welford::default());
+            for &(is_fixed, t) in measurements.iter().filter(|&&(_, t)| t <= cutoff) {
+                if is_fixed {
+                    fixed.push(t)
+                } else {
+                    random.push(t)
+                }
+            }
+            welch_t(&fixed, &random).abs()
+        })
+        .filter(|t| t.is_finite())
+        .fold(0.0, f64::max)
+}
+
+fn summary(name: &str, measurements: &[(bool, f64)]) -> (Value, bool) {
+    let t = max_t(measurements);
+    let result = json!({
+        "experiment": name,
+        "samples": measurements.len(),
+        "maxT": t,
+        "leakDetected": t > T_THRESHOLD,
+    });
+    (result, t > T_THRESHOLD)
+}
+
+/// Compares signing times across secret-key classes and message classes.
+pub fn run(backend: &dyn Backend, samples: usize) -> Value {
+    let (random_secret, _) = backend.generate_keys();
+    let mut fixed_secret = vec![0u8; random_secret.len()];
+    *fixed_secret.last_mut().expect("Secret key must not be empty") = 1;
+
+    let mut message = [0u8; MESSAGE_LEN];
+    OsRng.fill_bytes(&mut message);
+
+    let secret_classes = measure(
+        samples,
+        |is_fixed| {
+            if is_fixed {
+                fixed_secret.clone()
+            } else {
+                backend.generate_keys().0
+            }
+        },
+        |secret| {
+            backend.sign(secret, &message);
+        },
+    );
+
+    let message_classes = measure(
+        samples,
+        |is_fixed| {
+            let mut msg = [0u8; MESSAGE_LEN];
+            if !is_fixed {
+                OsRng.fill_bytes(&mut msg);
+            }
+            msg
+        },
+        |msg| {
+            backend.sign(&random_secret, msg);
+        },
+    );
+
+    let (secret_result, secret_leak) = summary("secret-key", &secret_classes);
+    let (message_result, message_leak) = summary("message", &message_classes);
+    json!({
+        "threshold": T_THRESHOLD,
+        "experiments": [secret_result, message_result],
+        "leakDetected": secret_leak || message_leak,
+    })
+}
@@ -0,0 +1,135 @@
+//! Vanity key search: generate key pairs until one's public key starts with a chosen hex prefix.
+//!
+//! Every extra hex digit multiplies the expected work by 16. Not every prefix is reachable:
+//! compressed BLS12-381 keys start with flag bits (so with `8`–`b`), and BN254 coordinates
+//! are below the field modulus (so start with `0`–`3`, and `30` is as far as it goes). A prefix
+//! no key of the curve can start with is refused up front rather than searched for forever.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::backend::{Backend, BackendKind};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The BN254 base field modulus minus one, the largest leading coordinate of a key.
+const BN254_MAX: &str = "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd46";
+
+/// The BLS12-381 base field modulus minus one, the largest coordinate under the flag bits.
+const BLS12_381_MAX: &str = "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf\
+                             6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaaa";
+
+/// Whether a key of `curve` can start with `prefix`, that is whether the smallest encoding that
+/// does is still below the largest one. BLS12-377 keys are little-endian, so start with anything.
+fn reachable(curve: &str, prefix: &str) -> bool {
+    let (prefix, max) = match curve {
+        "bn254" => (prefix.to_string(), BN254_MAX),
+        "bls12-381" => {
+            // The compression flag is set and the infinity flag clear; the sign flag is free.
+            let flags = u8::from_str_radix(&prefix[..1], 16).expect("Prefix is hex");
+            if flags & 0xc != 0x8 {
+                return false;
+            }
+            (format!("{:x}{}", flags & 0x1, &prefix[1..]), BLS12_381_MAX)
+        }
+        _ => return true,
+    };
+    let len = prefix.len().min(max.len());
+    prefix[..len] <= max[..len]
+}
+
+/// Searches on `threads` threads for a key pair whose hex public key starts with `prefix`,
+/// reporting progress on stderr. `backend` is the one `kind` or, if given, `suite` selects; the
+/// suite decides the curve, since every suite is on BLS12-381 whatever `--backend` says.
+pub fn search(
+    backend: &dyn Backend,
+    kind: BackendKind,
+    suite: Option<&str>,
+    prefix: &str,
+    threads: usize,
+) -> (Vec<u8>, Vec<u8>) {
+    let curve = if suite.is_some() { "bls12-381" } else { kind.curve() };
+    let prefix = prefix.to_lowercase();
+    assert!(
+        !prefix.is_empty() && prefix.chars().all(|c| c.is_ascii_hexdigit()),
+        "Vanity prefix must be hex digits"
+    );
+    assert!(
+        reachable(curve, &prefix),
+        "No {} public key starts with {}",
+        curve,
+        prefix
+    );
+    assert!(threads > 0, "At least one thread is needed");
+    let expected = 16f64.powi(prefix.len() as i32);
+    let attempts = AtomicU64::new(0);
+    let done = AtomicBool::new(false);
+    let found = Mutex::new(None);
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    let (secret_key, public_key) = backend.generate_keys();
+                    attempts.fetch_add(1, Ordering::Relaxed);
+                    if hex::encode(&public_key).starts_with(&prefix) {
+                        done.store(true, Ordering::Relaxed);
+                        found
+                            .lock()
+                            .expect("Vanity search thread panicked")
+                            .get_or_insert((secret_key, public_key));
+                    }
+                }
+            });
+        }
+
+        let start = Instant::now();
+        let mut last_report = start;
+        while !done.load(Ordering::Relaxed) {
+            thread::sleep(POLL_INTERVAL);
+            if last_report.elapsed() < PROGRESS_INTERVAL {
+                continue;
+            }
+            last_report = Instant::now();
+            let tried = attempts.load(Ordering::Relaxed);
+            let rate = tried as f64 / start.elapsed().as_secs_f64();
+            eprintln!(
+                "Tried {} keys ({:.0}/s); about {:.0}s expected for a {}-digit prefix",
+                tried,
+                rate,
+                expected / rate,
+                prefix.len()
+            );
+        }
+    });
+
+    found
+        .into_inner()
+        .expect("Vanity search thread panicked")
+        .expect("Vanity search ended without a key")
+}
+
+#[cfg(all(test, feature = "blst"))]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::suite;
+
+    /// Under `--suite`, `--backend` stays at its sylow default while the keys are BLS12-381.
+    #[test]
+    fn searches_suite_keys_on_their_curve() {
+        let backend = suite::instantiate("chia", &Config::default());
+        let (_, public_key) = search(backend.as_ref(), BackendKind::Sylow, Some("chia"), "8", 1);
+        assert!(hex::encode(public_key).starts_with('8'));
+    }
+
+    #[test]
+    #[should_panic(expected = "No bls12-381 public key starts with 1")]
+    fn refuses_prefixes_suite_keys_cannot_start_with() {
+        let backend = suite::instantiate("chia", &Config::default());
+        search(backend.as_ref(), BackendKind::Sylow, Some("chia"), "1", 1);
+    }
+}
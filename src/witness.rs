@@ -0,0 +1,72 @@
+//! Witnesses for circuits that prove an aggregate BN254 signature verified.
+//!
+//! The export spells out every value the verifier computes, in the order it computes them:
+//! the two field elements `hash_to_field` derives from the message, the hashed point, the
+//! individual and aggregated public keys, and the two pairs whose pairing product must be the
+//! identity. Field elements are `0x`-prefixed big-endian hex strings, which circom (snarkjs)
+//! and halo2 input loaders both parse; 𝔽ₚ² elements are written `[c0, c1]`.
+
+use serde_json::{json, Value};
+use sha3::Keccak256;
+use sylow::{
+    Expander, Fp, G1Affine, G1Projective, G2Affine, G2Projective, GroupTrait, XMDExpander,
+};
+
+use crate::backend::sylow::{
+    decode_public_key, decode_signature, hash_to_g1, pairing_check, DST, SECURITY_BITS,
+};
+
+/// Number and byte length of the field elements hashed from the message, as in `sylow`.
+const HASH_TO_FIELD_COUNT: usize = 2;
+const HASH_TO_FIELD_SIZE: usize = 48;
+
+fn field(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn g1(point: &G1Projective) -> Value {
+    let bytes = G1Affine::from(point).to_be_bytes();
+    json!({ "x": field(&bytes[..32]), "y": field(&bytes[32..]) })
+}
+
+/// 𝔾₂ points are serialized `x.c1 ‖ x.c0 ‖ y.c1 ‖ y.c0`; circuits expect `c0` first.
+fn g2(point: &G2Projective) -> Value {
+    let bytes = G2Affine::from(point).to_be_bytes();
+    json!({
+        "x": [field(&bytes[32..64]), field(&bytes[..32])],
+        "y": [field(&bytes[96..]), field(&bytes[64..96])],
+    })
+}
+
+/// Every intermediate value of verifying `signature` on `message` against the sum of `public_keys`.
+pub fn export(signature: &[u8], public_keys: &[Vec<u8>], message: &[u8]) -> Value {
+    let expander = XMDExpander::<Keccak256>::new(DST, SECURITY_BITS);
+    let field_elements: [Fp; 2] = expander
+        .hash_to_field(message, HASH_TO_FIELD_COUNT, HASH_TO_FIELD_SIZE)
+        .expect("Hashing to field failed");
+    let hashed_message = hash_to_g1(message);
+
+    let keys: Vec<G2Projective> = public_keys.iter().map(|key| decode_public_key(key)).collect();
+    let aggregated_key = keys.iter().fold(G2Projective::zero(), |acc, key| acc + *key);
+    let signature = decode_signature(signature);
+    let generator = -G2Projective::generator();
+
+    json!({
+        "curve": "bn254",
+        "dst": String::from_utf8_lossy(DST),
+        "message": hex::encode(message),
+        "hashToField": field_elements
+            .iter()
+            .map(|element| field(&element.to_be_bytes()))
+            .collect::<Vec<_>>(),
+        "hashedMessage": g1(&hashed_message),
+        "publicKeys": keys.iter().map(g2).collect::<Vec<_>>(),
+        "aggregatedPublicKey": g2(&aggregated_key),
+        "signature": g1(&signature),
+        "pairingInputs": [
+            { "g1": g1(&signature), "g2": g2(&generator) },
+            { "g1": g1(&hashed_message), "g2": g2(&aggregated_key) },
+        ],
+        "valid": pairing_check(&[(signature, generator), (hashed_message, aggregated_key)]),
+    })
+}